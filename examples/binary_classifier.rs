@@ -0,0 +1,45 @@
+use micrograd::prelude::*;
+
+fn main() {
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt::init();
+
+    let data = vec![
+        (vec![2.0, 3.0, -1.0], vec![1.0]),
+        (vec![3.0, -1.0, 0.5], vec![-1.0]),
+        (vec![0.5, 1.0, 1.0], vec![-1.0]),
+        (vec![1.0, 1.0, -1.0], vec![1.0]),
+    ];
+
+    let seed = 42;
+
+    let mlp = Mlp::new_seeded(3, &[4, 4, 1], seed);
+    let optimizer = Sgd::new(mlp.parameters(), 0.05);
+
+    let mut trainer = Trainer::new(mlp, optimizer, Loss::SquaredError).config(TrainConfig {
+        verbose: cfg!(not(feature = "progress")),
+        metrics: vec![Metric::Accuracy { threshold: 0.0 }],
+        seed: Some(seed),
+        ..Default::default()
+    });
+
+    #[cfg(feature = "progress")]
+    {
+        trainer = trainer.callback(micrograd::train::progress::TrainingProgress::new());
+    }
+
+    trainer
+        .fit(&InMemoryDataset::from(data.clone()), 500)
+        .expect("should train");
+
+    let predictions: Vec<_> = data
+        .iter()
+        .map(|(x, _)| {
+            let x: Vec<_> = x.iter().map(|x| Value::new(*x, "x")).collect();
+
+            trainer.model().predict(&x).expect("should predict")[0].value()
+        })
+        .collect();
+
+    println!("Predictions: {predictions:?}");
+}
@@ -0,0 +1,96 @@
+use micrograd::{
+    lm::{contexts, CharMlp, Tokenizer},
+    optim::AdamW,
+};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+const NAMES: &[&str] = &[
+    "emma",
+    "olivia",
+    "ava",
+    "isabella",
+    "sophia",
+    "charlotte",
+    "mia",
+    "amelia",
+    "harper",
+    "evelyn",
+    "abigail",
+    "emily",
+    "elizabeth",
+    "mila",
+    "ella",
+    "avery",
+    "sofia",
+    "camila",
+    "aria",
+    "scarlett",
+    "victoria",
+    "madison",
+    "luna",
+    "grace",
+    "chloe",
+    "penelope",
+    "layla",
+    "riley",
+    "zoey",
+    "nora",
+    "lily",
+    "eleanor",
+    "hannah",
+    "lillian",
+    "addison",
+    "aubrey",
+    "ellie",
+    "stella",
+    "natalie",
+    "zoe",
+    "leah",
+    "hazel",
+    "violet",
+    "aurora",
+    "savannah",
+    "audrey",
+    "brooklyn",
+    "bella",
+    "claire",
+    "skylar",
+    "lucy",
+    "paisley",
+    "everly",
+    "anna",
+];
+
+fn main() {
+    let block_size = 3;
+    let tokenizer = Tokenizer::fit(NAMES);
+    let mut examples = contexts(&tokenizer, NAMES, block_size).expect("names should encode");
+
+    let model = CharMlp::new_seeded(tokenizer.vocab_size(), block_size, 4, 32, 42);
+    let mut optimizer = AdamW::new(model.parameters(), 0.01, 0.0);
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    for step in 0..=1000 {
+        examples.shuffle(&mut rng);
+        let loss = model
+            .train_step(&mut optimizer, &examples[..32])
+            .expect("should train");
+
+        if step % 100 == 0 {
+            println!("step {step:4}, loss {loss:.4}");
+        }
+    }
+
+    println!(
+        "loss on all names {:.4}",
+        model.loss(&examples).expect("should compute").value()
+    );
+
+    for _ in 0..10 {
+        let name = model
+            .sample(&tokenizer, 20, &mut rng)
+            .expect("should sample");
+        println!("{name}");
+    }
+}
@@ -0,0 +1,157 @@
+//! Post-hoc calibration of classifiers, whose softmax probabilities are often
+//! more confident than their accuracy warrants.
+//!
+//! [`fit_temperature`] finds the softmax temperature which minimizes the
+//! negative log-likelihood of held out labels, and
+//! [`metrics::expected_calibration_error`](crate::metrics::expected_calibration_error)
+//! measures the remaining gap between confidence and accuracy.
+
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("No samples to calibrate on")]
+    NoSamples,
+    #[error("Sample count mismatch, {0} logits and {1} labels")]
+    SampleMismatch(usize, usize),
+    #[error("Label {label} of sample {sample} is out of range for {classes} classes")]
+    UnknownLabel {
+        sample: usize,
+        label: usize,
+        classes: usize,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Range of temperatures searched by `fit_temperature`
+const MIN_TEMPERATURE: f64 = 1e-2;
+const MAX_TEMPERATURE: f64 = 1e2;
+
+/// Probabilities of a softmax over `logits` divided by `temperature`, see
+/// [`nn::softmax`](crate::nn::softmax) for the differentiable version
+pub fn softmax(logits: &[f64], temperature: f64) -> Vec<f64> {
+    let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exps: Vec<_> = logits
+        .iter()
+        .map(|l| ((l - max) / temperature).exp())
+        .collect();
+    let total: f64 = exps.iter().sum();
+
+    exps.into_iter().map(|e| e / total).collect()
+}
+
+/// Mean negative log-likelihood of the class `labels` under the softmax of
+/// `logits` at `temperature`
+pub fn negative_log_likelihood(
+    logits: &[Vec<f64>],
+    labels: &[usize],
+    temperature: f64,
+) -> Result<f64> {
+    check(logits, labels)?;
+
+    let total: f64 = logits
+        .iter()
+        .zip(labels)
+        .map(|(logits, &label)| {
+            let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let log_sum = logits
+                .iter()
+                .map(|l| ((l - max) / temperature).exp())
+                .sum::<f64>()
+                .ln();
+
+            log_sum - (logits[label] - max) / temperature
+        })
+        .sum();
+
+    Ok(total / logits.len() as f64)
+}
+
+/// Temperature scaling: the temperature between 0.01 and 100 minimizing the
+/// negative log-likelihood of `labels` on a validation set. Dividing the logits
+/// by it calibrates the probabilities without changing any prediction.
+pub fn fit_temperature(logits: &[Vec<f64>], labels: &[usize]) -> Result<f64> {
+    check(logits, labels)?;
+
+    // the likelihood is convex in 1 / temperature, so a golden section search
+    // over the log of the temperature finds the minimum
+    let nll = |log_t: f64| negative_log_likelihood(logits, labels, log_t.exp());
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (MIN_TEMPERATURE.ln(), MAX_TEMPERATURE.ln());
+
+    while high - low > 1e-9 {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+
+        if nll(left)? < nll(right)? {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+
+    Ok(((low + high) / 2.0).exp())
+}
+
+fn check(logits: &[Vec<f64>], labels: &[usize]) -> Result<()> {
+    if logits.len() != labels.len() {
+        return Err(Error::SampleMismatch(logits.len(), labels.len()));
+    }
+    if logits.is_empty() {
+        return Err(Error::NoSamples);
+    }
+
+    match logits
+        .iter()
+        .zip(labels)
+        .position(|(logits, &label)| label >= logits.len())
+    {
+        Some(sample) => Err(Error::UnknownLabel {
+            sample,
+            label: labels[sample],
+            classes: logits[sample].len(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit_temperature, negative_log_likelihood, softmax, Error};
+    use crate::metrics::expected_calibration_error;
+
+    #[test]
+    fn temperature_scaling() {
+        // confident in the first class, which is right 70% of the time
+        let logits = vec![vec![3.0, 0.0]; 100];
+        let labels: Vec<_> = (0..100).map(|i| usize::from(i % 10 >= 7)).collect();
+
+        let temperature = fit_temperature(&logits, &labels).expect("should fit");
+        assert!((temperature - 3.0 / (7.0f64 / 3.0).ln()).abs() < 1e-6);
+        assert!(
+            negative_log_likelihood(&logits, &labels, temperature).expect("should compute")
+                < negative_log_likelihood(&logits, &labels, 1.0).expect("should compute")
+        );
+
+        let probabilities = |temperature| -> Vec<_> {
+            logits
+                .iter()
+                .map(|logits| softmax(logits, temperature))
+                .collect()
+        };
+        let before = expected_calibration_error(&probabilities(1.0), &labels, 10);
+        let after = expected_calibration_error(&probabilities(temperature), &labels, 10);
+        assert!((before - (1.0 / (1.0 + (-3.0f64).exp()) - 0.7)).abs() < 1e-12);
+        assert!(after < 1e-6);
+
+        assert!(matches!(
+            fit_temperature(&logits, &[0]),
+            Err(Error::SampleMismatch(100, 1))
+        ));
+        assert!(matches!(
+            fit_temperature(&[vec![1.0, 2.0]], &[2]),
+            Err(Error::UnknownLabel { sample: 0, .. })
+        ));
+    }
+}
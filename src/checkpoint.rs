@@ -0,0 +1,232 @@
+use std::{fs, io, path::Path, str::FromStr};
+
+use thiserror::Error as ThisError;
+
+use crate::{
+    nn::{Mlp, Precision},
+    optim::OptimizerState,
+};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid checkpoint, {0}")]
+    Format(String),
+    #[error("Parameter {0} isn't representable in {1}")]
+    Unrepresentable(f64, Precision),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const HEADER: &str = "micrograd-checkpoint 1";
+
+/// Everything needed to restore a training run: model architecture and weights,
+/// optimizer state and the number of completed epochs.
+///
+/// Stored as plain text, one `key values...` entry per line, with floats written
+/// in their shortest round-tripping representation, so loading a checkpoint
+/// restores the exact same values. With a lower `precision` the parameters
+/// are restored within [`Precision::tolerance`] instead, the optimizer state
+/// is always kept exactly.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Checkpoint {
+    pub epoch: usize,
+    pub layer_sizes: Vec<usize>,
+    pub parameters: Vec<f64>,
+    pub optimizer: OptimizerState,
+    pub precision: Precision,
+}
+
+impl Checkpoint {
+    pub fn new(epoch: usize, model: &Mlp, optimizer: OptimizerState) -> Self {
+        Self {
+            epoch,
+            layer_sizes: model.layer_sizes(),
+            parameters: model.parameters().iter().map(|p| p.value()).collect(),
+            optimizer,
+            precision: Precision::F64,
+        }
+    }
+
+    /// Fails if a parameter isn't representable in the checkpoint's precision
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(&parameter) = self.parameters.iter().find(|&&p| !self.precision.check(p)) {
+            return Err(Error::Unrepresentable(parameter, self.precision));
+        }
+
+        fs::write(path, self.to_string())?;
+
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+
+impl std::fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "epoch {}", self.epoch)?;
+        writeln!(f, "layers{}", joined(&self.layer_sizes))?;
+
+        if self.precision != Precision::F64 {
+            writeln!(f, "precision {}", self.precision)?;
+        }
+
+        let parameters: Vec<_> = self
+            .parameters
+            .iter()
+            .map(|&p| self.precision.shortest(p))
+            .collect();
+        writeln!(f, "parameters{}", joined(&parameters))?;
+        writeln!(f, "learning_rate {:?}", self.optimizer.learning_rate)?;
+        writeln!(f, "steps {}", self.optimizer.steps)?;
+
+        for buffer in &self.optimizer.buffers {
+            writeln!(f, "buffer{}", joined(buffer))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s.lines();
+
+        if lines.next() != Some(HEADER) {
+            return Err(Error::Format("missing header".to_string()));
+        }
+
+        let mut checkpoint = Checkpoint::default();
+
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let (key, values) = line.split_once(' ').unwrap_or((line, ""));
+
+            match key {
+                "epoch" => checkpoint.epoch = parse_one(key, values)?,
+                "layers" => checkpoint.layer_sizes = parse_all(key, values)?,
+                "precision" => checkpoint.precision = parse_one(key, values)?,
+                "parameters" => checkpoint.parameters = parse_all(key, values)?,
+                "learning_rate" => checkpoint.optimizer.learning_rate = parse_one(key, values)?,
+                "steps" => checkpoint.optimizer.steps = parse_one(key, values)?,
+                "buffer" => checkpoint.optimizer.buffers.push(parse_all(key, values)?),
+                _ => return Err(Error::Format(format!("unknown entry '{key}'"))),
+            }
+        }
+
+        let precision = checkpoint.precision;
+        checkpoint
+            .parameters
+            .iter_mut()
+            .for_each(|p| *p = precision.round(*p));
+
+        Ok(checkpoint)
+    }
+}
+
+fn joined<T: std::fmt::Debug>(values: &[T]) -> String {
+    values.iter().map(|v| format!(" {v:?}")).collect()
+}
+
+fn parse_one<T: FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::Format(format!("invalid value of '{key}'")))
+}
+
+fn parse_all<T: FromStr>(key: &str, values: &str) -> Result<Vec<T>> {
+    values
+        .split_whitespace()
+        .map(|value| parse_one(key, value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{Checkpoint, Error};
+    use crate::{
+        nn::{Mlp, Precision},
+        optim::OptimizerState,
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mlp = Mlp::new(3, &[4, 1], &mut rng);
+
+        let checkpoint = Checkpoint::new(
+            7,
+            &mlp,
+            OptimizerState {
+                learning_rate: 0.1,
+                steps: 3,
+                buffers: vec![vec![0.5; 21], vec![1e-300; 21]],
+            },
+        );
+
+        let parsed: Checkpoint = checkpoint.to_string().parse().expect("should parse");
+
+        assert_eq!(parsed, checkpoint);
+        assert_eq!(parsed.layer_sizes, vec![3, 4, 1]);
+    }
+
+    #[test]
+    fn half_precision() {
+        let mlp = Mlp::new_seeded(3, &[8, 1], 1);
+        let state = OptimizerState {
+            learning_rate: 0.1,
+            steps: 3,
+            buffers: vec![vec![0.1; 41]],
+        };
+
+        let full = Checkpoint::new(7, &mlp, state.clone());
+        let half = Checkpoint {
+            precision: Precision::F16,
+            ..full.clone()
+        };
+        assert!(half.to_string().len() < full.to_string().len() * 3 / 4);
+
+        let parsed: Checkpoint = half.to_string().parse().expect("should parse");
+        assert_eq!(parsed.precision, Precision::F16);
+        assert_eq!(parsed.optimizer, state);
+        for (p, expected) in parsed.parameters.iter().zip(&full.parameters) {
+            assert_eq!(*p, Precision::F16.round(*expected));
+            assert!((p - expected).abs() <= Precision::F16.tolerance());
+        }
+
+        // a diverged run still saves
+        let mut diverged = full.clone();
+        diverged.parameters[0] = f64::NAN;
+        diverged.parameters[1] = f64::INFINITY;
+        let path = std::env::temp_dir().join(format!("micrograd-nan-{}", std::process::id()));
+        diverged.save(&path).expect("should save");
+        let loaded = Checkpoint::load(&path).expect("should load");
+        std::fs::remove_file(&path).expect("should clean up");
+        assert!(loaded.parameters[0].is_nan());
+        assert_eq!(loaded.parameters[1], f64::INFINITY);
+
+        let mut large = half.clone();
+        large.parameters[0] = 1e6;
+        assert!(matches!(
+            large.save(std::env::temp_dir().join("micrograd-unsaved")),
+            Err(Error::Unrepresentable(..))
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("nonsense".parse::<Checkpoint>().is_err());
+        assert!("micrograd-checkpoint 1\nepoch x"
+            .parse::<Checkpoint>()
+            .is_err());
+    }
+}
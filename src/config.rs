@@ -0,0 +1,710 @@
+use std::{fs, io, path::Path};
+
+use serde::Deserialize;
+use thiserror::Error as ThisError;
+
+use crate::{
+    loss::{Loss, Reduction},
+    metrics::Metric,
+    nn::{self, Mlp},
+    optim::{
+        scheduler::{CosineAnnealingLR, CyclicLR, LrScheduler, StepLR, Warmup},
+        AdamW, Optimizer, Sgd,
+    },
+    train::{TrainConfig, Trainer},
+};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Unknown config format {0:?}, expected .toml, .yaml or .yml")]
+    UnknownFormat(String),
+    #[error("Invalid `{field}`, {reason}")]
+    Invalid { field: String, reason: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A complete training setup read from a TOML or YAML file, e.g.
+///
+/// ```toml
+/// [model]
+/// layers = [16, 16, 1]
+/// seed = 42
+///
+/// [optimizer]
+/// kind = "adamw"
+/// learning_rate = 0.01
+/// weight_decay = 0.001
+///
+/// [scheduler]
+/// kind = "cosine"
+/// t_max = 100
+/// warmup_steps = 5
+///
+/// [training]
+/// epochs = 100
+/// batch_size = 32
+/// loss = "binary_cross_entropy"
+/// metrics = [{ name = "accuracy", threshold = 0.0 }]
+/// ```
+///
+/// Unknown keys are rejected. Parsed configs should be checked with
+/// [`ExperimentConfig::validate`], which [`ExperimentConfig::load`] and
+/// [`ExperimentConfig::trainer`] do.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig {
+    pub model: ModelConfig,
+    pub optimizer: OptimizerConfig,
+    #[serde(default)]
+    pub scheduler: Option<SchedulerConfig>,
+    pub training: TrainingConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelConfig {
+    /// Outputs of every layer, the last one being the number of targets
+    pub layers: Vec<usize>,
+    #[serde(default)]
+    pub activation: Activation,
+    /// Seed of the weight initialization, random when missing
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Nonlinearity of the hidden layers
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    #[default]
+    Tanh,
+    /// Name of an activation registered with [`nn::register_activation`]
+    #[serde(untagged)]
+    Custom(String),
+}
+
+impl Activation {
+    fn resolve(&self) -> Option<nn::Activation> {
+        match self {
+            Self::Tanh => Some(nn::Activation::tanh()),
+            Self::Custom(name) => nn::Activation::named(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum OptimizerConfig {
+    Sgd {
+        learning_rate: f64,
+        #[serde(default)]
+        clip_grad_norm: Option<f64>,
+        #[serde(default)]
+        clip_grad_value: Option<f64>,
+    },
+    #[serde(rename = "adamw")]
+    AdamW {
+        learning_rate: f64,
+        #[serde(default)]
+        weight_decay: f64,
+        #[serde(default)]
+        betas: Option<(f64, f64)>,
+        #[serde(default)]
+        clip_grad_norm: Option<f64>,
+        #[serde(default)]
+        clip_grad_value: Option<f64>,
+    },
+}
+
+/// Learning rate schedule, optionally preceded by `warmup_steps` epochs of
+/// linear warmup
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SchedulerConfig {
+    Step {
+        step_size: usize,
+        gamma: f64,
+        #[serde(default)]
+        warmup_steps: usize,
+    },
+    Cosine {
+        t_max: usize,
+        #[serde(default)]
+        eta_min: f64,
+        #[serde(default)]
+        warmup_steps: usize,
+    },
+    /// Triangular cycles between the optimizer's learning rate and `max_lr`
+    Cyclic {
+        max_lr: f64,
+        cycle_length: usize,
+        #[serde(default)]
+        warmup_steps: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrainingConfig {
+    pub epochs: usize,
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+    #[serde(default)]
+    pub shuffle: bool,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub loss: LossConfig,
+    #[serde(default)]
+    pub reduction: ReductionConfig,
+    #[serde(default)]
+    pub metrics: Vec<MetricConfig>,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LossConfig {
+    #[default]
+    SquaredError,
+    BinaryCrossEntropy,
+    CrossEntropy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReductionConfig {
+    #[default]
+    Sum,
+    Mean,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MetricConfig {
+    Accuracy {
+        #[serde(default)]
+        threshold: f64,
+    },
+    MacroF1 {
+        classes: usize,
+        #[serde(default)]
+        threshold: f64,
+    },
+    R2,
+    Rmse,
+    Mae,
+    Mape,
+    RocAuc {
+        #[serde(default)]
+        threshold: f64,
+    },
+    TopKAccuracy {
+        k: usize,
+    },
+}
+
+impl ExperimentConfig {
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let config: Self = toml::from_str(source)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_yaml(source: &str) -> Result<Self> {
+        let config: Self = serde_yaml::from_str(source)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reads a config file, choosing the format by its extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&source),
+            Some("yaml" | "yml") => Self::from_yaml(&source),
+            other => Err(Error::UnknownFormat(other.unwrap_or_default().to_string())),
+        }
+    }
+
+    /// Checks the values deserialization can't, reporting the first offending
+    /// field by its path, e.g. `optimizer.learning_rate`
+    pub fn validate(&self) -> Result<()> {
+        if self.model.layers.is_empty() {
+            return Err(invalid("model.layers", "expected at least one layer"));
+        }
+
+        if let Some(i) = self.model.layers.iter().position(|&size| size == 0) {
+            return Err(invalid(
+                &format!("model.layers[{i}]"),
+                "layers need at least one neuron",
+            ));
+        }
+
+        if self.model.activation.resolve().is_none() {
+            return Err(invalid(
+                "model.activation",
+                "unknown activation, custom activations have to be registered first",
+            ));
+        }
+
+        let (learning_rate, clip_grad_norm, clip_grad_value) = match &self.optimizer {
+            OptimizerConfig::Sgd {
+                learning_rate,
+                clip_grad_norm,
+                clip_grad_value,
+            } => (learning_rate, clip_grad_norm, clip_grad_value),
+            OptimizerConfig::AdamW {
+                learning_rate,
+                weight_decay,
+                betas,
+                clip_grad_norm,
+                clip_grad_value,
+            } => {
+                if *weight_decay < 0.0 || weight_decay.is_nan() {
+                    return Err(invalid("optimizer.weight_decay", "expected zero or more"));
+                }
+
+                if let Some((beta1, beta2)) = betas {
+                    if !(0.0..1.0).contains(beta1) || !(0.0..1.0).contains(beta2) {
+                        return Err(invalid("optimizer.betas", "expected values in [0, 1)"));
+                    }
+                }
+
+                (learning_rate, clip_grad_norm, clip_grad_value)
+            }
+        };
+
+        positive("optimizer.learning_rate", *learning_rate)?;
+
+        if let Some(max) = clip_grad_norm {
+            positive("optimizer.clip_grad_norm", *max)?;
+        }
+
+        if let Some(max) = clip_grad_value {
+            positive("optimizer.clip_grad_value", *max)?;
+        }
+
+        match &self.scheduler {
+            Some(SchedulerConfig::Step {
+                step_size, gamma, ..
+            }) => {
+                if *step_size == 0 {
+                    return Err(invalid("scheduler.step_size", "expected at least one step"));
+                }
+
+                positive("scheduler.gamma", *gamma)?
+            }
+            Some(SchedulerConfig::Cosine { t_max, eta_min, .. }) => {
+                if *t_max == 0 {
+                    return Err(invalid("scheduler.t_max", "expected at least one step"));
+                }
+
+                if !(*eta_min >= 0.0 && eta_min < learning_rate) {
+                    return Err(invalid(
+                        "scheduler.eta_min",
+                        "expected zero or more and below the learning rate",
+                    ));
+                }
+            }
+            Some(SchedulerConfig::Cyclic {
+                max_lr,
+                cycle_length,
+                ..
+            }) => {
+                if *cycle_length < 2 {
+                    return Err(invalid(
+                        "scheduler.cycle_length",
+                        "expected at least two steps",
+                    ));
+                }
+
+                if max_lr <= learning_rate || max_lr.is_nan() {
+                    return Err(invalid(
+                        "scheduler.max_lr",
+                        "expected above the learning rate",
+                    ));
+                }
+            }
+            None => {}
+        }
+
+        if self.training.epochs == 0 {
+            return Err(invalid("training.epochs", "expected at least one epoch"));
+        }
+
+        if self.training.batch_size == Some(0) {
+            return Err(invalid(
+                "training.batch_size",
+                "expected at least one sample",
+            ));
+        }
+
+        let outputs = self.model.layers[self.model.layers.len() - 1];
+
+        if self.training.loss == LossConfig::CrossEntropy && outputs < 2 {
+            return Err(invalid(
+                "training.loss",
+                "cross entropy needs at least two outputs",
+            ));
+        }
+
+        for (i, metric) in self.training.metrics.iter().enumerate() {
+            if let MetricConfig::MacroF1 { classes, .. } = metric {
+                if *classes < 2 {
+                    return Err(invalid(
+                        &format!("training.metrics[{i}].classes"),
+                        "expected at least two classes",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds a freshly initialized model for `inputs` features
+    pub fn model(&self, inputs: usize) -> Mlp {
+        let model = match self.model.seed {
+            Some(seed) => Mlp::new_seeded(inputs, &self.model.layers, seed),
+            None => Mlp::new(inputs, &self.model.layers, &mut rand::thread_rng()),
+        };
+
+        match self.model.activation.resolve() {
+            Some(activation) => model.with_activation(activation),
+            None => model,
+        }
+    }
+
+    /// Validates the config and builds a trainer for a new model with `inputs`
+    /// features. Train it for `training.epochs` epochs.
+    pub fn trainer(&self, inputs: usize) -> Result<Trainer> {
+        self.validate()?;
+
+        let model = self.model(inputs);
+        let parameters = model.parameters();
+
+        let trainer = match self.optimizer {
+            OptimizerConfig::Sgd {
+                learning_rate,
+                clip_grad_norm,
+                clip_grad_value,
+            } => {
+                let mut sgd = Sgd::new(parameters, learning_rate);
+
+                if let Some(max) = clip_grad_norm {
+                    sgd = sgd.clip_grad_norm(max);
+                }
+                if let Some(max) = clip_grad_value {
+                    sgd = sgd.clip_grad_value(max);
+                }
+
+                self.build(model, sgd)
+            }
+            OptimizerConfig::AdamW {
+                learning_rate,
+                weight_decay,
+                betas,
+                clip_grad_norm,
+                clip_grad_value,
+            } => {
+                let mut adamw = AdamW::new(parameters, learning_rate, weight_decay);
+
+                if let Some((beta1, beta2)) = betas {
+                    adamw = adamw.betas(beta1, beta2);
+                }
+                if let Some(max) = clip_grad_norm {
+                    adamw = adamw.clip_grad_norm(max);
+                }
+                if let Some(max) = clip_grad_value {
+                    adamw = adamw.clip_grad_value(max);
+                }
+
+                self.build(model, adamw)
+            }
+        };
+
+        Ok(trainer)
+    }
+
+    // Schedulers with warmup adjust the optimizer on construction, so they're
+    // built before it moves into the trainer
+    fn build<O: Optimizer + 'static>(&self, model: Mlp, mut optimizer: O) -> Trainer {
+        let scheduler: Option<Box<dyn LrScheduler>> = self.scheduler.as_ref().map(|scheduler| {
+            let (schedule, warmup_steps): (Box<dyn LrScheduler>, _) = match *scheduler {
+                SchedulerConfig::Step {
+                    step_size,
+                    gamma,
+                    warmup_steps,
+                } => (Box::new(StepLR::new(step_size, gamma)), warmup_steps),
+                SchedulerConfig::Cosine {
+                    t_max,
+                    eta_min,
+                    warmup_steps,
+                } => (
+                    Box::new(CosineAnnealingLR::new(t_max, eta_min)),
+                    warmup_steps,
+                ),
+                SchedulerConfig::Cyclic {
+                    max_lr,
+                    cycle_length,
+                    warmup_steps,
+                } => (
+                    Box::new(CyclicLR::new(
+                        optimizer.learning_rate(),
+                        max_lr,
+                        cycle_length,
+                    )),
+                    warmup_steps,
+                ),
+            };
+
+            if warmup_steps > 0 {
+                Box::new(Warmup::new(warmup_steps, schedule, &mut optimizer))
+            } else {
+                schedule
+            }
+        });
+
+        let loss = match self.training.loss {
+            LossConfig::SquaredError => Loss::SquaredError,
+            LossConfig::BinaryCrossEntropy => Loss::BinaryCrossEntropy {
+                class_weights: None,
+            },
+            LossConfig::CrossEntropy => Loss::CrossEntropy {
+                class_weights: None,
+            },
+        };
+
+        let trainer = Trainer::new(model, optimizer, loss).config(self.train_config());
+
+        match scheduler {
+            Some(scheduler) => trainer.scheduler(scheduler),
+            None => trainer,
+        }
+    }
+
+    fn train_config(&self) -> TrainConfig {
+        let training = &self.training;
+
+        TrainConfig {
+            verbose: training.verbose,
+            sample_weights: None,
+            batch_size: training.batch_size,
+            shuffle: training.shuffle,
+            seed: training.seed,
+            reduction: match training.reduction {
+                ReductionConfig::Sum => Reduction::Sum,
+                ReductionConfig::Mean => Reduction::Mean,
+            },
+            metrics: training.metrics.iter().map(MetricConfig::metric).collect(),
+            histogram_buckets: None,
+        }
+    }
+}
+
+impl MetricConfig {
+    fn metric(&self) -> Metric {
+        match *self {
+            MetricConfig::Accuracy { threshold } => Metric::Accuracy { threshold },
+            MetricConfig::MacroF1 { classes, threshold } => Metric::MacroF1 { classes, threshold },
+            MetricConfig::R2 => Metric::R2,
+            MetricConfig::Rmse => Metric::Rmse,
+            MetricConfig::Mae => Metric::Mae,
+            MetricConfig::Mape => Metric::Mape,
+            MetricConfig::RocAuc { threshold } => Metric::RocAuc { threshold },
+            MetricConfig::TopKAccuracy { k } => Metric::TopKAccuracy { k },
+        }
+    }
+}
+
+fn invalid(field: &str, reason: &str) -> Error {
+    Error::Invalid {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+fn positive(field: &str, value: f64) -> Result<()> {
+    if value > 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid(
+            field,
+            &format!("expected a positive number, found {value}"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Activation, Error, ExperimentConfig, LossConfig, MetricConfig, OptimizerConfig,
+        SchedulerConfig,
+    };
+    use crate::{data::InMemoryDataset, nn};
+
+    const TOML: &str = r#"
+        [model]
+        layers = [4, 1]
+        seed = 1
+
+        [optimizer]
+        kind = "adamw"
+        learning_rate = 0.05
+        betas = [0.8, 0.99]
+
+        [scheduler]
+        kind = "step"
+        step_size = 5
+        gamma = 0.5
+        warmup_steps = 2
+
+        [training]
+        epochs = 20
+        loss = "squared_error"
+        metrics = [{ name = "accuracy" }, { name = "rmse" }]
+    "#;
+
+    #[test]
+    fn toml_and_yaml() {
+        let toml = ExperimentConfig::from_toml(TOML).expect("should parse");
+
+        assert_eq!(toml.model.layers, [4, 1]);
+        assert_eq!(toml.model.activation, Activation::Tanh);
+        assert!(matches!(
+            toml.optimizer,
+            OptimizerConfig::AdamW { weight_decay, betas: Some((0.8, 0.99)), .. } if weight_decay == 0.0
+        ));
+        assert!(matches!(
+            toml.scheduler,
+            Some(SchedulerConfig::Step {
+                warmup_steps: 2,
+                ..
+            })
+        ));
+        assert_eq!(toml.training.loss, LossConfig::SquaredError);
+        assert_eq!(
+            toml.training.metrics,
+            [
+                MetricConfig::Accuracy { threshold: 0.0 },
+                MetricConfig::Rmse
+            ]
+        );
+
+        let yaml = ExperimentConfig::from_yaml(
+            "
+model:
+  layers: [4, 1]
+  seed: 1
+optimizer:
+  kind: adamw
+  learning_rate: 0.05
+  betas: [0.8, 0.99]
+scheduler:
+  kind: step
+  step_size: 5
+  gamma: 0.5
+  warmup_steps: 2
+training:
+  epochs: 20
+  loss: squared_error
+  metrics:
+    - name: accuracy
+    - name: rmse
+",
+        )
+        .expect("should parse");
+
+        assert_eq!(yaml, toml);
+    }
+
+    #[test]
+    fn invalid_fields() {
+        let field = |source: &str| match ExperimentConfig::from_toml(source) {
+            Err(Error::Invalid { field, .. }) => field,
+            other => panic!("expected a validation error, got {other:?}"),
+        };
+
+        assert_eq!(
+            field(&TOML.replace("learning_rate = 0.05", "learning_rate = -1.0")),
+            "optimizer.learning_rate"
+        );
+        assert_eq!(
+            field(&TOML.replace("layers = [4, 1]", "layers = [4, 0]")),
+            "model.layers[1]"
+        );
+        assert_eq!(
+            field(&TOML.replace("epochs = 20", "epochs = 20\nbatch_size = 0")),
+            "training.batch_size"
+        );
+        assert_eq!(
+            field(&TOML.replace("step_size = 5", "step_size = 0")),
+            "scheduler.step_size"
+        );
+
+        let cyclic = TOML.replace(
+            "kind = \"step\"\n        step_size = 5\n        gamma = 0.5",
+            "kind = \"cyclic\"\n        max_lr = 0.01\n        cycle_length = 4",
+        );
+        assert_eq!(field(&cyclic), "scheduler.max_lr");
+        assert!(ExperimentConfig::from_toml(&cyclic.replace("0.01", "0.5")).is_ok());
+
+        let unknown = ExperimentConfig::from_toml(&TOML.replace("gamma", "gama"));
+        assert!(matches!(unknown, Err(Error::Toml(err)) if err.to_string().contains("gama")));
+
+        let activation =
+            ExperimentConfig::from_toml(&TOML.replace("seed = 1", "activation = \"relu\""));
+        assert!(
+            matches!(activation, Err(Error::Invalid { field, .. }) if field == "model.activation")
+        );
+
+        nn::register_activation(nn::Activation::new(
+            "config_swish",
+            |x| x.clone() * x.sigmoid(),
+            |x| x / (1.0 + (-x).exp()),
+        ))
+        .expect("should register");
+        let custom = ExperimentConfig::from_toml(
+            &TOML.replace("seed = 1", "seed = 1\nactivation = \"config_swish\""),
+        )
+        .expect("should parse");
+        assert_eq!(
+            custom.model.activation,
+            Activation::Custom("config_swish".to_string())
+        );
+        assert_eq!(custom.model(2).activation().name(), "config_swish");
+    }
+
+    #[test]
+    fn builds_trainer() {
+        let config = ExperimentConfig::from_toml(TOML).expect("should parse");
+        let data = InMemoryDataset::from(vec![
+            (vec![1.0, 0.0], vec![1.0]),
+            (vec![0.0, 1.0], vec![-1.0]),
+        ]);
+
+        let mut trainer = config.trainer(2).expect("should build");
+        let history = trainer
+            .fit(&data, config.training.epochs)
+            .expect("should train");
+
+        assert_eq!(trainer.model().layer_sizes(), [2, 4, 1]);
+        assert_eq!(history.len(), 20);
+        assert_eq!(history.metric("accuracy")[19], Some(1.0));
+        assert!(history.learning_rate[0] < history.learning_rate[1]);
+        assert!(history.learning_rate[19] < 0.05);
+    }
+}
@@ -0,0 +1,380 @@
+#[cfg(feature = "polars")]
+mod dataframe;
+pub mod generators;
+#[cfg(feature = "arrow")]
+mod ipc;
+#[cfg(feature = "datasets")]
+mod iris;
+#[cfg(feature = "mnist")]
+pub mod mnist;
+#[cfg(feature = "numpy")]
+mod npy;
+mod scale;
+mod streaming;
+pub mod transform;
+mod window;
+
+use rand::{seq::SliceRandom, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error as ThisError;
+
+#[cfg(feature = "arrow")]
+pub use ipc::from_arrow;
+#[cfg(feature = "datasets")]
+pub use iris::{iris, IRIS_CLASSES, IRIS_FEATURES};
+#[cfg(feature = "numpy")]
+pub use npy::{from_npy, from_npz};
+pub use scale::{MinMaxScaler, StandardScaler};
+pub use streaming::StreamingDataset;
+pub use window::windows;
+
+use transform::{Phase, Pipeline};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("Sample count mismatch, got {0} inputs and {1} targets")]
+    SampleMismatch(usize, usize),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid dataset file, {0}")]
+    Format(String),
+    #[error("One-hot encoding needs at least one class")]
+    NoClasses,
+    #[error("Windows need at least one input and one step ahead, got {0} and {1}")]
+    Window(usize, usize),
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    Arrow(#[from] arrow::error::ArrowError),
+    #[cfg(feature = "numpy")]
+    #[error(transparent)]
+    Numpy(#[from] crate::numpy::Error),
+    #[cfg(feature = "polars")]
+    #[error(transparent)]
+    Polars(#[from] polars::prelude::PolarsError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An indexable collection of `(input, target)` samples
+pub trait Dataset {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `(input, target)` pair at `index`, panicking when out of bounds
+    fn get(&self, index: usize) -> (Vec<f64>, Vec<f64>);
+}
+
+/// A dataset held entirely in memory
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryDataset {
+    samples: Vec<(Vec<f64>, Vec<f64>)>,
+}
+
+impl InMemoryDataset {
+    pub fn new(inputs: Vec<Vec<f64>>, targets: Vec<Vec<f64>>) -> Result<Self> {
+        if inputs.len() != targets.len() {
+            return Err(Error::SampleMismatch(inputs.len(), targets.len()));
+        }
+
+        Ok(Self {
+            samples: inputs.into_iter().zip(targets).collect(),
+        })
+    }
+}
+
+impl From<Vec<(Vec<f64>, Vec<f64>)>> for InMemoryDataset {
+    fn from(samples: Vec<(Vec<f64>, Vec<f64>)>) -> Self {
+        Self { samples }
+    }
+}
+
+impl Dataset for InMemoryDataset {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn get(&self, index: usize) -> (Vec<f64>, Vec<f64>) {
+        self.samples[index].clone()
+    }
+}
+
+/// A view of selected samples of another dataset, without copying them
+#[derive(Debug, Clone)]
+pub struct Subset<'a, D: ?Sized> {
+    dataset: &'a D,
+    indices: Vec<usize>,
+}
+
+impl<'a, D: Dataset + ?Sized> Subset<'a, D> {
+    pub fn new(dataset: &'a D, indices: Vec<usize>) -> Self {
+        Self { dataset, indices }
+    }
+
+    /// Indices of the selected samples in the underlying dataset
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl<D: Dataset + ?Sized> Dataset for Subset<'_, D> {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn get(&self, index: usize) -> (Vec<f64>, Vec<f64>) {
+        self.dataset.get(self.indices[index])
+    }
+}
+
+/// Randomly splits a dataset into two disjoint views, the first one holding
+/// `ratio` of the samples (rounded), e.g. a 0.8 train / 0.2 validation split.
+pub fn split<D: Dataset + ?Sized>(
+    dataset: &D,
+    ratio: f64,
+    seed: u64,
+) -> (Subset<'_, D>, Subset<'_, D>) {
+    let mut indices: Vec<_> = (0..dataset.len()).collect();
+    indices.shuffle(&mut ChaCha8Rng::seed_from_u64(seed));
+
+    let at = ((dataset.len() as f64 * ratio.clamp(0.0, 1.0)).round() as usize).min(dataset.len());
+    let rest = indices.split_off(at);
+
+    (Subset::new(dataset, indices), Subset::new(dataset, rest))
+}
+
+/// A batch of samples drawn from a dataset, along with their dataset indices
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub indices: Vec<usize>,
+    pub inputs: Vec<Vec<f64>>,
+    pub targets: Vec<Vec<f64>>,
+}
+
+impl Batch {
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// Splits a dataset into batches, optionally in a freshly shuffled order every
+/// epoch and with every sample passed through a transform pipeline
+pub struct DataLoader<'a, D: ?Sized> {
+    dataset: &'a D,
+    batch_size: usize,
+    rng: Option<ChaCha8Rng>,
+    transform: Option<(&'a Pipeline, ChaCha8Rng)>,
+}
+
+impl<'a, D: Dataset + ?Sized> DataLoader<'a, D> {
+    pub fn new(dataset: &'a D, batch_size: usize) -> Self {
+        Self {
+            dataset,
+            batch_size: batch_size.max(1),
+            rng: None,
+            transform: None,
+        }
+    }
+
+    /// Shuffles the samples at the start of every epoch. The order of every
+    /// epoch is fully determined by `seed`.
+    pub fn shuffle(mut self, seed: u64) -> Self {
+        self.rng = Some(ChaCha8Rng::seed_from_u64(seed));
+        self
+    }
+
+    /// Passes every sample through the training phase of `pipeline`, with the
+    /// random augmentations of every epoch determined by `seed`
+    pub fn transform(mut self, pipeline: &'a Pipeline, seed: u64) -> Self {
+        self.transform = Some((pipeline, ChaCha8Rng::seed_from_u64(seed)));
+        self
+    }
+
+    /// Number of batches in one epoch
+    pub fn len(&self) -> usize {
+        self.dataset.len().div_ceil(self.batch_size)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dataset.is_empty()
+    }
+
+    /// Skips `epochs` epochs without loading any samples, e.g. to restore the
+    /// shuffling order of a resumed training run
+    pub fn skip_epochs(&mut self, epochs: usize) {
+        for _ in 0..epochs {
+            self.next_order();
+        }
+    }
+
+    fn next_order(&mut self) -> (Vec<usize>, Option<(&'a Pipeline, ChaCha8Rng)>) {
+        let mut order: Vec<_> = (0..self.dataset.len()).collect();
+
+        if let Some(rng) = &mut self.rng {
+            order.shuffle(rng);
+        }
+
+        let transform = self
+            .transform
+            .as_mut()
+            .map(|(pipeline, rng)| (*pipeline, ChaCha8Rng::seed_from_u64(rng.next_u64())));
+
+        (order, transform)
+    }
+
+    /// Batches of the next epoch. The last batch may be smaller than the batch size.
+    pub fn epoch(&mut self) -> impl Iterator<Item = Batch> + 'a {
+        let (order, mut transform) = self.next_order();
+        let dataset = self.dataset;
+        let batches: Vec<_> = order.chunks(self.batch_size).map(<[_]>::to_vec).collect();
+
+        batches.into_iter().map(move |indices| {
+            let (inputs, targets) = indices
+                .iter()
+                .map(|&i| match &mut transform {
+                    Some((pipeline, rng)) => pipeline.apply(dataset.get(i), Phase::Train, rng),
+                    None => dataset.get(i),
+                })
+                .unzip();
+
+            Batch {
+                indices,
+                inputs,
+                targets,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        split,
+        transform::{GaussianNoise, Pipeline, Sample},
+        DataLoader, Dataset, Error, InMemoryDataset,
+    };
+
+    #[test]
+    fn in_memory_dataset() {
+        let dataset = InMemoryDataset::new(
+            vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+            vec![vec![0.0], vec![1.0]],
+        )
+        .expect("dataset should build");
+
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.get(1), (vec![3.0, 4.0], vec![1.0]));
+    }
+
+    #[test]
+    fn in_memory_dataset_mismatch() {
+        let result = InMemoryDataset::new(vec![vec![1.0]], vec![]);
+
+        assert!(matches!(result, Err(Error::SampleMismatch(1, 0))));
+    }
+
+    fn numbers(count: usize) -> InMemoryDataset {
+        InMemoryDataset::from(
+            (0..count)
+                .map(|i| (vec![i as f64], vec![i as f64 * 2.0]))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn data_loader_batches() {
+        let dataset = numbers(5);
+        let mut loader = DataLoader::new(&dataset, 2);
+
+        let batches: Vec<_> = loader.epoch().collect();
+
+        assert_eq!(loader.len(), 3);
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[1].indices, vec![2, 3]);
+        assert_eq!(batches[1].inputs, vec![vec![2.0], vec![3.0]]);
+        assert_eq!(batches[1].targets, vec![vec![4.0], vec![6.0]]);
+        assert_eq!(batches[2].len(), 1);
+    }
+
+    #[test]
+    fn data_loader_shuffle() {
+        let dataset = numbers(20);
+        let order = |loader: &mut DataLoader<InMemoryDataset>| {
+            loader
+                .epoch()
+                .flat_map(|batch| batch.indices)
+                .collect::<Vec<_>>()
+        };
+
+        let mut first = DataLoader::new(&dataset, 3).shuffle(7);
+        let mut second = DataLoader::new(&dataset, 3).shuffle(7);
+
+        let epoch_1 = order(&mut first);
+        let epoch_2 = order(&mut first);
+
+        assert_eq!(epoch_1, order(&mut second));
+        assert_eq!(epoch_2, order(&mut second));
+        assert_ne!(epoch_1, epoch_2);
+
+        let mut sorted = epoch_1.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn data_loader_transform() {
+        let dataset = numbers(4);
+        let pipeline = Pipeline::new()
+            .then(|(x, y): Sample| (x, y.iter().map(|y| y + 1.0).collect()))
+            .augment(GaussianNoise { std: 0.1 });
+        let inputs = |loader: &mut DataLoader<InMemoryDataset>| {
+            loader
+                .epoch()
+                .flat_map(|batch| batch.inputs.concat())
+                .collect::<Vec<_>>()
+        };
+
+        let mut first = DataLoader::new(&dataset, 3).transform(&pipeline, 1);
+        let mut second = DataLoader::new(&dataset, 3).transform(&pipeline, 1);
+
+        let epoch_1 = inputs(&mut first);
+
+        assert_eq!(epoch_1, inputs(&mut second));
+        assert_ne!(epoch_1, inputs(&mut first));
+        assert_ne!(epoch_1, vec![0.0, 1.0, 2.0, 3.0]);
+
+        let batch = first.epoch().next().expect("should have a batch");
+        assert_eq!(batch.targets, vec![vec![1.0], vec![3.0], vec![5.0]]);
+    }
+
+    #[test]
+    fn split_dataset() {
+        let dataset = numbers(10);
+
+        let (train, validation) = split(&dataset, 0.7, 3);
+
+        assert_eq!(train.len(), 7);
+        assert_eq!(validation.len(), 3);
+
+        let mut all: Vec<_> = train
+            .indices()
+            .iter()
+            .chain(validation.indices())
+            .copied()
+            .collect();
+        all.sort();
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+
+        let i = validation.indices()[0];
+        assert_eq!(validation.get(0), dataset.get(i));
+
+        let (again, _) = split(&dataset, 0.7, 3);
+        assert_eq!(train.indices(), again.indices());
+    }
+}
@@ -0,0 +1,68 @@
+use polars::prelude::{DataFrame, DataType};
+
+use super::{Error, InMemoryDataset, Result};
+
+impl InMemoryDataset {
+    /// Builds a dataset from the `features` and `targets` columns of a polars
+    /// `DataFrame`, a sample per row. Columns are cast to `f64`, so booleans
+    /// and integers work too, but missing values are an error.
+    pub fn from_dataframe(df: &DataFrame, features: &[&str], targets: &[&str]) -> Result<Self> {
+        let inputs = rows(df, features)?;
+        let targets = rows(df, targets)?;
+
+        Self::new(inputs, targets)
+    }
+}
+
+fn rows(df: &DataFrame, names: &[&str]) -> Result<Vec<Vec<f64>>> {
+    let mut rows = vec![Vec::with_capacity(names.len()); df.height()];
+
+    for name in names {
+        let column = df.column(name)?.cast(&DataType::Float64)?;
+
+        for (row, value) in rows.iter_mut().zip(column.f64()?) {
+            row.push(value.ok_or_else(|| Error::Format(format!("missing value in {name:?}")))?);
+        }
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{df, NamedFrom, Series};
+
+    use crate::data::{Dataset, Error, InMemoryDataset};
+
+    #[test]
+    fn dataframe() {
+        let df = df!(
+            "x" => [1.0, 2.0, 3.0],
+            "label" => ["a", "b", "c"],
+            "y" => [1i32, 0, 1],
+            "z" => [true, false, false],
+        )
+        .expect("should build");
+
+        let dataset =
+            InMemoryDataset::from_dataframe(&df, &["z", "x"], &["y"]).expect("should convert");
+
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(dataset.get(1), (vec![0.0, 2.0], vec![0.0]));
+
+        assert!(matches!(
+            InMemoryDataset::from_dataframe(&df, &["w"], &["y"]),
+            Err(Error::Polars(_))
+        ));
+
+        let mut missing = df.clone();
+        missing
+            .with_column(Series::new("x".into(), [Some(1.0), None, Some(3.0)]))
+            .expect("should replace");
+
+        assert!(matches!(
+            InMemoryDataset::from_dataframe(&missing, &["x"], &["y"]),
+            Err(Error::Format(_))
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+//! Classic toy datasets, generated deterministically from a seed.
+//!
+//! Binary classification datasets have a single target of `1.0` or `-1.0`, to
+//! match the range of a tanh output. `noise` is the standard deviation of the
+//! Gaussian noise added to every coordinate, or to the target for regressions.
+
+use std::f64::consts::PI;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::InMemoryDataset;
+
+/// Four clusters around the corners of `[-1, 1]²`, positive when the signs of
+/// the two coordinates differ
+pub fn xor(samples: usize, noise: f64, seed: u64) -> InMemoryDataset {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..samples)
+        .map(|i| {
+            let (a, b) = (i % 2 == 0, (i / 2) % 2 == 0);
+            let corner = |positive| if positive { 1.0 } else { -1.0 };
+
+            let x = vec![
+                corner(a) + noise * gaussian(&mut rng),
+                corner(b) + noise * gaussian(&mut rng),
+            ];
+
+            (x, vec![corner(a != b)])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Two interleaving half circles, the upper one negative and the lower one
+/// positive, with samples alternating between them
+pub fn moons(samples: usize, noise: f64, seed: u64) -> InMemoryDataset {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..samples)
+        .map(|i| {
+            let t = rng.gen::<f64>() * PI;
+            let lower = i % 2 == 1;
+
+            let (x, y) = if lower {
+                (1.0 - t.cos(), 0.5 - t.sin())
+            } else {
+                (t.cos(), t.sin())
+            };
+
+            let x = vec![
+                x + noise * gaussian(&mut rng),
+                y + noise * gaussian(&mut rng),
+            ];
+
+            (x, vec![if lower { 1.0 } else { -1.0 }])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// A circle of radius `factor` (positive) inside a circle of radius 1
+/// (negative), with samples alternating between them
+pub fn circles(samples: usize, factor: f64, noise: f64, seed: u64) -> InMemoryDataset {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..samples)
+        .map(|i| {
+            let t = rng.gen::<f64>() * 2.0 * PI;
+            let inner = i % 2 == 1;
+            let radius = if inner { factor } else { 1.0 };
+
+            let x = vec![
+                radius * t.cos() + noise * gaussian(&mut rng),
+                radius * t.sin() + noise * gaussian(&mut rng),
+            ];
+
+            (x, vec![if inner { 1.0 } else { -1.0 }])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// `classes` intertwined spiral arms with `samples_per_class` points each and
+/// one-hot targets. `noise` perturbs the angle of every point.
+pub fn spirals(samples_per_class: usize, classes: usize, noise: f64, seed: u64) -> InMemoryDataset {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut data = Vec::with_capacity(samples_per_class * classes);
+
+    for class in 0..classes {
+        for i in 0..samples_per_class {
+            let radius = i as f64 / samples_per_class.max(2).saturating_sub(1) as f64;
+            let angle = (class as f64 * 2.0 * PI / classes as f64)
+                + 4.0 * radius
+                + noise * gaussian(&mut rng);
+
+            let mut target = vec![0.0; classes];
+            target[class] = 1.0;
+
+            data.push((vec![radius * angle.cos(), radius * angle.sin()], target));
+        }
+    }
+
+    data.into()
+}
+
+/// Inputs drawn uniformly from `[-1, 1]` for every weight, with the target
+/// `weights · x + bias` plus noise
+pub fn linear(
+    samples: usize,
+    weights: &[f64],
+    bias: f64,
+    noise: f64,
+    seed: u64,
+) -> InMemoryDataset {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..samples)
+        .map(|_| {
+            let x: Vec<_> = weights.iter().map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let y = weights.iter().zip(&x).map(|(w, x)| w * x).sum::<f64>()
+                + bias
+                + noise * gaussian(&mut rng);
+
+            (x, vec![y])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Inputs drawn uniformly from `[-π, π]` with the target `sin(x)` plus noise
+pub fn sine(samples: usize, noise: f64, seed: u64) -> InMemoryDataset {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..samples)
+        .map(|_| {
+            let x = rng.gen_range(-PI..PI);
+
+            (vec![x], vec![x.sin() + noise * gaussian(&mut rng)])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+// Standard normal sample using the Box-Muller transform
+pub(crate) fn gaussian<R: Rng>(rng: &mut R) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{circles, linear, moons, sine, spirals, xor};
+    use crate::data::Dataset;
+
+    #[test]
+    fn classification() {
+        let xor = xor(8, 0.0, 1);
+
+        assert_eq!(xor.len(), 8);
+        assert_eq!(xor.get(0), (vec![1.0, 1.0], vec![-1.0]));
+        assert_eq!(xor.get(1), (vec![-1.0, 1.0], vec![1.0]));
+
+        let moons = moons(100, 0.1, 1);
+        let positive = (0..moons.len()).filter(|&i| moons.get(i).1[0] > 0.0);
+        assert_eq!(positive.count(), 50);
+
+        let circles = circles(100, 0.5, 0.0, 1);
+        for i in 0..circles.len() {
+            let (x, y) = circles.get(i);
+            let radius = (x[0].powi(2) + x[1].powi(2)).sqrt();
+            let expected = if y[0] > 0.0 { 0.5 } else { 1.0 };
+
+            assert!((radius - expected).abs() < 1e-12);
+        }
+
+        let spirals = spirals(50, 3, 0.2, 1);
+        assert_eq!(spirals.len(), 150);
+        assert_eq!(spirals.get(120).1, vec![0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn regression() {
+        let linear = linear(20, &[2.0, -1.0], 0.5, 0.0, 1);
+
+        for i in 0..linear.len() {
+            let (x, y) = linear.get(i);
+
+            assert!((2.0 * x[0] - x[1] + 0.5 - y[0]).abs() < 1e-12);
+        }
+
+        let sine = sine(20, 0.0, 1);
+        let (x, y) = sine.get(3);
+        assert_eq!(y[0], x[0].sin());
+    }
+
+    #[test]
+    fn seeded() {
+        let first = moons(10, 0.1, 7);
+        let second = moons(10, 0.1, 7);
+        let other = moons(10, 0.1, 8);
+
+        assert_eq!(first.get(5), second.get(5));
+        assert_ne!(first.get(5), other.get(5));
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use arrow::{
+    array::{Array, AsArray},
+    compute::cast,
+    datatypes::{DataType, Float64Type},
+    error::ArrowError,
+    ipc::reader::{FileReader, StreamReader},
+    record_batch::RecordBatch,
+};
+
+use super::{Error, InMemoryDataset, Result};
+
+/// Loads the `features` and `targets` columns of an Arrow IPC file, also
+/// known as Feather v2, or an Arrow IPC stream, a sample per row.
+///
+/// Columns are cast to `f64`, so booleans and integers work too, but missing
+/// values are an error.
+pub fn from_arrow<P: AsRef<Path>>(
+    path: P,
+    features: &[&str],
+    targets: &[&str],
+) -> Result<InMemoryDataset> {
+    let mut file = File::open(path)?;
+
+    // only the file format starts with a magic number
+    let mut magic = [0; 6];
+    let is_file = file.read_exact(&mut magic).is_ok() && &magic == b"ARROW1";
+    file.seek(SeekFrom::Start(0))?;
+
+    let batches = if is_file {
+        FileReader::try_new(file, None)?.collect::<std::result::Result<Vec<_>, ArrowError>>()?
+    } else {
+        StreamReader::try_new(BufReader::new(file), None)?
+            .collect::<std::result::Result<Vec<_>, ArrowError>>()?
+    };
+
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    for batch in &batches {
+        inputs.extend(rows(batch, features)?);
+        outputs.extend(rows(batch, targets)?);
+    }
+
+    InMemoryDataset::new(inputs, outputs)
+}
+
+fn rows(batch: &RecordBatch, names: &[&str]) -> Result<Vec<Vec<f64>>> {
+    let mut rows = vec![Vec::with_capacity(names.len()); batch.num_rows()];
+
+    for name in names {
+        let column = batch
+            .column_by_name(name)
+            .ok_or_else(|| Error::Format(format!("missing column {name:?}")))?;
+        let column = cast(column, &DataType::Float64)?;
+        let column = column.as_primitive::<Float64Type>();
+
+        if column.null_count() > 0 {
+            return Err(Error::Format(format!("missing value in {name:?}")));
+        }
+
+        for (row, value) in rows.iter_mut().zip(column.values()) {
+            row.push(*value);
+        }
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs::File, process, sync::Arc};
+
+    use arrow::{
+        array::{BooleanArray, Float32Array, Int64Array},
+        datatypes::{DataType, Field, Schema},
+        ipc::writer::{FileWriter, StreamWriter},
+        record_batch::RecordBatch,
+    };
+
+    use super::from_arrow;
+    use crate::data::{Dataset, Error};
+
+    fn batches() -> (Arc<Schema>, Vec<RecordBatch>) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("x", DataType::Float32, true),
+            Field::new("y", DataType::Int64, false),
+            Field::new("z", DataType::Boolean, false),
+        ]));
+
+        let batch = |x: Vec<Option<f32>>, y: Vec<i64>, z: Vec<bool>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Float32Array::from(x)),
+                    Arc::new(Int64Array::from(y)),
+                    Arc::new(BooleanArray::from(z)),
+                ],
+            )
+            .expect("should build")
+        };
+
+        let batches = vec![
+            batch(vec![Some(0.5), Some(1.5)], vec![1, 0], vec![true, false]),
+            batch(vec![Some(-2.0)], vec![3], vec![true]),
+        ];
+
+        (schema, batches)
+    }
+
+    #[test]
+    fn ipc_file_and_stream() {
+        let (schema, batches) = batches();
+        let file = env::temp_dir().join(format!("micrograd-ipc-{}.arrow", process::id()));
+        let stream = env::temp_dir().join(format!("micrograd-ipc-{}.arrows", process::id()));
+
+        let mut writer = FileWriter::try_new(File::create(&file).expect("should create"), &schema)
+            .expect("should write");
+        let mut stream_writer =
+            StreamWriter::try_new(File::create(&stream).expect("should create"), &schema)
+                .expect("should write");
+        for batch in &batches {
+            writer.write(batch).expect("should write");
+            stream_writer.write(batch).expect("should write");
+        }
+        writer.finish().expect("should write");
+        stream_writer.finish().expect("should write");
+
+        let from_file = from_arrow(&file, &["z", "x"], &["y"]);
+        let from_stream = from_arrow(&stream, &["z", "x"], &["y"]);
+        let missing = from_arrow(&file, &["w"], &["y"]);
+        std::fs::remove_file(&file).expect("should clean up");
+        std::fs::remove_file(&stream).expect("should clean up");
+
+        for dataset in [from_file, from_stream] {
+            let dataset = dataset.expect("should load");
+
+            assert_eq!(dataset.len(), 3);
+            assert_eq!(dataset.get(1), (vec![0.0, 1.5], vec![0.0]));
+            assert_eq!(dataset.get(2), (vec![1.0, -2.0], vec![3.0]));
+        }
+        assert!(matches!(missing, Err(Error::Format(_))));
+    }
+}
@@ -0,0 +1,56 @@
+use super::InMemoryDataset;
+
+const IRIS: &str = include_str!("iris.csv");
+
+/// Names of the Iris classes, in the order of the one-hot targets
+pub const IRIS_CLASSES: [&str; 3] = ["setosa", "versicolor", "virginica"];
+
+/// Names of the Iris features, in centimeters
+pub const IRIS_FEATURES: [&str; 4] = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+
+/// Fisher's Iris dataset: 150 flowers described by 4 features, 50 of each of
+/// the 3 species, with one-hot targets ordered as in [`IRIS_CLASSES`].
+///
+/// Samples are sorted by species, so shuffle or `split` them before training.
+pub fn iris() -> InMemoryDataset {
+    IRIS.lines()
+        .map(|line| {
+            let fields: Vec<_> = line.split(',').collect();
+
+            let x = fields[..4]
+                .iter()
+                .map(|value| value.parse().expect("embedded data should be valid"))
+                .collect();
+            let y = IRIS_CLASSES
+                .iter()
+                .map(|class| if *class == fields[4] { 1.0 } else { 0.0 })
+                .collect();
+
+            (x, y)
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::iris;
+    use crate::data::Dataset;
+
+    #[test]
+    fn iris_dataset() {
+        let iris = iris();
+
+        assert_eq!(iris.len(), 150);
+        assert_eq!(iris.get(0), (vec![5.1, 3.5, 1.4, 0.2], vec![1.0, 0.0, 0.0]));
+        assert_eq!(iris.get(149).1, vec![0.0, 0.0, 1.0]);
+
+        let means: Vec<_> = (0..4)
+            .map(|j| (0..150).map(|i| iris.get(i).0[j]).sum::<f64>() / 150.0)
+            .collect();
+
+        for (mean, expected) in means.iter().zip([5.843333, 3.057333, 3.758, 1.199333]) {
+            assert!((mean - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+use thiserror::Error as ThisError;
+
+use super::Dataset;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid IDX file, {0}")]
+    Format(String),
+    #[error("Found {0} images but {1} labels")]
+    SampleMismatch(usize, usize),
+    #[cfg(feature = "download")]
+    #[error("Failed to download {0}, {1}")]
+    Download(String, String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Which half of the dataset to load
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    Train,
+    Test,
+}
+
+impl Split {
+    fn prefix(self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Test => "t10k",
+        }
+    }
+}
+
+/// Original handwritten digits or the drop-in Fashion-MNIST replacement, both
+/// 28×28 grayscale images in 10 classes
+#[cfg(feature = "download")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Mnist,
+    FashionMnist,
+}
+
+/// Images and labels read from a pair of IDX files.
+///
+/// Samples are the flattened images with pixels scaled into `[0, 1]` and
+/// one-hot encoded labels. Pixels are kept as bytes until a sample is requested.
+#[derive(Debug, Clone)]
+pub struct Mnist {
+    images: Vec<u8>,
+    labels: Vec<u8>,
+    pixels: usize,
+}
+
+impl Mnist {
+    /// Loads `train-*` or `t10k-*` image and label files from `dir`, accepting
+    /// them both plain and gzipped (with a `.gz` extension)
+    pub fn load<P: AsRef<Path>>(dir: P, split: Split) -> Result<Self> {
+        let dir = dir.as_ref();
+        let images = read_idx(dir, &format!("{}-images-idx3-ubyte", split.prefix()), 3)?;
+        let labels = read_idx(dir, &format!("{}-labels-idx1-ubyte", split.prefix()), 1)?;
+
+        if images.dims[0] != labels.dims[0] {
+            return Err(Error::SampleMismatch(images.dims[0], labels.dims[0]));
+        }
+
+        if let Some(label) = labels.data.iter().find(|&&label| label > 9) {
+            return Err(Error::Format(format!("label {label} out of range")));
+        }
+
+        Ok(Self {
+            pixels: images.dims[1] * images.dims[2],
+            images: images.data,
+            labels: labels.data,
+        })
+    }
+
+    /// Downloads the gzipped IDX files of both splits into `dir`, skipping files
+    /// which already exist
+    #[cfg(feature = "download")]
+    pub fn download<P: AsRef<Path>>(dir: P, source: Source) -> Result<()> {
+        let base = match source {
+            Source::Mnist => "https://ossci-datasets.s3.amazonaws.com/mnist/",
+            Source::FashionMnist => "http://fashion-mnist.s3-website.eu-central-1.amazonaws.com/",
+        };
+
+        std::fs::create_dir_all(&dir)?;
+
+        for split in [Split::Train, Split::Test] {
+            for kind in ["images-idx3-ubyte", "labels-idx1-ubyte"] {
+                let name = format!("{}-{kind}.gz", split.prefix());
+                let path = dir.as_ref().join(&name);
+
+                if path.exists() {
+                    continue;
+                }
+
+                let url = format!("{base}{name}");
+                let response = ureq::get(&url)
+                    .call()
+                    .map_err(|err| Error::Download(url.clone(), err.to_string()))?;
+
+                let mut bytes = Vec::new();
+                response.into_reader().read_to_end(&mut bytes)?;
+
+                // interrupted downloads mustn't be mistaken for complete files
+                let partial = path.with_extension("part");
+                std::fs::write(&partial, bytes)?;
+                std::fs::rename(partial, path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of pixels of every image
+    pub fn pixels(&self) -> usize {
+        self.pixels
+    }
+
+    /// Class of the sample at `index`
+    pub fn label(&self, index: usize) -> usize {
+        self.labels[index] as usize
+    }
+}
+
+impl Dataset for Mnist {
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn get(&self, index: usize) -> (Vec<f64>, Vec<f64>) {
+        let image = &self.images[index * self.pixels..(index + 1) * self.pixels];
+
+        let mut target = vec![0.0; 10];
+        target[self.label(index)] = 1.0;
+
+        (image.iter().map(|&p| p as f64 / 255.0).collect(), target)
+    }
+}
+
+struct Idx {
+    dims: Vec<usize>,
+    data: Vec<u8>,
+}
+
+fn read_idx(dir: &Path, name: &str, rank: usize) -> Result<Idx> {
+    let plain = dir.join(name);
+    let gzipped = dir.join(format!("{name}.gz"));
+
+    let mut bytes = Vec::new();
+    if plain.exists() {
+        File::open(plain)?.read_to_end(&mut bytes)?;
+    } else {
+        GzDecoder::new(File::open(gzipped)?).read_to_end(&mut bytes)?;
+    }
+
+    parse_idx(&bytes, rank)
+}
+
+// Big-endian header: two zero bytes, data type (0x08 for u8), number of
+// dimensions, then a u32 size per dimension, followed by the data
+fn parse_idx(bytes: &[u8], rank: usize) -> Result<Idx> {
+    if bytes.len() < 4 || bytes[0..2] != [0, 0] {
+        return Err(Error::Format("missing magic number".to_string()));
+    }
+
+    if bytes[2] != 0x08 {
+        return Err(Error::Format(format!(
+            "unsupported data type {:#04x}",
+            bytes[2]
+        )));
+    }
+
+    if bytes[3] as usize != rank {
+        return Err(Error::Format(format!(
+            "expected {rank} dimensions, found {}",
+            bytes[3]
+        )));
+    }
+
+    let header = 4 + 4 * rank;
+    if bytes.len() < header {
+        return Err(Error::Format("truncated header".to_string()));
+    }
+
+    let dims: Vec<_> = bytes[4..header]
+        .chunks(4)
+        .map(|size| u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize)
+        .collect();
+
+    let size: usize = dims.iter().product();
+    if bytes.len() - header != size {
+        return Err(Error::Format(format!(
+            "expected {size} bytes of data, found {}",
+            bytes.len() - header
+        )));
+    }
+
+    Ok(Idx {
+        dims,
+        data: bytes[header..].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::{parse_idx, Error, Mnist, Split};
+    use crate::data::Dataset;
+
+    fn idx(dims: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 0x08, dims.len() as u8];
+
+        for dim in dims {
+            bytes.extend_from_slice(&dim.to_be_bytes());
+        }
+        bytes.extend_from_slice(data);
+
+        bytes
+    }
+
+    #[test]
+    fn loads_idx_files() {
+        let dir = std::env::temp_dir().join(format!("micrograd-mnist-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("should create dir");
+
+        let images = idx(&[2, 2, 2], &[0, 255, 51, 0, 255, 255, 0, 0]);
+        let labels = idx(&[2], &[7, 1]);
+
+        std::fs::write(dir.join("train-images-idx3-ubyte"), images).expect("should write");
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&labels).expect("should compress");
+        std::fs::write(
+            dir.join("train-labels-idx1-ubyte.gz"),
+            encoder.finish().expect("should compress"),
+        )
+        .expect("should write");
+
+        let mnist = Mnist::load(&dir, Split::Train);
+        std::fs::remove_dir_all(&dir).expect("should clean up");
+        let mnist = mnist.expect("should load");
+
+        assert_eq!(mnist.len(), 2);
+        assert_eq!(mnist.pixels(), 4);
+        assert_eq!(mnist.label(0), 7);
+
+        let (x, y) = mnist.get(0);
+        assert_eq!(x, vec![0.0, 1.0, 0.2, 0.0]);
+        assert_eq!(y[7], 1.0);
+        assert_eq!(y.iter().sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn invalid_idx() {
+        assert!(matches!(parse_idx(&[1, 2, 3], 1), Err(Error::Format(_))));
+        assert!(matches!(
+            parse_idx(&idx(&[3], &[1, 2]), 1),
+            Err(Error::Format(_))
+        ));
+        assert!(matches!(
+            parse_idx(&idx(&[1, 1], &[1]), 1),
+            Err(Error::Format(_))
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::Path;
+
+use super::{Error, InMemoryDataset, Result};
+use crate::numpy::{self, Array};
+
+/// Loads a 2 dimensional `.npy` array whose first `inputs` columns are the
+/// inputs and the remaining ones the targets, a sample per row.
+///
+/// Use [`numpy::load_npy`] to read arrays of any shape.
+pub fn from_npy<P: AsRef<Path>>(path: P, inputs: usize) -> Result<InMemoryDataset> {
+    let array = numpy::load_npy(path)?;
+
+    let columns = match array.shape[..] {
+        [_, columns] if inputs <= columns => columns,
+        _ => {
+            return Err(Error::Format(format!(
+                "expected a 2 dimensional array with at least {inputs} columns, found shape {:?}",
+                array.shape
+            )))
+        }
+    };
+
+    Ok(InMemoryDataset::from(
+        array
+            .data
+            .chunks(columns.max(1))
+            .map(|row| (row[..inputs].to_vec(), row[inputs..].to_vec()))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Loads the `inputs` and `targets` arrays of an `.npz` archive, e.g. written
+/// by `numpy.savez("data.npz", x=x, y=y)`. Rows of 2 dimensional arrays are
+/// samples, 1 dimensional arrays have a single value per sample, e.g. a label.
+pub fn from_npz<P: AsRef<Path>>(path: P, inputs: &str, targets: &str) -> Result<InMemoryDataset> {
+    let arrays = numpy::load_npz(path)?;
+    let samples = |name: &str| {
+        let (_, array) = arrays
+            .iter()
+            .find(|(key, _)| key == name)
+            .ok_or_else(|| Error::Format(format!("missing array {name:?}")))?;
+
+        samples(array).ok_or_else(|| {
+            Error::Format(format!(
+                "expected {name:?} to have 1 or 2 dimensions, found shape {:?}",
+                array.shape
+            ))
+        })
+    };
+
+    InMemoryDataset::new(samples(inputs)?, samples(targets)?)
+}
+
+fn samples(array: &Array) -> Option<Vec<Vec<f64>>> {
+    match array.shape[..] {
+        [_] => Some(array.data.iter().map(|value| vec![*value]).collect()),
+        [_, _] => array.rows(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{from_npy, from_npz};
+    use crate::{
+        data::{Dataset, Error},
+        numpy::tests::{bytes, npy, zip},
+    };
+
+    #[test]
+    fn npy_dataset() {
+        let path = env::temp_dir().join(format!("micrograd-data-{}.npy", process::id()));
+        let data = bytes(&[1.0f32, 2.0, 0.5, 3.0, 4.0, -0.5], f32::to_le_bytes);
+        fs::write(&path, npy("<f4", false, "(2, 3)", &data)).expect("should write");
+
+        let dataset = from_npy(&path, 2);
+        let too_wide = from_npy(&path, 4);
+        fs::remove_file(&path).expect("should clean up");
+
+        let dataset = dataset.expect("should load");
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.get(1), (vec![3.0, 4.0], vec![-0.5]));
+        assert!(matches!(too_wide, Err(Error::Format(_))));
+    }
+
+    #[test]
+    fn npz_dataset() {
+        let path = env::temp_dir().join(format!("micrograd-data-{}.npz", process::id()));
+        let x = npy(
+            "<f8",
+            false,
+            "(3, 2)",
+            &bytes(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], f64::to_le_bytes),
+        );
+        let y = npy(
+            "<i8",
+            false,
+            "(3,)",
+            &bytes(&[0i64, 1, 1], i64::to_le_bytes),
+        );
+        let cube = npy("<f8", false, "(1, 1, 1)", &bytes(&[0.0], f64::to_le_bytes));
+        fs::write(
+            &path,
+            zip(&[("x.npy", x), ("y.npy", y), ("cube.npy", cube)]),
+        )
+        .expect("should write");
+
+        let dataset = from_npz(&path, "x", "y");
+        let missing = from_npz(&path, "x", "labels");
+        let cube = from_npz(&path, "cube", "y");
+        fs::remove_file(&path).expect("should clean up");
+
+        let dataset = dataset.expect("should load");
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(dataset.get(0), (vec![1.0, 2.0], vec![0.0]));
+        assert_eq!(dataset.get(2), (vec![5.0, 6.0], vec![1.0]));
+        assert!(matches!(missing, Err(Error::Format(_))));
+        assert!(matches!(cube, Err(Error::Format(_))));
+    }
+
+    #[test]
+    fn npz_missing_array() {
+        let result = from_npz("/nonexistent/data.npz", "x", "y");
+
+        assert!(matches!(result, Err(Error::Numpy(_))));
+    }
+}
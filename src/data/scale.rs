@@ -0,0 +1,255 @@
+use super::{Dataset, InMemoryDataset};
+
+/// Standardizes every input feature to zero mean and unit variance, using the
+/// statistics of the dataset it was fitted on.
+///
+/// Features which are constant in the fitted data are only centered.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StandardScaler {
+    mean: Vec<f64>,
+    std: Vec<f64>,
+}
+
+impl StandardScaler {
+    /// Computes the mean and (population) standard deviation of every input feature
+    pub fn fit<D: Dataset + ?Sized>(dataset: &D) -> Self {
+        let inputs = inputs(dataset);
+        let n = inputs.len().max(1) as f64;
+        let features = inputs.first().map_or(0, Vec::len);
+
+        let mean: Vec<_> = (0..features)
+            .map(|j| inputs.iter().map(|x| x[j]).sum::<f64>() / n)
+            .collect();
+        let std = (0..features)
+            .map(|j| {
+                let variance = inputs.iter().map(|x| (x[j] - mean[j]).powi(2)).sum::<f64>() / n;
+
+                if variance > 0.0 {
+                    variance.sqrt()
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+
+        Self { mean, std }
+    }
+
+    pub fn mean(&self) -> &[f64] {
+        &self.mean
+    }
+
+    pub fn std(&self) -> &[f64] {
+        &self.std
+    }
+
+    /// Standardizes a single input
+    pub fn transform_input(&self, x: &[f64]) -> Vec<f64> {
+        x.iter()
+            .zip(self.mean.iter().zip(&self.std))
+            .map(|(x, (mean, std))| (x - mean) / std)
+            .collect()
+    }
+
+    /// Maps a standardized input back to the original scale
+    pub fn inverse_transform_input(&self, x: &[f64]) -> Vec<f64> {
+        x.iter()
+            .zip(self.mean.iter().zip(&self.std))
+            .map(|(x, (mean, std))| x * std + mean)
+            .collect()
+    }
+
+    /// A copy of the dataset with standardized inputs and unchanged targets
+    pub fn transform<D: Dataset + ?Sized>(&self, dataset: &D) -> InMemoryDataset {
+        map_inputs(dataset, |x| self.transform_input(x))
+    }
+
+    pub fn inverse_transform<D: Dataset + ?Sized>(&self, dataset: &D) -> InMemoryDataset {
+        map_inputs(dataset, |x| self.inverse_transform_input(x))
+    }
+}
+
+/// Linearly maps every input feature from the range seen in the fitted data into
+/// a target range, `[0, 1]` by default.
+///
+/// Features which are constant in the fitted data map to the lower bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinMaxScaler {
+    min: Vec<f64>,
+    max: Vec<f64>,
+    range: (f64, f64),
+}
+
+impl MinMaxScaler {
+    /// Computes the minimum and maximum of every input feature
+    pub fn fit<D: Dataset + ?Sized>(dataset: &D) -> Self {
+        let inputs = inputs(dataset);
+        let features = inputs.first().map_or(0, Vec::len);
+
+        let column = |j: usize| inputs.iter().map(move |x| x[j]);
+
+        Self {
+            min: (0..features)
+                .map(|j| column(j).fold(f64::INFINITY, f64::min))
+                .collect(),
+            max: (0..features)
+                .map(|j| column(j).fold(f64::NEG_INFINITY, f64::max))
+                .collect(),
+            range: (0.0, 1.0),
+        }
+    }
+
+    /// Target range of the scaled features, e.g. `(-1.0, 1.0)` for tanh networks
+    pub fn range(mut self, low: f64, high: f64) -> Self {
+        self.range = (low, high);
+        self
+    }
+
+    pub fn min(&self) -> &[f64] {
+        &self.min
+    }
+
+    pub fn max(&self) -> &[f64] {
+        &self.max
+    }
+
+    /// Scales a single input into the target range
+    pub fn transform_input(&self, x: &[f64]) -> Vec<f64> {
+        let (low, high) = self.range;
+
+        x.iter()
+            .zip(self.min.iter().zip(&self.max))
+            .map(|(x, (min, max))| low + (x - min) / span(*min, *max) * (high - low))
+            .collect()
+    }
+
+    /// Maps a scaled input back to the original range
+    pub fn inverse_transform_input(&self, x: &[f64]) -> Vec<f64> {
+        let (low, high) = self.range;
+
+        x.iter()
+            .zip(self.min.iter().zip(&self.max))
+            .map(|(x, (min, max))| min + (x - low) / (high - low) * span(*min, *max))
+            .collect()
+    }
+
+    /// A copy of the dataset with scaled inputs and unchanged targets
+    pub fn transform<D: Dataset + ?Sized>(&self, dataset: &D) -> InMemoryDataset {
+        map_inputs(dataset, |x| self.transform_input(x))
+    }
+
+    pub fn inverse_transform<D: Dataset + ?Sized>(&self, dataset: &D) -> InMemoryDataset {
+        map_inputs(dataset, |x| self.inverse_transform_input(x))
+    }
+}
+
+// Width of the fitted range of a feature, 1 for constant features
+fn span(min: f64, max: f64) -> f64 {
+    if max > min {
+        max - min
+    } else {
+        1.0
+    }
+}
+
+fn inputs<D: Dataset + ?Sized>(dataset: &D) -> Vec<Vec<f64>> {
+    (0..dataset.len()).map(|i| dataset.get(i).0).collect()
+}
+
+fn map_inputs<D, F>(dataset: &D, f: F) -> InMemoryDataset
+where
+    D: Dataset + ?Sized,
+    F: Fn(&[f64]) -> Vec<f64>,
+{
+    (0..dataset.len())
+        .map(|i| {
+            let (x, y) = dataset.get(i);
+
+            (f(&x), y)
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MinMaxScaler, StandardScaler};
+    use crate::data::{Dataset, InMemoryDataset};
+
+    fn dataset() -> InMemoryDataset {
+        InMemoryDataset::from(vec![
+            (vec![1.0, 10.0, 5.0], vec![0.0]),
+            (vec![2.0, 20.0, 5.0], vec![1.0]),
+            (vec![3.0, 30.0, 5.0], vec![0.0]),
+            (vec![4.0, 40.0, 5.0], vec![1.0]),
+        ])
+    }
+
+    #[test]
+    fn standard_scaler() {
+        let dataset = dataset();
+        let scaler = StandardScaler::fit(&dataset);
+
+        assert_eq!(scaler.mean(), &[2.5, 25.0, 5.0]);
+        assert_eq!(scaler.std()[2], 1.0);
+
+        let scaled = scaler.transform(&dataset);
+
+        for j in 0..2 {
+            let column: Vec<_> = (0..scaled.len()).map(|i| scaled.get(i).0[j]).collect();
+            let mean = column.iter().sum::<f64>() / 4.0;
+            let variance = column.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 4.0;
+
+            assert!(mean.abs() < 1e-12);
+            assert!((variance - 1.0).abs() < 1e-12);
+        }
+
+        assert_eq!(scaled.get(0).0[2], 0.0);
+        assert_eq!(scaled.get(1).1, vec![1.0]);
+
+        let restored = scaler.inverse_transform(&scaled);
+
+        for i in 0..dataset.len() {
+            let (original, _) = dataset.get(i);
+            let (restored, _) = restored.get(i);
+
+            for (a, b) in original.iter().zip(restored) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn min_max_scaler() {
+        let dataset = dataset();
+        let scaler = MinMaxScaler::fit(&dataset).range(-1.0, 1.0);
+
+        assert_eq!(scaler.min(), &[1.0, 10.0, 5.0]);
+        assert_eq!(scaler.max(), &[4.0, 40.0, 5.0]);
+
+        let scaled = scaler.transform(&dataset);
+
+        assert_eq!(scaled.get(0).0, vec![-1.0, -1.0, -1.0]);
+        assert_eq!(scaled.get(3).0, vec![1.0, 1.0, -1.0]);
+        assert!((scaled.get(1).0[0] + 1.0 / 3.0).abs() < 1e-12);
+
+        // like the standard scaler, features beyond the fitted ones are dropped
+        let wide = [4.0, 40.0, 5.0, 7.0];
+        assert_eq!(scaler.transform_input(&wide), [1.0, 1.0, -1.0]);
+        assert_eq!(
+            StandardScaler::fit(&dataset).transform_input(&wide).len(),
+            3
+        );
+
+        let restored = scaler.inverse_transform(&scaled);
+
+        for i in 0..dataset.len() {
+            let (original, _) = dataset.get(i);
+            let (restored, _) = restored.get(i);
+
+            for (a, b) in original.iter().zip(restored) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+}
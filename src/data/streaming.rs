@@ -0,0 +1,294 @@
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use super::{Dataset, Error, Result};
+
+/// A dataset read from a file on demand, one sample at a time, so that it
+/// never has to fit in memory.
+///
+/// Two formats are supported:
+///
+/// * CSV, one sample per line with the input columns followed by the targets.
+///   The file is validated and indexed by line offsets when opened.
+/// * Binary, fixed size records of little-endian `f64` inputs followed by the
+///   targets, as written by [`StreamingDataset::write_binary`]. Binary files can
+///   also be memory-mapped with the `mmap` feature.
+///
+/// Reading errors after the file was opened (e.g. when it's modified
+/// underneath) panic, like out of bounds indices do.
+pub struct StreamingDataset {
+    source: Source,
+    inputs: usize,
+    targets: usize,
+}
+
+enum Source {
+    Csv {
+        file: RefCell<BufReader<File>>,
+        offsets: Vec<u64>,
+    },
+    Binary {
+        file: RefCell<File>,
+        len: usize,
+    },
+    #[cfg(feature = "mmap")]
+    Mmap(Mmap),
+}
+
+impl StreamingDataset {
+    /// Opens a CSV file whose first `inputs` columns are the inputs and the
+    /// remaining ones the targets, optionally skipping a header line
+    pub fn csv<P: AsRef<Path>>(path: P, inputs: usize, header: bool) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut offsets = Vec::new();
+        let mut columns = None;
+        let mut offset = 0;
+        let mut line = String::new();
+
+        for number in 1.. {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+
+            let start = offset;
+            offset += read as u64;
+
+            if (header && number == 1) || line.trim().is_empty() {
+                continue;
+            }
+
+            let values = parse_csv_line(&line)
+                .map_err(|_| Error::Format(format!("invalid number on line {number}")))?;
+
+            match columns {
+                None if values.len() <= inputs => {
+                    return Err(Error::Format(format!(
+                        "expected more than {inputs} columns, found {} on line {number}",
+                        values.len()
+                    )))
+                }
+                None => columns = Some(values.len()),
+                Some(columns) if columns != values.len() => {
+                    return Err(Error::Format(format!(
+                        "expected {columns} columns, found {} on line {number}",
+                        values.len()
+                    )))
+                }
+                Some(_) => {}
+            }
+
+            offsets.push(start);
+        }
+
+        Ok(Self {
+            source: Source::Csv {
+                file: RefCell::new(reader),
+                offsets,
+            },
+            inputs,
+            targets: columns.map_or(0, |columns| columns - inputs),
+        })
+    }
+
+    /// Opens a binary file of `inputs + targets` values per sample
+    pub fn binary<P: AsRef<Path>>(path: P, inputs: usize, targets: usize) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = record_count(file.metadata()?.len() as usize, inputs + targets)?;
+
+        Ok(Self {
+            source: Source::Binary {
+                file: RefCell::new(file),
+                len,
+            },
+            inputs,
+            targets,
+        })
+    }
+
+    /// Memory-maps a binary file of `inputs + targets` values per sample,
+    /// leaving it to the operating system to page samples in and out
+    #[cfg(feature = "mmap")]
+    pub fn binary_mmap<P: AsRef<Path>>(path: P, inputs: usize, targets: usize) -> Result<Self> {
+        let file = File::open(path)?;
+
+        // SAFETY: the map is read-only. Like any other reading error, changes
+        // to the file while it's mapped are the caller's responsibility.
+        let map = unsafe { Mmap::map(&file)? };
+        record_count(map.len(), inputs + targets)?;
+
+        Ok(Self {
+            source: Source::Mmap(map),
+            inputs,
+            targets,
+        })
+    }
+
+    /// Writes every sample of a dataset into a binary file which can be opened
+    /// with [`StreamingDataset::binary`]
+    pub fn write_binary<D: Dataset + ?Sized, P: AsRef<Path>>(dataset: &D, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        for i in 0..dataset.len() {
+            let (x, y) = dataset.get(i);
+
+            for value in x.iter().chain(&y) {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn record_size(&self) -> usize {
+        (self.inputs + self.targets) * 8
+    }
+
+    fn split(&self, mut values: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
+        let targets = values.split_off(self.inputs);
+
+        (values, targets)
+    }
+}
+
+impl Dataset for StreamingDataset {
+    fn len(&self) -> usize {
+        match &self.source {
+            Source::Csv { offsets, .. } => offsets.len(),
+            Source::Binary { len, .. } => *len,
+            #[cfg(feature = "mmap")]
+            Source::Mmap(map) => map.len() / self.record_size().max(1),
+        }
+    }
+
+    fn get(&self, index: usize) -> (Vec<f64>, Vec<f64>) {
+        assert!(index < self.len(), "sample {index} out of bounds");
+
+        let values = match &self.source {
+            Source::Csv { file, offsets } => {
+                let mut file = file.borrow_mut();
+                let mut line = String::new();
+
+                file.seek(SeekFrom::Start(offsets[index]))
+                    .and_then(|_| file.read_line(&mut line))
+                    .expect("should read from the dataset file");
+
+                parse_csv_line(&line).expect("dataset file should not change")
+            }
+            Source::Binary { file, .. } => {
+                let mut file = file.borrow_mut();
+                let mut bytes = vec![0; self.record_size()];
+
+                file.seek(SeekFrom::Start((index * self.record_size()) as u64))
+                    .and_then(|_| file.read_exact(&mut bytes))
+                    .expect("should read from the dataset file");
+
+                decode(&bytes)
+            }
+            #[cfg(feature = "mmap")]
+            Source::Mmap(map) => {
+                let start = index * self.record_size();
+
+                decode(&map[start..start + self.record_size()])
+            }
+        };
+
+        self.split(values)
+    }
+}
+
+fn parse_csv_line(line: &str) -> std::result::Result<Vec<f64>, std::num::ParseFloatError> {
+    line.trim()
+        .split(',')
+        .map(|value| value.trim().parse())
+        .collect()
+}
+
+fn record_count(bytes: usize, values: usize) -> Result<usize> {
+    let size = values * 8;
+
+    if size == 0 || !bytes.is_multiple_of(size) {
+        return Err(Error::Format(format!(
+            "file size {bytes} isn't a multiple of the record size {size}"
+        )));
+    }
+
+    Ok(bytes / size)
+}
+
+fn decode(bytes: &[u8]) -> Vec<f64> {
+    bytes
+        .chunks_exact(8)
+        .map(|chunk| f64::from_le_bytes(chunk.try_into().expect("chunk should be 8 bytes")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamingDataset;
+    use crate::data::{DataLoader, Dataset, Error, InMemoryDataset};
+
+    fn path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("micrograd-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn csv() {
+        let path = path("streaming.csv");
+        std::fs::write(&path, "a,b,y\n1,2,3\n4.5,-5,6\n\n7,8,9\n").expect("should write");
+
+        let dataset = StreamingDataset::csv(&path, 2, true).expect("should open");
+
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(dataset.get(1), (vec![4.5, -5.0], vec![6.0]));
+        assert_eq!(dataset.get(2), (vec![7.0, 8.0], vec![9.0]));
+
+        let batches: Vec<_> = DataLoader::new(&dataset, 2).epoch().collect();
+        assert_eq!(batches[0].targets, vec![vec![3.0], vec![6.0]]);
+
+        std::fs::write(&path, "1,2,3\n4,5\n").expect("should write");
+        let ragged = StreamingDataset::csv(&path, 2, false);
+
+        std::fs::remove_file(&path).expect("should clean up");
+
+        assert!(matches!(ragged, Err(Error::Format(_))));
+    }
+
+    #[test]
+    fn binary() {
+        let path = path("streaming.bin");
+        let data = InMemoryDataset::from(vec![
+            (vec![1.0, 2.0], vec![0.5]),
+            (vec![3.0, 4.0], vec![-0.5]),
+        ]);
+
+        StreamingDataset::write_binary(&data, &path).expect("should write");
+        let dataset = StreamingDataset::binary(&path, 2, 1).expect("should open");
+
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.get(1), data.get(1));
+
+        #[cfg(feature = "mmap")]
+        {
+            let mapped = StreamingDataset::binary_mmap(&path, 2, 1).expect("should map");
+
+            assert_eq!(mapped.get(0), data.get(0));
+        }
+
+        let mismatched = StreamingDataset::binary(&path, 2, 2);
+
+        std::fs::remove_file(&path).expect("should clean up");
+
+        assert!(matches!(mismatched, Err(Error::Format(_))));
+    }
+}
@@ -0,0 +1,210 @@
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{Dataset, Error, MinMaxScaler, Result, StandardScaler};
+
+/// An `(input, target)` pair
+pub type Sample = (Vec<f64>, Vec<f64>);
+
+/// Whether samples are transformed for training, with augmentation, or for
+/// evaluation without it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Train,
+    Eval,
+}
+
+/// A per-sample preprocessing or augmentation step.
+///
+/// Closures taking and returning a `Sample` are transforms too.
+pub trait Transform {
+    /// Transforms one sample, drawing any randomness from `rng`
+    fn apply(&self, sample: Sample, rng: &mut dyn RngCore) -> Sample;
+}
+
+impl<F: Fn(Sample) -> Sample> Transform for F {
+    fn apply(&self, sample: Sample, _rng: &mut dyn RngCore) -> Sample {
+        self(sample)
+    }
+}
+
+impl Transform for StandardScaler {
+    fn apply(&self, (x, y): Sample, _rng: &mut dyn RngCore) -> Sample {
+        (self.transform_input(&x), y)
+    }
+}
+
+impl Transform for MinMaxScaler {
+    fn apply(&self, (x, y): Sample, _rng: &mut dyn RngCore) -> Sample {
+        (self.transform_input(&x), y)
+    }
+}
+
+/// Adds Gaussian noise with standard deviation `std` to every input feature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaussianNoise {
+    pub std: f64,
+}
+
+impl Transform for GaussianNoise {
+    fn apply(&self, (x, y): Sample, rng: &mut dyn RngCore) -> Sample {
+        let x = x
+            .into_iter()
+            .map(|x| {
+                // Box-Muller transform
+                let u: f64 = 1.0 - rng.gen::<f64>();
+                let v: f64 = rng.gen();
+
+                x + self.std * (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+            })
+            .collect();
+
+        (x, y)
+    }
+}
+
+/// Replaces a single class index target by its one-hot encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OneHot {
+    classes: usize,
+}
+
+impl OneHot {
+    /// Fails without any classes to encode
+    pub fn new(classes: usize) -> Result<Self> {
+        if classes == 0 {
+            return Err(Error::NoClasses);
+        }
+
+        Ok(Self { classes })
+    }
+
+    pub fn classes(&self) -> usize {
+        self.classes
+    }
+}
+
+impl Transform for OneHot {
+    fn apply(&self, (x, y): Sample, _rng: &mut dyn RngCore) -> Sample {
+        let mut target = vec![0.0; self.classes];
+
+        if let Some(class) = y.first() {
+            target[(*class as usize).min(self.classes - 1)] = 1.0;
+        }
+
+        (x, target)
+    }
+}
+
+/// A sequence of transforms applied to every sample in order.
+///
+/// Steps added with `augment` only run in the training phase, e.g.
+/// `Pipeline::new().then(scaler).augment(GaussianNoise { std: 0.1 }).then(OneHot::new(3)?)`.
+#[derive(Default)]
+pub struct Pipeline {
+    steps: Vec<(Box<dyn Transform>, Phase)>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a step applied in both phases
+    pub fn then<T: Transform + 'static>(mut self, transform: T) -> Self {
+        self.steps.push((Box::new(transform), Phase::Eval));
+        self
+    }
+
+    /// Adds a step applied during training only
+    pub fn augment<T: Transform + 'static>(mut self, transform: T) -> Self {
+        self.steps.push((Box::new(transform), Phase::Train));
+        self
+    }
+
+    pub fn apply(&self, sample: Sample, phase: Phase, rng: &mut dyn RngCore) -> Sample {
+        self.steps
+            .iter()
+            .filter(|(_, only)| phase == Phase::Train || *only == Phase::Eval)
+            .fold(sample, |sample, (transform, _)| {
+                transform.apply(sample, rng)
+            })
+    }
+}
+
+/// A view of a dataset with every sample passed through the evaluation phase
+/// of a pipeline
+pub struct Transformed<'a, D: ?Sized> {
+    dataset: &'a D,
+    pipeline: &'a Pipeline,
+}
+
+impl<'a, D: Dataset + ?Sized> Transformed<'a, D> {
+    pub fn new(dataset: &'a D, pipeline: &'a Pipeline) -> Self {
+        Self { dataset, pipeline }
+    }
+}
+
+impl<D: Dataset + ?Sized> Dataset for Transformed<'_, D> {
+    fn len(&self) -> usize {
+        self.dataset.len()
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        // evaluation steps are deterministic, the generator is never used
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        self.pipeline
+            .apply(self.dataset.get(index), Phase::Eval, &mut rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{GaussianNoise, OneHot, Phase, Pipeline, Sample, Transform, Transformed};
+    use crate::data::{Dataset, Error, InMemoryDataset, StandardScaler};
+
+    #[test]
+    fn pipeline_phases() {
+        let pipeline = Pipeline::new()
+            .then(|(x, y): Sample| (x.iter().map(|x| x * 2.0).collect(), y))
+            .augment(GaussianNoise { std: 0.5 })
+            .then(OneHot::new(3).expect("should build"));
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        let eval = pipeline.apply((vec![1.0, 2.0], vec![2.0]), Phase::Eval, &mut rng);
+        assert_eq!(eval, (vec![2.0, 4.0], vec![0.0, 0.0, 1.0]));
+
+        let (x, y) = pipeline.apply((vec![1.0, 2.0], vec![1.0]), Phase::Train, &mut rng);
+        assert_ne!(x, vec![2.0, 4.0]);
+        assert!((x[0] - 2.0).abs() < 3.0);
+        assert_eq!(y, vec![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn one_hot() {
+        let one_hot = OneHot::new(2).expect("should build");
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        assert_eq!(one_hot.classes(), 2);
+        assert_eq!(one_hot.apply((vec![], vec![5.0]), &mut rng).1, [0.0, 1.0]);
+
+        assert!(matches!(OneHot::new(0), Err(Error::NoClasses)));
+    }
+
+    #[test]
+    fn transformed_dataset() {
+        let dataset = InMemoryDataset::from(vec![(vec![1.0], vec![0.0]), (vec![3.0], vec![1.0])]);
+        let pipeline = Pipeline::new()
+            .then(StandardScaler::fit(&dataset))
+            .augment(GaussianNoise { std: 1.0 });
+
+        let transformed = Transformed::new(&dataset, &pipeline);
+
+        assert_eq!(transformed.len(), 2);
+        assert_eq!(transformed.get(0), (vec![-1.0], vec![0.0]));
+        assert_eq!(transformed.get(1), (vec![1.0], vec![1.0]));
+    }
+}
@@ -0,0 +1,83 @@
+use super::{Error, InMemoryDataset, Result};
+
+/// Supervised samples for forecasting from a 1D series: every run of
+/// `input_len` consecutive values as the input, and the value `horizon` steps
+/// after its last one as the target, e.g. with an input length of 2 and a
+/// horizon of 1
+///
+/// ```text
+/// [1, 2, 3, 4] -> [1, 2] => [3], [2, 3] => [4]
+/// ```
+///
+/// A series too short for a single window gives an empty dataset.
+pub fn windows(series: &[f64], input_len: usize, horizon: usize) -> Result<InMemoryDataset> {
+    if input_len == 0 || horizon == 0 {
+        return Err(Error::Window(input_len, horizon));
+    }
+
+    let span = input_len + horizon;
+    let samples = (0..(series.len() + 1).saturating_sub(span))
+        .map(|start| {
+            let end = start + input_len;
+
+            (series[start..end].to_vec(), vec![series[end + horizon - 1]])
+        })
+        .collect::<Vec<_>>();
+
+    Ok(InMemoryDataset::from(samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::windows;
+    use crate::data::{Dataset, Error};
+
+    #[test]
+    fn sliding_windows() {
+        let series = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+        let next = windows(&series, 2, 1).expect("should window");
+        assert_eq!(next.len(), 3);
+        assert_eq!(next.get(0), (vec![1.0, 2.0], vec![3.0]));
+        assert_eq!(next.get(2), (vec![3.0, 4.0], vec![5.0]));
+
+        let ahead = windows(&series, 2, 3).expect("should window");
+        assert_eq!(ahead.len(), 1);
+        assert_eq!(ahead.get(0), (vec![1.0, 2.0], vec![5.0]));
+
+        assert!(windows(&series, 3, 3).expect("should window").is_empty());
+        assert!(windows(&[], 1, 1).expect("should window").is_empty());
+        assert!(matches!(windows(&series, 0, 1), Err(Error::Window(0, 1))));
+        assert!(matches!(windows(&series, 2, 0), Err(Error::Window(2, 0))));
+    }
+
+    #[test]
+    fn forecasts_sine() {
+        use crate::{
+            loss::Loss,
+            nn::Mlp,
+            optim::AdamW,
+            train::{TrainConfig, Trainer},
+        };
+
+        let series: Vec<_> = (0..60).map(|t| (t as f64 * 0.3).sin() * 0.8).collect();
+        let data = windows(&series, 4, 1).expect("should window");
+
+        let mlp = Mlp::new_seeded(4, &[8, 1], 1);
+        let adam = AdamW::new(mlp.parameters(), 0.02, 0.0);
+        let mut trainer = Trainer::new(mlp, adam, Loss::SquaredError).config(TrainConfig {
+            batch_size: Some(8),
+            seed: Some(1),
+            ..Default::default()
+        });
+        trainer.fit(&data, 150).expect("should train");
+
+        // the next value continues the sine
+        let last = &series[series.len() - 4..];
+        let forecast = trainer.model().infer(last).expect("should infer")[0];
+        assert!(
+            (forecast - (60.0f64 * 0.3).sin() * 0.8).abs() < 0.1,
+            "{forecast}"
+        );
+    }
+}
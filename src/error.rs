@@ -0,0 +1,101 @@
+use thiserror::Error as ThisError;
+
+#[cfg(feature = "std")]
+use crate::{calibration, checkpoint, data, lm, loss, onnx, optim, rl, train, tune};
+use crate::{nn, value};
+
+/// Any error of the crate, for applications which pass errors of several
+/// modules up with `?`. Every module also has its own, narrower error type.
+#[derive(ThisError, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    #[error(transparent)]
+    Value(#[from] value::Error),
+    #[error(transparent)]
+    Model(#[from] nn::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Calibration(#[from] calibration::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Checkpoint(#[from] checkpoint::Error),
+    #[cfg(feature = "config")]
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Data(#[from] data::Error),
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Json(#[from] nn::json::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    LanguageModel(#[from] lm::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Loss(#[from] loss::Error),
+    #[cfg(feature = "mnist")]
+    #[error(transparent)]
+    Mnist(#[from] data::mnist::Error),
+    #[cfg(feature = "numpy")]
+    #[error(transparent)]
+    Numpy(#[from] crate::numpy::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Onnx(#[from] onnx::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Optim(#[from] optim::Error),
+    #[cfg(feature = "plot")]
+    #[error(transparent)]
+    Plot(#[from] crate::plot::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Rl(#[from] rl::Error),
+    #[cfg(feature = "serve")]
+    #[error(transparent)]
+    Serve(#[from] crate::serve::Error),
+    #[cfg(feature = "torch")]
+    #[error(transparent)]
+    Torch(#[from] nn::torch::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Train(#[from] train::Error),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Tune(#[from] tune::Error),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Result};
+    use crate::{
+        data::{Dataset, InMemoryDataset},
+        nn::Mlp,
+        value::Value,
+    };
+
+    fn run() -> Result<f64> {
+        let dataset = InMemoryDataset::new(vec![vec![1.0, 2.0]], vec![vec![1.0]])?;
+        let quotient = Value::new(1.0, "one").try_div(Value::new(2.0, "two"))?;
+        let output = Mlp::new_seeded(3, &[1], 0).infer(&[1.0, 2.0])?;
+
+        Ok(quotient.value() + output[0] + dataset.len() as f64)
+    }
+
+    #[test]
+    fn unified_error() {
+        let error = run().expect_err("should fail");
+
+        assert!(matches!(error, Error::Model(_)));
+        assert_eq!(
+            error.to_string(),
+            "Dimension mismatch in layer 0 (input), expected 3 inputs, got 2"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! A scalar-valued automatic differentiation engine with a small neural network
+//! library on top, following Andrej Karpathy's micrograd.
+//!
+//! The [`prelude`] brings the types needed to build and train a model into scope.
+//!
+//! Without the default `std` feature the crate is `no_std` and only needs an
+//! allocator. Only [`value`] and [`nn`] are available then, enough to run a
+//! trained model, e.g. restored with `Mlp::load_parameters`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod calibration;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod data;
+mod error;
+#[cfg(feature = "std")]
+pub mod lm;
+#[cfg(feature = "std")]
+pub mod loss;
+mod math;
+#[cfg(feature = "std")]
+pub mod metrics;
+pub mod nn;
+#[cfg(feature = "numpy")]
+pub mod numpy;
+#[cfg(feature = "std")]
+pub mod onnx;
+#[cfg(feature = "std")]
+pub mod optim;
+#[cfg(feature = "plot")]
+pub mod plot;
+#[cfg(feature = "std")]
+mod protobuf;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+pub mod rl;
+#[cfg(feature = "serve")]
+pub mod serve;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "std")]
+pub mod train;
+#[cfg(feature = "std")]
+pub mod tune;
+pub mod value;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::{Error, Result};
+
+/// The most commonly used types, e.g. `use micrograd::prelude::*;`
+pub mod prelude {
+    #[cfg(feature = "std")]
+    pub use crate::{
+        data::{DataLoader, Dataset, InMemoryDataset},
+        loss::{Loss, Reduction},
+        metrics::Metric,
+        optim::{AdamW, Optimizer, Sgd},
+        train::{TrainConfig, Trainer},
+    };
+    pub use crate::{nn::Mlp, value::Value};
+}
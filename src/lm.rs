@@ -0,0 +1,472 @@
+//! Character-level language modeling in the style of Andrej Karpathy's
+//! makemore: a model reads the last few characters of a word and predicts the
+//! next one, and sampling one character after another makes up new words.
+//!
+//! The model embeds every character of the context, passes the concatenated
+//! vectors through a `tanh` hidden layer and scores each character with a
+//! linear output layer, trained with cross-entropy. See the `names` example.
+
+mod generate;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error as ThisError;
+
+use crate::{
+    loss::{self, cross_entropy},
+    nn::{self, Embedding, Mlp},
+    optim::Optimizer,
+    value::Value,
+};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Model(#[from] nn::Error),
+    #[error(transparent)]
+    Loss(#[from] loss::Error),
+    #[error("Unknown character {0:?}")]
+    UnknownChar(char),
+    #[error("Unknown token {0}, expected one of {1}")]
+    UnknownToken(usize, usize),
+    #[error("Context of {0} tokens, expected {1}")]
+    ContextLength(usize, usize),
+    #[error("Invalid sampling, {0}")]
+    Sampling(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub use generate::{generate, GenerateConfig, Sampling};
+
+/// Stands in for characters missing from the vocabulary, when added with
+/// [`Tokenizer::with_specials`]
+pub const UNKNOWN: &str = "<unk>";
+
+/// Maps the characters of a set of words to tokens. Token 0, written as `.`,
+/// marks the start and the end of a word. Special tokens follow it, in the
+/// order they were added, then the characters in sorted order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tokenizer {
+    specials: Vec<String>,
+    chars: Vec<char>,
+}
+
+impl Tokenizer {
+    pub const BOUNDARY: usize = 0;
+
+    /// Collects the characters of `words`, in sorted order
+    pub fn fit<S: AsRef<str>>(words: &[S]) -> Self {
+        let mut chars: Vec<_> = words
+            .iter()
+            .flat_map(|word| word.as_ref().chars())
+            .collect();
+        chars.sort_unstable();
+        chars.dedup();
+
+        Self {
+            specials: Vec::new(),
+            chars,
+        }
+    }
+
+    /// Adds special tokens, e.g. [`UNKNOWN`] to encode characters missing
+    /// from the vocabulary rather than fail on them
+    pub fn with_specials(mut self, specials: &[&str]) -> Self {
+        self.specials
+            .extend(specials.iter().map(|special| special.to_string()));
+        self
+    }
+
+    /// Number of tokens, including the boundary and the special tokens
+    pub fn vocab_size(&self) -> usize {
+        1 + self.specials.len() + self.chars.len()
+    }
+
+    /// The token of the special token `name`, if the vocabulary has it
+    pub fn special(&self, name: &str) -> Option<usize> {
+        self.specials
+            .iter()
+            .position(|special| special == name)
+            .map(|index| index + 1)
+    }
+
+    /// Whether `token` is the boundary or a special token
+    pub fn is_special(&self, token: usize) -> bool {
+        token <= self.specials.len()
+    }
+
+    pub fn encode(&self, word: &str) -> Result<Vec<usize>> {
+        let unknown = self.special(UNKNOWN);
+
+        word.chars()
+            .map(|c| match self.chars.binary_search(&c) {
+                Ok(index) => Ok(1 + self.specials.len() + index),
+                Err(_) => unknown.ok_or(Error::UnknownChar(c)),
+            })
+            .collect()
+    }
+
+    /// The characters of `tokens`, with boundaries as `.` and special tokens
+    /// written by name. Tokens beyond the vocabulary, which
+    /// [`Tokenizer::one_hot`] rejects, are written as `?`.
+    pub fn decode(&self, tokens: &[usize]) -> String {
+        let mut text = String::new();
+
+        for &token in tokens {
+            match token {
+                Self::BOUNDARY => text.push('.'),
+                token if self.is_special(token) => text.push_str(&self.specials[token - 1]),
+                token => text.push(
+                    self.chars
+                        .get(token - 1 - self.specials.len())
+                        .copied()
+                        .unwrap_or('?'),
+                ),
+            }
+        }
+
+        text
+    }
+
+    /// A cross-entropy target putting all weight on `token`
+    pub fn one_hot(&self, token: usize) -> Result<Vec<f64>> {
+        if token >= self.vocab_size() {
+            return Err(Error::UnknownToken(token, self.vocab_size()));
+        }
+
+        let mut target = vec![0.0; self.vocab_size()];
+        target[token] = 1.0;
+
+        Ok(target)
+    }
+}
+
+/// A training example, the `block_size` tokens preceding every character and
+/// the end of every word, padded with boundaries, and the token following them
+pub fn contexts<S: AsRef<str>>(
+    tokenizer: &Tokenizer,
+    words: &[S],
+    block_size: usize,
+) -> Result<Vec<(Vec<usize>, usize)>> {
+    let mut examples = Vec::new();
+
+    for word in words {
+        let mut context = vec![Tokenizer::BOUNDARY; block_size];
+
+        for token in tokenizer
+            .encode(word.as_ref())?
+            .into_iter()
+            .chain([Tokenizer::BOUNDARY])
+        {
+            examples.push((context.clone(), token));
+
+            if block_size > 0 {
+                context.remove(0);
+                context.push(token);
+            }
+        }
+    }
+
+    Ok(examples)
+}
+
+/// Embedding, hidden layer and linear output layer scoring the next character
+pub struct CharMlp {
+    block_size: usize,
+    embedding: Embedding,
+    hidden: Mlp,
+    output: Vec<(Vec<Value>, Value)>,
+}
+
+impl CharMlp {
+    pub fn new<R: Rng>(
+        vocab_size: usize,
+        block_size: usize,
+        dimensions: usize,
+        hidden: usize,
+        rng: &mut R,
+    ) -> Self {
+        let embedding = Embedding::new(vocab_size, dimensions, rng);
+        let hidden_layer = Mlp::new(block_size * dimensions, &[hidden], rng);
+        let output = (0..vocab_size)
+            .map(|token| {
+                let weights = (0..hidden)
+                    .map(|i| Value::new(rng.gen_range(-1.0..=1.0), &format!("o_{token}_{i}")))
+                    .collect();
+
+                (weights, Value::new(0.0, &format!("ob_{token}")))
+            })
+            .collect();
+
+        Self {
+            block_size,
+            embedding,
+            hidden: hidden_layer,
+            output,
+        }
+    }
+
+    /// Builds a model with weights fully determined by `seed`
+    pub fn new_seeded(
+        vocab_size: usize,
+        block_size: usize,
+        dimensions: usize,
+        hidden: usize,
+        seed: u64,
+    ) -> Self {
+        Self::new(
+            vocab_size,
+            block_size,
+            dimensions,
+            hidden,
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        )
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Unnormalized scores of every token following `context`
+    pub fn logits(&self, context: &[usize]) -> Result<Vec<Value>> {
+        if context.len() != self.block_size {
+            return Err(Error::ContextLength(context.len(), self.block_size));
+        }
+
+        let hidden = self.hidden.predict(&self.embedding.lookup_all(context)?)?;
+
+        // labels spell out whole expressions, which would add up to megabytes
+        // of text over the softmax of a batch
+        for (i, h) in hidden.iter().enumerate() {
+            h.set_label(&format!("h_{i}"));
+        }
+
+        Ok(self
+            .output
+            .iter()
+            .enumerate()
+            .map(|(token, (weights, bias))| {
+                let logit = bias.clone() + Value::dot(weights, &hidden);
+                logit.set_label(&format!("logit_{token}"));
+
+                logit
+            })
+            .collect())
+    }
+
+    /// Mean cross-entropy of the model's predictions for `examples`, built by
+    /// [`contexts`]
+    pub fn loss(&self, examples: &[(Vec<usize>, usize)]) -> Result<Value> {
+        let logits = examples
+            .iter()
+            .map(|(context, _)| self.logits(context))
+            .collect::<Result<Vec<_>>>()?;
+        let targets: Vec<_> = examples
+            .iter()
+            .map(|&(_, next)| {
+                let mut target = vec![0.0; self.output.len()];
+                target[next] = 1.0;
+                target
+            })
+            .collect();
+
+        let loss = cross_entropy(&targets, &logits, None, None)?;
+
+        Ok(loss * Value::new(1.0 / examples.len().max(1) as f64, "1/n"))
+    }
+
+    /// Takes an optimizer step on the loss of a batch of examples, returning it
+    pub fn train_step(
+        &self,
+        optimizer: &mut dyn Optimizer,
+        batch: &[(Vec<usize>, usize)],
+    ) -> Result<f64> {
+        let loss = self.loss(batch)?;
+
+        loss.backpropagate();
+        optimizer.step();
+        optimizer.zero_grad();
+
+        Ok(loss.value())
+    }
+
+    /// Makes up a word, one sampled character at a time, until the model
+    /// predicts its end or it reaches `max_len` characters
+    pub fn sample<R: Rng + ?Sized>(
+        &self,
+        tokenizer: &Tokenizer,
+        max_len: usize,
+        rng: &mut R,
+    ) -> Result<String> {
+        let mut context = vec![Tokenizer::BOUNDARY; self.block_size];
+        let mut tokens = Vec::new();
+
+        while tokens.len() < max_len {
+            let logits: Vec<_> = self.logits(&context)?.iter().map(Value::value).collect();
+            let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let weights: Vec<_> = logits.iter().map(|l| (l - max).exp()).collect();
+
+            let mut threshold = rng.gen::<f64>() * weights.iter().sum::<f64>();
+            let token = weights
+                .iter()
+                .position(|w| {
+                    threshold -= w;
+                    threshold < 0.0
+                })
+                .unwrap_or(weights.len() - 1);
+
+            if token == Tokenizer::BOUNDARY {
+                break;
+            }
+
+            tokens.push(token);
+            if self.block_size > 0 {
+                context.remove(0);
+                context.push(token);
+            }
+        }
+
+        Ok(tokenizer.decode(&tokens))
+    }
+
+    /// Makes up a word with [`generate`], ending it when the model predicts
+    /// its end, at the stop token of `config` or after `config.max_len`
+    /// characters
+    pub fn generate(&self, tokenizer: &Tokenizer, config: &GenerateConfig) -> Result<String> {
+        let scores = |tokens: &[usize]| -> Result<Vec<f64>> {
+            let context: Vec<_> = [Tokenizer::BOUNDARY]
+                .repeat(self.block_size.saturating_sub(tokens.len()))
+                .into_iter()
+                .chain(tokens.iter().copied())
+                .skip(tokens.len().saturating_sub(self.block_size))
+                .collect();
+
+            Ok(self.logits(&context)?.iter().map(Value::value).collect())
+        };
+
+        let tokens = generate(scores, &[], config)?;
+        let end = tokens
+            .iter()
+            .position(|&token| token == Tokenizer::BOUNDARY)
+            .unwrap_or(tokens.len());
+
+        Ok(tokenizer.decode(&tokens[..end]))
+    }
+
+    /// Embedding vectors, then the hidden and the output layer
+    pub fn parameters(&self) -> Vec<Value> {
+        let mut parameters = self.embedding.parameters();
+        parameters.extend(self.hidden.parameters());
+        for (weights, bias) in &self.output {
+            parameters.extend(weights.iter().cloned());
+            parameters.push(bias.clone());
+        }
+
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{contexts, CharMlp, Error, GenerateConfig, Sampling, Tokenizer, UNKNOWN};
+    use crate::optim::AdamW;
+
+    #[test]
+    fn tokenizer() {
+        let tokenizer = Tokenizer::fit(&["emma", "ava"]);
+
+        assert_eq!(tokenizer.vocab_size(), 5);
+        assert_eq!(tokenizer.encode("ava").expect("should encode"), [1, 4, 1]);
+        assert_eq!(tokenizer.decode(&[0, 2, 3, 0]), ".em.");
+        assert_eq!(tokenizer.decode(&[1, 5]), "a?");
+        assert!(matches!(
+            tokenizer.encode("bob"),
+            Err(Error::UnknownChar('b'))
+        ));
+
+        let examples = contexts(&tokenizer, &["ava"], 2).expect("should build");
+        assert_eq!(
+            examples,
+            [
+                (vec![0, 0], 1),
+                (vec![0, 1], 4),
+                (vec![1, 4], 1),
+                (vec![4, 1], 0)
+            ]
+        );
+        assert_eq!(
+            contexts(&tokenizer, &["av"], 0).expect("should build"),
+            [(vec![], 1), (vec![], 4), (vec![], 0)]
+        );
+
+        assert_eq!(
+            tokenizer.one_hot(4).expect("should encode"),
+            [0.0, 0.0, 0.0, 0.0, 1.0]
+        );
+        assert!(matches!(
+            tokenizer.one_hot(5),
+            Err(Error::UnknownToken(5, 5))
+        ));
+    }
+
+    #[test]
+    fn special_tokens() {
+        let tokenizer = Tokenizer::fit(&["emma", "ava"]).with_specials(&["<pad>", UNKNOWN]);
+
+        assert_eq!(tokenizer.vocab_size(), 7);
+        assert_eq!(tokenizer.special("<pad>"), Some(1));
+        assert_eq!(tokenizer.special(UNKNOWN), Some(2));
+        assert_eq!(tokenizer.special("<mask>"), None);
+        assert!(tokenizer.is_special(Tokenizer::BOUNDARY));
+        assert!(!tokenizer.is_special(3));
+
+        let tokens = tokenizer.encode("bav").expect("should encode");
+        assert_eq!(tokens, [2, 3, 6]);
+        assert_eq!(tokenizer.decode(&tokens), "<unk>av");
+        assert_eq!(tokenizer.decode(&[0, 1, 3, 0]), ".<pad>a.");
+    }
+
+    #[test]
+    fn char_model() {
+        let words = ["abc", "abd", "abc"];
+        let tokenizer = Tokenizer::fit(&words);
+        let examples = contexts(&tokenizer, &words, 2).expect("should build");
+
+        let model = CharMlp::new_seeded(tokenizer.vocab_size(), 2, 2, 8, 1);
+        let mut optimizer = AdamW::new(model.parameters(), 0.05, 0.0);
+
+        let before = model.loss(&examples).expect("should compute").value();
+        for _ in 0..100 {
+            model
+                .train_step(&mut optimizer, &examples)
+                .expect("should train");
+        }
+        let after = model.loss(&examples).expect("should compute").value();
+        assert!(after < before / 2.0, "loss went from {before} to {after}");
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let word = model
+            .sample(&tokenizer, 10, &mut rng)
+            .expect("should sample");
+        assert!(word.starts_with("ab"), "sampled {word:?}");
+
+        let greedy = GenerateConfig {
+            sampling: Sampling::Greedy,
+            max_len: 10,
+            ..Default::default()
+        };
+        let word = model
+            .generate(&tokenizer, &greedy)
+            .expect("should generate");
+        assert!(word == "abc" || word == "abd", "generated {word:?}");
+
+        assert!(matches!(
+            model.logits(&[0]),
+            Err(Error::ContextLength(1, 2))
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{Error, Result};
+
+/// How [`generate`] picks every next token from the scores of the model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// The most likely token, the first one on ties
+    Greedy,
+    /// A token drawn from the softmax of the scores divided by the
+    /// temperature. Temperatures below 1 sharpen the distribution, above 1
+    /// flatten it.
+    Temperature(f64),
+    /// A token drawn like with `Temperature`, but only among the `k` most
+    /// likely ones
+    TopK { k: usize, temperature: f64 },
+}
+
+/// Settings of [`generate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerateConfig {
+    pub sampling: Sampling,
+    /// Most tokens to generate
+    pub max_len: usize,
+    /// Token ending the generation, which isn't included in the output
+    pub stop_token: Option<usize>,
+    /// Seed of the random draws, the same seed generates the same tokens
+    pub seed: u64,
+}
+
+impl Default for GenerateConfig {
+    fn default() -> Self {
+        Self {
+            sampling: Sampling::Temperature(1.0),
+            max_len: 100,
+            stop_token: None,
+            seed: 0,
+        }
+    }
+}
+
+/// Generates tokens one at a time, each picked from the scores (logits)
+/// `model` returns for all the tokens so far, `prompt` first. Returns the
+/// generated tokens without the prompt, stopping at the stop token or after
+/// `max_len` tokens.
+pub fn generate<F>(mut model: F, prompt: &[usize], config: &GenerateConfig) -> Result<Vec<usize>>
+where
+    F: FnMut(&[usize]) -> Result<Vec<f64>>,
+{
+    match config.sampling {
+        Sampling::Temperature(temperature) | Sampling::TopK { temperature, .. }
+            if temperature <= 0.0 || temperature.is_nan() =>
+        {
+            return Err(Error::Sampling(format!(
+                "temperature {temperature} isn't positive"
+            )));
+        }
+        Sampling::TopK { k: 0, .. } => {
+            return Err(Error::Sampling("top-k with k = 0".to_string()));
+        }
+        _ => {}
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+    let mut tokens = prompt.to_vec();
+
+    while tokens.len() - prompt.len() < config.max_len {
+        let logits = model(&tokens)?;
+        let token = pick(&logits, config.sampling, &mut rng);
+
+        if Some(token) == config.stop_token {
+            break;
+        }
+
+        tokens.push(token);
+    }
+
+    Ok(tokens.split_off(prompt.len()))
+}
+
+fn pick<R: Rng>(logits: &[f64], sampling: Sampling, rng: &mut R) -> usize {
+    let (k, temperature) = match sampling {
+        Sampling::Greedy => {
+            return (0..logits.len())
+                .fold(0, |best, i| if logits[i] > logits[best] { i } else { best });
+        }
+        Sampling::Temperature(temperature) => (logits.len(), temperature),
+        Sampling::TopK { k, temperature } => (k, temperature),
+    };
+
+    // the k most likely tokens, the first ones on ties
+    let mut candidates: Vec<_> = (0..logits.len()).collect();
+    candidates.sort_by(|&a, &b| logits[b].total_cmp(&logits[a]));
+    candidates.truncate(k);
+
+    let max = candidates.first().map_or(0.0, |&best| logits[best]);
+    let weights: Vec<_> = candidates
+        .iter()
+        .map(|&i| ((logits[i] - max) / temperature).exp())
+        .collect();
+
+    let mut threshold = rng.gen::<f64>() * weights.iter().sum::<f64>();
+    let position = weights
+        .iter()
+        .position(|w| {
+            threshold -= w;
+            threshold < 0.0
+        })
+        .unwrap_or(weights.len().saturating_sub(1));
+
+    candidates.get(position).copied().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, GenerateConfig, Sampling};
+    use crate::lm::Error;
+
+    // scores favouring the token after the last one, cycling through 4 tokens
+    fn model(tokens: &[usize]) -> crate::lm::Result<Vec<f64>> {
+        let next = (tokens.last().copied().unwrap_or(0) + 1) % 4;
+
+        Ok((0..4).map(|t| if t == next { 2.0 } else { 0.0 }).collect())
+    }
+
+    #[test]
+    fn sampling() {
+        let greedy = GenerateConfig {
+            sampling: Sampling::Greedy,
+            max_len: 6,
+            ..Default::default()
+        };
+        assert_eq!(
+            generate(model, &[1], &greedy).expect("should generate"),
+            [2, 3, 0, 1, 2, 3]
+        );
+
+        let stopped = GenerateConfig {
+            stop_token: Some(0),
+            ..greedy.clone()
+        };
+        assert_eq!(
+            generate(model, &[1], &stopped).expect("should generate"),
+            [2, 3]
+        );
+
+        let top_1 = GenerateConfig {
+            sampling: Sampling::TopK {
+                k: 1,
+                temperature: 10.0,
+            },
+            ..greedy.clone()
+        };
+        assert_eq!(
+            generate(model, &[1], &top_1).expect("should generate"),
+            [2, 3, 0, 1, 2, 3]
+        );
+
+        // sampling varies with the seed and repeats with the same one
+        let hot = |seed| GenerateConfig {
+            sampling: Sampling::Temperature(5.0),
+            max_len: 50,
+            seed,
+            ..Default::default()
+        };
+        let a = generate(model, &[], &hot(1)).expect("should generate");
+        assert_eq!(a, generate(model, &[], &hot(1)).expect("should generate"));
+        assert_ne!(a, generate(model, &[], &hot(2)).expect("should generate"));
+        assert!(a.windows(2).any(|pair| pair[1] != (pair[0] + 1) % 4));
+
+        // a cold temperature is nearly greedy
+        let cold = GenerateConfig {
+            sampling: Sampling::Temperature(0.01),
+            ..greedy.clone()
+        };
+        assert_eq!(
+            generate(model, &[1], &cold).expect("should generate"),
+            [2, 3, 0, 1, 2, 3]
+        );
+
+        for sampling in [
+            Sampling::Temperature(0.0),
+            Sampling::TopK {
+                k: 0,
+                temperature: 1.0,
+            },
+        ] {
+            let config = GenerateConfig {
+                sampling,
+                ..Default::default()
+            };
+            assert!(matches!(
+                generate(model, &[], &config),
+                Err(Error::Sampling(_))
+            ));
+        }
+    }
+}
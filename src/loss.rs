@@ -0,0 +1,373 @@
+use thiserror::Error as ThisError;
+
+use crate::value::Value;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("Sample count mismatch, got {0} targets and {1} predictions")]
+    SampleMismatch(usize, usize),
+    #[error("Weight count mismatch, expected {0} sample weights, got {1}")]
+    WeightMismatch(usize, usize),
+    #[error("Dimension mismatch in sample {0}, expected {1} outputs, got {2}")]
+    DimensionMismatch(usize, usize, usize),
+    #[error("Class weight count mismatch, expected {0} class weights, got {1}")]
+    ClassWeightMismatch(usize, usize),
+    #[error("Mask count mismatch, expected {0} masks, got {1}")]
+    MaskMismatch(usize, usize),
+    #[error(
+        "Length mismatch in sequence {0}, got {1} targets, {2} predictions and {3} mask entries"
+    )]
+    SequenceMismatch(usize, usize, usize, usize),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// How per-sample losses are combined into the loss of a batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Reduction {
+    #[default]
+    Sum,
+    Mean,
+}
+
+/// A loss function selectable at runtime, e.g. by a `Trainer`
+#[derive(Debug, Clone)]
+pub enum Loss {
+    SquaredError,
+    BinaryCrossEntropy { class_weights: Option<[f64; 2]> },
+    CrossEntropy { class_weights: Option<Vec<f64>> },
+}
+
+impl Loss {
+    pub fn compute(
+        &self,
+        ys: &[Vec<f64>],
+        ypred: &[Vec<Value>],
+        weights: Option<&[f64]>,
+    ) -> Result<Value> {
+        match self {
+            Loss::SquaredError => squared_error(ys, ypred, weights),
+            Loss::BinaryCrossEntropy { class_weights } => {
+                binary_cross_entropy(ys, ypred, weights, *class_weights)
+            }
+            Loss::CrossEntropy { class_weights } => {
+                cross_entropy(ys, ypred, weights, class_weights.as_deref())
+            }
+        }
+    }
+
+    /// Loss of padded sequences, see `nn::pad`, summed over the positions
+    /// marked in `mask`. Padded positions aren't part of the loss, so they
+    /// contribute no gradient either.
+    pub fn compute_masked(
+        &self,
+        ys: &[Vec<Vec<f64>>],
+        ypred: &[Vec<Vec<Value>>],
+        mask: &[Vec<bool>],
+    ) -> Result<Value> {
+        if ys.len() != ypred.len() {
+            return Err(Error::SampleMismatch(ys.len(), ypred.len()));
+        }
+        if ys.len() != mask.len() {
+            return Err(Error::MaskMismatch(ys.len(), mask.len()));
+        }
+
+        let mut targets = Vec::new();
+        let mut predictions = Vec::new();
+
+        for (i, ((ys, ypred), mask)) in ys.iter().zip(ypred).zip(mask).enumerate() {
+            if ys.len() != ypred.len() || ys.len() != mask.len() {
+                return Err(Error::SequenceMismatch(
+                    i,
+                    ys.len(),
+                    ypred.len(),
+                    mask.len(),
+                ));
+            }
+
+            for ((y, pred), _) in ys.iter().zip(ypred).zip(mask).filter(|(_, &real)| real) {
+                targets.push(y.clone());
+                predictions.push(pred.clone());
+            }
+        }
+
+        self.compute(&targets, &predictions, None)
+    }
+}
+
+/// Sum of squared errors over all samples and outputs.
+///
+/// When `weights` are given, each sample's contribution is multiplied by its
+/// weight before summing.
+pub fn squared_error(
+    ys: &[Vec<f64>],
+    ypred: &[Vec<Value>],
+    weights: Option<&[f64]>,
+) -> Result<Value> {
+    check_samples(ys, ypred, weights)?;
+
+    ys.iter()
+        .zip(ypred)
+        .enumerate()
+        .try_fold(Value::new(0.0, "0"), |sum, (i, (y, yp))| {
+            if y.len() != yp.len() {
+                return Err(Error::DimensionMismatch(i, y.len(), yp.len()));
+            }
+
+            let sample = y.iter().zip(yp).fold(Value::new(0.0, "0"), |sum, (y, yp)| {
+                sum + (yp.clone() - Value::new(*y, "y")).pow(2.0)
+            });
+
+            Ok(sum + weighted(sample, weights, i))
+        })
+}
+
+/// Binary cross-entropy over all samples and outputs.
+///
+/// Predictions are probabilities (e.g. outputs of `Value::sigmoid`) and targets
+/// are 0.0 or 1.0. `class_weights` scale the negative and positive terms
+/// respectively, so the rarer class can be up-weighted.
+pub fn binary_cross_entropy(
+    ys: &[Vec<f64>],
+    ypred: &[Vec<Value>],
+    weights: Option<&[f64]>,
+    class_weights: Option<[f64; 2]>,
+) -> Result<Value> {
+    check_samples(ys, ypred, weights)?;
+
+    let [negative, positive] = class_weights.unwrap_or([1.0, 1.0]);
+
+    ys.iter()
+        .zip(ypred)
+        .enumerate()
+        .try_fold(Value::new(0.0, "0"), |sum, (i, (y, yp))| {
+            if y.len() != yp.len() {
+                return Err(Error::DimensionMismatch(i, y.len(), yp.len()));
+            }
+
+            let sample = y.iter().zip(yp).fold(Value::new(0.0, "0"), |sum, (y, yp)| {
+                let pos =
+                    (yp.clone() + Value::new(EPSILON, "eps")).ln() * Value::new(-y * positive, "y");
+                let neg = (Value::new(1.0 + EPSILON, "1") - yp.clone()).ln()
+                    * Value::new(-(1.0 - y) * negative, "1 - y");
+
+                sum + pos + neg
+            });
+
+            Ok(sum + weighted(sample, weights, i))
+        })
+}
+
+/// Cross-entropy of softmax over raw scores (logits) against target class
+/// distributions, usually one-hot vectors.
+///
+/// `class_weights` holds one weight per class and scales each sample's
+/// contribution by the weight of its target class.
+pub fn cross_entropy(
+    ys: &[Vec<f64>],
+    logits: &[Vec<Value>],
+    weights: Option<&[f64]>,
+    class_weights: Option<&[f64]>,
+) -> Result<Value> {
+    check_samples(ys, logits, weights)?;
+
+    ys.iter()
+        .zip(logits)
+        .enumerate()
+        .try_fold(Value::new(0.0, "0"), |sum, (i, (y, logits))| {
+            if y.len() != logits.len() {
+                return Err(Error::DimensionMismatch(i, y.len(), logits.len()));
+            }
+
+            if let Some(class_weights) = class_weights {
+                if class_weights.len() != y.len() {
+                    return Err(Error::ClassWeightMismatch(y.len(), class_weights.len()));
+                }
+            }
+
+            let log_probs = log_softmax(logits);
+            let sample = y.iter().zip(log_probs).enumerate().fold(
+                Value::new(0.0, "0"),
+                |sum, (k, (y, log_p))| {
+                    let class_weight = class_weights.map_or(1.0, |cw| cw[k]);
+
+                    sum + log_p * Value::new(-y * class_weight, "y")
+                },
+            );
+
+            Ok(sum + weighted(sample, weights, i))
+        })
+}
+
+// Guards the logarithms in binary cross-entropy against predictions of exactly 0 or 1
+const EPSILON: f64 = 1e-12;
+
+/// Log-probabilities of a softmax over `logits`, e.g. of the actions of a policy.
+///
+/// Logits are shifted by their maximum for numerical stability, which as a
+/// constant doesn't affect the gradients.
+pub fn log_softmax(logits: &[Value]) -> Vec<Value> {
+    let max = logits
+        .iter()
+        .map(|l| l.value())
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let shifted: Vec<_> = logits
+        .iter()
+        .map(|l| l.clone() - Value::new(max, "max"))
+        .collect();
+
+    let log_sum = shifted
+        .iter()
+        .fold(Value::new(0.0, "0"), |sum, l| sum + l.clone().exp())
+        .ln();
+
+    shifted.into_iter().map(|l| l - log_sum.clone()).collect()
+}
+
+fn check_samples(ys: &[Vec<f64>], ypred: &[Vec<Value>], weights: Option<&[f64]>) -> Result<()> {
+    if ys.len() != ypred.len() {
+        return Err(Error::SampleMismatch(ys.len(), ypred.len()));
+    }
+
+    match weights {
+        Some(weights) if weights.len() != ys.len() => {
+            Err(Error::WeightMismatch(ys.len(), weights.len()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn weighted(sample: Value, weights: Option<&[f64]>, i: usize) -> Value {
+    match weights {
+        Some(weights) => sample * Value::new(weights[i], &format!("sw_{i}")),
+        None => sample,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binary_cross_entropy, cross_entropy, squared_error, Error, Loss};
+    use crate::{nn::pad, value::Value};
+
+    #[test]
+    fn squared_error_unweighted() {
+        let ys = vec![vec![1.0], vec![-1.0]];
+        let ypred = vec![vec![Value::new(0.5, "p_1")], vec![Value::new(0.0, "p_2")]];
+
+        let loss = squared_error(&ys, &ypred, None).expect("loss should compute");
+
+        assert_eq!(loss.value(), 1.25);
+    }
+
+    #[test]
+    fn squared_error_weighted() {
+        let ys = vec![vec![1.0], vec![-1.0]];
+        let ypred = vec![vec![Value::new(0.5, "p_1")], vec![Value::new(0.0, "p_2")]];
+
+        let loss = squared_error(&ys, &ypred, Some(&[2.0, 0.0])).expect("loss should compute");
+        loss.backpropagate();
+
+        assert_eq!(loss.value(), 0.5);
+        assert_eq!(ypred[0][0].gradient(), -2.0);
+        assert_eq!(ypred[1][0].gradient(), 0.0);
+    }
+
+    #[test]
+    fn weight_mismatch() {
+        let ys = vec![vec![1.0], vec![-1.0]];
+        let ypred = vec![vec![Value::new(0.5, "p_1")], vec![Value::new(0.0, "p_2")]];
+
+        let result = squared_error(&ys, &ypred, Some(&[1.0]));
+
+        assert!(matches!(result, Err(Error::WeightMismatch(2, 1))));
+    }
+
+    #[test]
+    fn binary_cross_entropy_class_weights() {
+        let ys = vec![vec![1.0], vec![0.0]];
+        let ypred = vec![vec![Value::new(0.5, "p_1")], vec![Value::new(0.5, "p_2")]];
+
+        let plain = binary_cross_entropy(&ys, &ypred, None, None).expect("loss should compute");
+        let weighted =
+            binary_cross_entropy(&ys, &ypred, None, Some([1.0, 3.0])).expect("loss should compute");
+
+        assert!((plain.value() - 2.0 * 2.0f64.ln()).abs() < 1e-9);
+        assert!((weighted.value() - 4.0 * 2.0f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cross_entropy_gradient() {
+        let ys = vec![vec![0.0, 1.0, 0.0]];
+        let logits = vec![vec![
+            Value::new(1.0, "l_1"),
+            Value::new(2.0, "l_2"),
+            Value::new(0.5, "l_3"),
+        ]];
+
+        let loss = cross_entropy(&ys, &logits, None, None).expect("loss should compute");
+        loss.backpropagate();
+
+        let exps: Vec<f64> = [1.0f64, 2.0, 0.5].iter().map(|l| l.exp()).collect();
+        let total: f64 = exps.iter().sum();
+
+        assert!((loss.value() + (exps[1] / total).ln()).abs() < 1e-9);
+
+        // d(loss)/d(logit) = softmax - target
+        for (k, logit) in logits[0].iter().enumerate() {
+            let expected = exps[k] / total - ys[0][k];
+
+            assert!((logit.gradient() - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn cross_entropy_class_weights() {
+        let ys = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let logits = vec![
+            vec![Value::new(0.0, "l_1"), Value::new(0.0, "l_2")],
+            vec![Value::new(0.0, "l_1"), Value::new(0.0, "l_2")],
+        ];
+
+        let loss =
+            cross_entropy(&ys, &logits, None, Some(&[1.0, 4.0])).expect("loss should compute");
+        let mismatch = cross_entropy(&ys, &logits, None, Some(&[1.0]));
+
+        assert!((loss.value() - 5.0 * 2.0f64.ln()).abs() < 1e-9);
+        assert!(matches!(mismatch, Err(Error::ClassWeightMismatch(2, 1))));
+    }
+
+    #[test]
+    fn masked_sequences() {
+        let ypred = pad(
+            &[
+                vec![vec![Value::new(0.5, "p")], vec![Value::new(1.0, "p")]],
+                vec![vec![Value::new(0.0, "p")]],
+            ],
+            vec![Value::new(7.0, "pad")],
+        );
+        let ys = pad(&[vec![vec![1.0], vec![1.0]], vec![vec![-1.0]]], vec![0.0]);
+
+        let loss = Loss::SquaredError
+            .compute_masked(&ys.sequences, &ypred.sequences, &ypred.mask)
+            .expect("loss should compute");
+        assert_eq!(loss.value(), 1.25);
+
+        loss.backpropagate();
+        assert_eq!(ypred.sequences[0][0][0].gradient(), -1.0);
+        assert_eq!(ypred.sequences[1][1][0].gradient(), 0.0);
+
+        assert!(matches!(
+            Loss::SquaredError.compute_masked(&ys.sequences, &ypred.sequences, &ypred.mask[..1]),
+            Err(Error::MaskMismatch(2, 1))
+        ));
+        assert!(matches!(
+            Loss::SquaredError.compute_masked(
+                &ys.sequences,
+                &ypred.sequences,
+                &vec![vec![true]; 2]
+            ),
+            Err(Error::SequenceMismatch(0, 2, 2, 1))
+        ));
+    }
+}
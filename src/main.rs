@@ -1,74 +1,161 @@
-pub mod nn;
-pub mod value;
-
-use rand::prelude::*;
-
-use nn::Mlp;
-use value::Value;
-
-fn main() {
-    let mut rng = thread_rng();
-
-    let xs = [
-        [
-            Value::new(2.0, "x_1"),
-            Value::new(3.0, "x_2"),
-            Value::new(-1.0, "x_3"),
-        ],
-        [
-            Value::new(3.0, "x_1"),
-            Value::new(-1.0, "x_2"),
-            Value::new(0.5, "x_3"),
-        ],
-        [
-            Value::new(0.5, "x_1"),
-            Value::new(1.0, "x_2"),
-            Value::new(1.0, "x_3"),
-        ],
-        [
-            Value::new(1.0, "x_1"),
-            Value::new(1.0, "x_2"),
-            Value::new(-1.0, "x_3"),
-        ],
-    ];
-    let ys = [
-        Value::new(1.0, "y_1"),
-        Value::new(-1.0, "y_2"),
-        Value::new(-1.0, "y_3"),
-        Value::new(1.0, "y_4"),
-    ];
-
-    let mlp = Mlp::new(3, &[4, 4, 1], &mut rng);
-
-    for i in 0..500 {
-        let ypred: Vec<_> = xs
-            .iter()
-            .map(|x| mlp.predict(x).expect("should predict")[0].clone())
-            .collect();
-
-        let loss = loss(&ys, &ypred);
-
-        loss.backpropagate();
-        mlp.nudge_parameters(0.05);
-
-        let ypred_raw: Vec<_> = ypred.iter().map(|yp| yp.value()).collect();
-
-        println!(
-            "Iteration {}, loss {}, prediction: {:?}",
-            i,
-            loss.value(),
-            ypred_raw
-        );
+//! Command line interface for training and running models without writing Rust:
+//!
+//! ```sh
+//! micrograd train --config config.toml --data data.csv --header --out model.json
+//! micrograd predict --model model.json --input 1,2,3
+//! micrograd export --model model.json --format onnx --out model.onnx
+//! ```
+
+use std::{
+    error::Error,
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use micrograd::{config::ExperimentConfig, data::StreamingDataset, nn::Mlp, onnx};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(version, about = "Train and run small neural networks")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Trains a model on a CSV file of inputs followed by targets
+    Train {
+        /// TOML or YAML experiment config with the architecture and
+        /// training settings
+        #[arg(long)]
+        config: PathBuf,
+        #[arg(long)]
+        data: PathBuf,
+        /// Skip the first line of the CSV file
+        #[arg(long)]
+        header: bool,
+        /// Where to write the trained model as JSON
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Prints the outputs of a model for comma separated inputs
+    Predict {
+        #[arg(long)]
+        model: PathBuf,
+        /// One sample, e.g. `1,2.5,-3`, repeatable
+        #[arg(long, required = true)]
+        input: Vec<String>,
+    },
+    /// Converts a model into another format
+    Export {
+        #[arg(long)]
+        model: PathBuf,
+        #[arg(long, value_enum)]
+        format: Format,
+        #[arg(long)]
+        out: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Onnx,
+    /// Standalone Rust source of a `predict` function
+    Rust,
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Train {
+            config,
+            data,
+            header,
+            out,
+        } => train(&config, &data, header, &out),
+        Command::Predict { model, input } => predict(&model, &input),
+        Command::Export { model, format, out } => {
+            let model = load(&model)?;
+
+            match format {
+                Format::Onnx => onnx::save(&model, out)?,
+                Format::Rust => fs::write(out, model.to_rust_source()?)?,
+            }
+
+            Ok(())
+        }
     }
 }
 
-fn loss(ys: &[Value], ypred: &[Value]) -> Value {
-    let loss: Value = ypred
-        .iter()
-        .zip(ys)
-        .fold(Value::new(0.0, "0"), |sum, (ypred, y)| {
-            sum + (ypred.clone() - y.clone()).pow(2.0)
-        });
+fn train(config: &Path, data: &Path, header: bool, out: &Path) -> Result<()> {
+    let mut config = ExperimentConfig::load(config)?;
+    config.training.verbose = true;
 
-    loss
+    let targets = config.model.layers[config.model.layers.len() - 1];
+    let inputs = columns(data, header)?
+        .checked_sub(targets)
+        .filter(|&inputs| inputs > 0)
+        .ok_or("the data has no input columns besides the targets")?;
+    let dataset = StreamingDataset::csv(data, inputs, header)?;
+
+    let mut trainer = config.trainer(inputs)?;
+    trainer.fit(&dataset, config.training.epochs)?;
+
+    fs::write(out, trainer.model().to_json())?;
+
+    Ok(())
+}
+
+fn predict(model: &Path, inputs: &[String]) -> Result<()> {
+    let model = load(model)?;
+
+    for input in inputs {
+        let x = input
+            .split(',')
+            .map(|value| value.trim().parse())
+            .collect::<std::result::Result<Vec<f64>, _>>()?;
+
+        let outputs: Vec<_> = model.infer(&x)?.iter().map(f64::to_string).collect();
+
+        println!("{}", outputs.join(","));
+    }
+
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Mlp> {
+    Ok(Mlp::from_json(&fs::read_to_string(path)?)?)
+}
+
+// Number of columns of the first sample in a CSV file
+fn columns(path: &Path, header: bool) -> Result<usize> {
+    BufReader::new(fs::File::open(path)?)
+        .lines()
+        .skip(usize::from(header))
+        .find(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .ok_or("the data file has no samples")?
+        .map(|line| line.split(',').count())
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::columns;
+
+    #[test]
+    fn counts_columns() {
+        let path = std::env::temp_dir().join(format!("micrograd-cli-{}.csv", std::process::id()));
+        std::fs::write(&path, "a,b,y\n\n1,2,3,4\n").expect("should write");
+
+        let header = columns(&path, true);
+        let no_header = columns(&path, false);
+        std::fs::remove_file(&path).expect("should clean up");
+
+        assert_eq!(header.expect("should count"), 4);
+        assert_eq!(no_header.expect("should count"), 3);
+    }
 }
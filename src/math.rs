@@ -0,0 +1,50 @@
+//! Floating point functions which `core` lacks, from `std` when available and
+//! from `libm` otherwise
+
+#[cfg(feature = "std")]
+mod imp {
+    pub fn tanh(x: f64) -> f64 {
+        x.tanh()
+    }
+
+    pub fn exp(x: f64) -> f64 {
+        x.exp()
+    }
+
+    pub fn ln(x: f64) -> f64 {
+        x.ln()
+    }
+
+    pub fn powf(x: f64, exponent: f64) -> f64 {
+        x.powf(exponent)
+    }
+
+    pub fn round(x: f64) -> f64 {
+        x.round()
+    }
+
+    pub fn round_ties_even(x: f64) -> f64 {
+        x.round_ties_even()
+    }
+
+    pub fn sin(x: f64) -> f64 {
+        x.sin()
+    }
+
+    pub fn cos(x: f64) -> f64 {
+        x.cos()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod imp {
+    pub use libm::{cos, exp, log as ln, pow as powf, rint as round_ties_even, round, sin, tanh};
+}
+
+pub(crate) use imp::*;
+
+/// Rounds `x` to the nearest multiple of `scale` representable as an 8 bit
+/// integer, i.e. within `-127..=127` steps of zero
+pub(crate) fn fake_quantize(x: f64, scale: f64) -> f64 {
+    round(x / scale).clamp(-127.0, 127.0) * scale
+}
@@ -0,0 +1,695 @@
+use std::fmt::{self, Display};
+
+/// A metric the `Trainer` computes on its predictions every epoch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Metric {
+    /// See `accuracy_with_threshold`
+    Accuracy { threshold: f64 },
+    /// Macro-averaged F1 score over `classes` classes, see `classification_report`
+    MacroF1 { classes: usize, threshold: f64 },
+    /// Coefficient of determination over all outputs, see `r2`
+    R2,
+    /// Root mean squared error over all outputs
+    Rmse,
+    /// Mean absolute error over all outputs
+    Mae,
+    /// Mean absolute percentage error over all outputs, see `mape`
+    Mape,
+    /// Area under the ROC curve of the scores, targets at or above `threshold`
+    /// being positive. Averaged over the outputs of multi-output models, each
+    /// scored against its own targets, 0.0 for no samples.
+    RocAuc { threshold: f64 },
+    /// Fraction of multi-class samples whose target class is among the `k`
+    /// largest outputs, see `top_k_accuracy`
+    TopKAccuracy { k: usize },
+}
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Accuracy { .. } => "accuracy",
+            Metric::MacroF1 { .. } => "macro_f1",
+            Metric::R2 => "r2",
+            Metric::Rmse => "rmse",
+            Metric::Mae => "mae",
+            Metric::Mape => "mape",
+            Metric::RocAuc { .. } => "roc_auc",
+            Metric::TopKAccuracy { .. } => "top_k_accuracy",
+        }
+    }
+
+    pub fn compute(&self, preds: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+        match self {
+            Metric::Accuracy { threshold } => accuracy_with_threshold(preds, targets, *threshold),
+            Metric::MacroF1 { classes, threshold } => {
+                let predicted = classes_of(preds, *threshold);
+                let labels = classes_of(targets, *threshold);
+
+                classification_report(&predicted, &labels, *classes)
+                    .macro_average()
+                    .f1
+            }
+            Metric::R2 => r2(&preds.concat(), &targets.concat()),
+            Metric::Rmse => rmse(&preds.concat(), &targets.concat()),
+            Metric::Mae => mae(&preds.concat(), &targets.concat()),
+            Metric::Mape => mape(&preds.concat(), &targets.concat()),
+            Metric::RocAuc { threshold } => {
+                let outputs = targets.first().map_or(0, Vec::len);
+                let total: f64 = (0..outputs)
+                    .map(|j| {
+                        let (scores, labels): (Vec<_>, Vec<_>) = preds
+                            .iter()
+                            .zip(targets)
+                            .filter_map(|(pred, target)| {
+                                Some((*pred.get(j)?, *target.get(j)? >= *threshold))
+                            })
+                            .unzip();
+
+                        roc_auc(&scores, &labels)
+                    })
+                    .sum();
+
+                total / outputs.max(1) as f64
+            }
+            Metric::TopKAccuracy { k } => {
+                let labels: Vec<_> = targets.iter().map(|t| argmax(t)).collect();
+
+                top_k_accuracy(preds, &labels, *k)
+            }
+        }
+    }
+}
+
+/// Fraction of correctly classified samples, thresholding single outputs at 0.5.
+///
+/// See `accuracy_with_threshold`.
+pub fn accuracy(preds: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+    accuracy_with_threshold(preds, targets, 0.5)
+}
+
+/// Fraction of correctly classified samples.
+///
+/// Samples with a single output are binary: prediction and target are both
+/// assigned the positive class when at or above `threshold` (0.5 for sigmoid
+/// outputs, 0.0 for tanh outputs with ±1 targets). Samples with several outputs
+/// are compared by the index of their largest value, so targets can be one-hot
+/// vectors. Returns 0.0 for no samples.
+pub fn accuracy_with_threshold(preds: &[Vec<f64>], targets: &[Vec<f64>], threshold: f64) -> f64 {
+    if preds.is_empty() {
+        return 0.0;
+    }
+
+    let correct = preds
+        .iter()
+        .zip(targets)
+        .filter(|(pred, target)| class_of(pred, threshold) == class_of(target, threshold))
+        .count();
+
+    correct as f64 / preds.len() as f64
+}
+
+/// Fraction of samples whose class in `labels` is among the `k` largest of
+/// their `logits`, or probabilities. Ties count in the label's favour. Returns
+/// 0.0 for no samples.
+pub fn top_k_accuracy(logits: &[Vec<f64>], labels: &[usize], k: usize) -> f64 {
+    if logits.is_empty() {
+        return 0.0;
+    }
+
+    let correct = logits
+        .iter()
+        .zip(labels)
+        .filter(|(logits, &label)| {
+            logits
+                .get(label)
+                .is_some_and(|&score| logits.iter().filter(|&&other| other > score).count() < k)
+        })
+        .count();
+
+    correct as f64 / logits.len() as f64
+}
+
+/// Class index of a model output or target, see `accuracy_with_threshold`
+pub fn class_of(output: &[f64], threshold: f64) -> usize {
+    match output {
+        [single] => usize::from(*single >= threshold),
+        _ => argmax(output),
+    }
+}
+
+/// Class indices of a set of outputs or targets, see `class_of`
+pub fn classes_of(outputs: &[Vec<f64>], threshold: f64) -> Vec<usize> {
+    outputs
+        .iter()
+        .map(|output| class_of(output, threshold))
+        .collect()
+}
+
+/// Precision, recall and F1 score of a single class
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClassScores {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Number of samples labelled with the class
+    pub support: usize,
+}
+
+/// Per-class classification scores
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassificationReport {
+    pub classes: Vec<ClassScores>,
+}
+
+impl ClassificationReport {
+    /// Unweighted mean of the per-class scores, with the total support
+    pub fn macro_average(&self) -> ClassScores {
+        let count = self.classes.len().max(1) as f64;
+
+        ClassScores {
+            precision: self.classes.iter().map(|c| c.precision).sum::<f64>() / count,
+            recall: self.classes.iter().map(|c| c.recall).sum::<f64>() / count,
+            f1: self.classes.iter().map(|c| c.f1).sum::<f64>() / count,
+            support: self.classes.iter().map(|c| c.support).sum(),
+        }
+    }
+}
+
+/// Precision, recall and F1 score of every class in `0..classes`, computed from
+/// predicted and true class labels.
+///
+/// Scores with a zero denominator (e.g. precision of a never predicted class)
+/// are reported as 0.0.
+pub fn classification_report(
+    predicted: &[usize],
+    labels: &[usize],
+    classes: usize,
+) -> ClassificationReport {
+    let classes = (0..classes)
+        .map(|class| {
+            let (mut tp, mut fp, mut fn_) = (0, 0, 0);
+
+            for (&p, &l) in predicted.iter().zip(labels) {
+                match (p == class, l == class) {
+                    (true, true) => tp += 1,
+                    (true, false) => fp += 1,
+                    (false, true) => fn_ += 1,
+                    (false, false) => (),
+                }
+            }
+
+            let precision = ratio(tp, tp + fp);
+            let recall = ratio(tp, tp + fn_);
+            let f1 = if precision + recall > 0.0 {
+                2.0 * precision * recall / (precision + recall)
+            } else {
+                0.0
+            };
+
+            ClassScores {
+                precision,
+                recall,
+                f1,
+                support: tp + fn_,
+            }
+        })
+        .collect();
+
+    ClassificationReport { classes }
+}
+
+/// Counts of (true label, predicted class) pairs, accumulated over any number of batches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    // rows are true labels, columns predicted classes
+    counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(classes: usize) -> Self {
+        Self {
+            counts: vec![vec![0; classes]; classes],
+        }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Records a single prediction. Out of range classes are ignored.
+    pub fn add(&mut self, predicted: usize, label: usize) {
+        if let Some(count) = self
+            .counts
+            .get_mut(label)
+            .and_then(|row| row.get_mut(predicted))
+        {
+            *count += 1;
+        }
+    }
+
+    /// Records a batch of predictions against their true labels
+    pub fn add_batch(&mut self, predicted: &[usize], labels: &[usize]) {
+        for (&p, &l) in predicted.iter().zip(labels) {
+            self.add(p, l);
+        }
+    }
+
+    /// Number of samples labelled `label` that were predicted as `predicted`
+    pub fn get(&self, label: usize, predicted: usize) -> usize {
+        self.counts[label][predicted]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        let correct: usize = (0..self.classes()).map(|c| self.counts[c][c]).sum();
+
+        ratio(correct, self.total())
+    }
+}
+
+impl Display for ConfusionMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let classes = self.classes();
+        let largest = self.counts.iter().flatten().max().copied().unwrap_or(0);
+        let width = largest
+            .to_string()
+            .len()
+            .max(classes.saturating_sub(1).to_string().len());
+
+        let label_width = "true".len() + 1 + width;
+
+        writeln!(f, "{:label_width$} predicted", "")?;
+        write!(f, "{:label_width$}", "")?;
+        for class in 0..classes {
+            write!(f, " {class:>width$}")?;
+        }
+        writeln!(f)?;
+
+        for (label, row) in self.counts.iter().enumerate() {
+            let prefix = if label == 0 { "true" } else { "" };
+
+            write!(f, "{prefix:4} {label:>width$}")?;
+            for count in row {
+                write!(f, " {count:>width$}")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The standard set of regression metrics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegressionReport {
+    pub r2: f64,
+    pub rmse: f64,
+    pub mae: f64,
+    pub mape: f64,
+}
+
+pub fn regression_report(preds: &[f64], targets: &[f64]) -> RegressionReport {
+    RegressionReport {
+        r2: r2(preds, targets),
+        rmse: rmse(preds, targets),
+        mae: mae(preds, targets),
+        mape: mape(preds, targets),
+    }
+}
+
+/// Coefficient of determination, 1 - SS_res / SS_tot.
+///
+/// 1.0 is a perfect fit, 0.0 is no better than predicting the mean, and it can
+/// go arbitrarily negative. Constant targets give 0.0 unless predicted exactly.
+pub fn r2(preds: &[f64], targets: &[f64]) -> f64 {
+    let mean = mean(targets);
+    let residual: f64 = preds
+        .iter()
+        .zip(targets)
+        .map(|(p, t)| (t - p).powi(2))
+        .sum();
+    let total: f64 = targets.iter().map(|t| (t - mean).powi(2)).sum();
+
+    if total == 0.0 {
+        if residual == 0.0 {
+            1.0
+        } else {
+            0.0
+        }
+    } else {
+        1.0 - residual / total
+    }
+}
+
+pub fn rmse(preds: &[f64], targets: &[f64]) -> f64 {
+    mean(
+        &preds
+            .iter()
+            .zip(targets)
+            .map(|(p, t)| (t - p).powi(2))
+            .collect::<Vec<_>>(),
+    )
+    .sqrt()
+}
+
+pub fn mae(preds: &[f64], targets: &[f64]) -> f64 {
+    mean(
+        &preds
+            .iter()
+            .zip(targets)
+            .map(|(p, t)| (t - p).abs())
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Mean absolute percentage error as a fraction (0.1 is 10%).
+///
+/// Samples with a zero target are skipped, since their percentage error is undefined.
+pub fn mape(preds: &[f64], targets: &[f64]) -> f64 {
+    mean(
+        &preds
+            .iter()
+            .zip(targets)
+            .filter(|(_, t)| **t != 0.0)
+            .map(|(p, t)| ((t - p) / t).abs())
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// A point on the ROC curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RocPoint {
+    /// Scores at or above the threshold are classified positive
+    pub threshold: f64,
+    pub false_positive_rate: f64,
+    pub true_positive_rate: f64,
+}
+
+/// Receiver operating characteristic of binary classifier scores (e.g. sigmoid
+/// outputs) against true labels.
+///
+/// Points go from (0, 0) at an infinite threshold to (1, 1), with one point per
+/// distinct score. Rates are NaN when the labels are all of one class.
+pub fn roc_curve(scores: &[f64], labels: &[bool]) -> Vec<RocPoint> {
+    let positives = labels.iter().filter(|l| **l).count() as f64;
+    let negatives = labels.len() as f64 - positives;
+
+    let mut ranked: Vec<_> = scores.iter().copied().zip(labels.iter().copied()).collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut points = vec![RocPoint {
+        threshold: f64::INFINITY,
+        false_positive_rate: 0.0 / negatives,
+        true_positive_rate: 0.0 / positives,
+    }];
+    let (mut tp, mut fp) = (0.0, 0.0);
+
+    for (i, (score, label)) in ranked.iter().enumerate() {
+        if *label {
+            tp += 1.0;
+        } else {
+            fp += 1.0;
+        }
+
+        // tied scores can't be separated by any threshold, so they form a single point
+        if ranked.get(i + 1).is_none_or(|next| next.0 != *score) {
+            points.push(RocPoint {
+                threshold: *score,
+                false_positive_rate: fp / negatives,
+                true_positive_rate: tp / positives,
+            });
+        }
+    }
+
+    points
+}
+
+/// Area under the ROC curve, by the trapezoidal rule
+pub fn roc_auc(scores: &[f64], labels: &[bool]) -> f64 {
+    roc_curve(scores, labels)
+        .windows(2)
+        .map(|w| {
+            (w[1].false_positive_rate - w[0].false_positive_rate)
+                * (w[1].true_positive_rate + w[0].true_positive_rate)
+                / 2.0
+        })
+        .sum()
+}
+
+/// Expected calibration error: the gap between the confidence of the predicted
+/// class and the accuracy, averaged over `bins` equal width confidence bins
+/// weighted by their number of samples. 0 for perfectly calibrated
+/// probabilities or no samples.
+pub fn expected_calibration_error(
+    probabilities: &[Vec<f64>],
+    labels: &[usize],
+    bins: usize,
+) -> f64 {
+    let bins = bins.max(1);
+    // confidence sums, correct predictions and samples of every bin
+    let mut totals = vec![(0.0, 0, 0); bins];
+
+    for (probabilities, &label) in probabilities.iter().zip(labels) {
+        let predicted = argmax(probabilities);
+        let confidence = probabilities.get(predicted).copied().unwrap_or(0.0);
+        let bin = ((confidence * bins as f64) as usize).min(bins - 1);
+
+        totals[bin].0 += confidence;
+        totals[bin].1 += usize::from(predicted == label);
+        totals[bin].2 += 1;
+    }
+
+    let samples: usize = totals.iter().map(|bin| bin.2).sum();
+
+    totals
+        .iter()
+        .filter(|bin| bin.2 > 0)
+        .map(|&(confidence, correct, _)| (confidence - correct as f64).abs() / samples as f64)
+        .sum()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn argmax(values: &[f64]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f64::NEG_INFINITY), |(best, max), (i, &v)| {
+            if v > max {
+                (i, v)
+            } else {
+                (best, max)
+            }
+        })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        accuracy, accuracy_with_threshold, classification_report, regression_report, roc_auc,
+        roc_curve, top_k_accuracy, ConfusionMatrix, Metric,
+    };
+
+    #[test]
+    fn binary_accuracy() {
+        let preds = vec![vec![0.9], vec![0.2], vec![0.6], vec![0.4]];
+        let targets = vec![vec![1.0], vec![0.0], vec![0.0], vec![0.0]];
+
+        assert_eq!(accuracy(&preds, &targets), 0.75);
+    }
+
+    #[test]
+    fn tanh_accuracy() {
+        let preds = vec![vec![0.3], vec![-0.1]];
+        let targets = vec![vec![1.0], vec![1.0]];
+
+        assert_eq!(accuracy_with_threshold(&preds, &targets, 0.0), 0.5);
+    }
+
+    #[test]
+    fn multi_class_accuracy() {
+        let preds = vec![vec![0.1, 2.0, -1.0], vec![3.0, 0.0, 0.5]];
+        let targets = vec![vec![0.0, 1.0, 0.0], vec![0.0, 0.0, 1.0]];
+
+        assert_eq!(accuracy(&preds, &targets), 0.5);
+    }
+
+    #[test]
+    fn top_k() {
+        let logits = vec![
+            vec![0.1, 0.5, 0.4],
+            vec![2.0, 1.0, 3.0],
+            vec![1.0, 1.0, 0.0],
+            vec![0.15, 0.2, 0.1],
+        ];
+        let labels = [1, 1, 1, 0];
+
+        assert_eq!(top_k_accuracy(&logits, &labels, 1), 0.5);
+        assert_eq!(top_k_accuracy(&logits, &labels, 2), 0.75);
+        assert_eq!(top_k_accuracy(&logits, &labels, 3), 1.0);
+        assert_eq!(top_k_accuracy(&logits, &[3, 1, 1, 0], 3), 0.75);
+        assert_eq!(top_k_accuracy(&[], &[], 1), 0.0);
+
+        let targets = vec![
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![1.0, 0.0, 0.0],
+        ];
+        assert_eq!(
+            Metric::TopKAccuracy { k: 2 }.compute(&logits, &targets),
+            0.75
+        );
+    }
+
+    #[test]
+    fn precision_recall_f1() {
+        let predicted = [0, 1, 1, 2, 0, 1];
+        let labels = [0, 1, 2, 2, 1, 1];
+
+        let report = classification_report(&predicted, &labels, 3);
+
+        assert_eq!(report.classes[0].precision, 0.5);
+        assert_eq!(report.classes[0].recall, 1.0);
+        assert!((report.classes[1].precision - 2.0 / 3.0).abs() < 1e-12);
+        assert!((report.classes[1].recall - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(report.classes[2].precision, 1.0);
+        assert_eq!(report.classes[2].recall, 0.5);
+        assert_eq!(report.classes[2].support, 2);
+
+        let f1_0 = 2.0 * 0.5 / 1.5;
+        let f1_1 = 2.0 / 3.0;
+        let f1_2 = 2.0 * 0.5 / 1.5;
+        let average = report.macro_average();
+
+        assert!((average.f1 - (f1_0 + f1_1 + f1_2) / 3.0).abs() < 1e-12);
+        assert_eq!(average.support, 6);
+    }
+
+    #[test]
+    fn macro_f1_metric() {
+        let preds = vec![vec![0.9], vec![0.1], vec![0.8]];
+        let targets = vec![vec![1.0], vec![0.0], vec![0.0]];
+
+        let metric = Metric::MacroF1 {
+            classes: 2,
+            threshold: 0.5,
+        };
+
+        // class 0: precision 1, recall 0.5; class 1: precision 0.5, recall 1
+        assert!((metric.compute(&preds, &targets) - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn confusion_matrix() {
+        let mut matrix = ConfusionMatrix::new(3);
+
+        matrix.add_batch(&[0, 1, 1], &[0, 1, 2]);
+        matrix.add_batch(&[2, 0, 1], &[2, 1, 1]);
+
+        assert_eq!(matrix.get(1, 1), 2);
+        assert_eq!(matrix.get(2, 1), 1);
+        assert_eq!(matrix.get(1, 0), 1);
+        assert_eq!(matrix.total(), 6);
+        assert!((matrix.accuracy() - 4.0 / 6.0).abs() < 1e-12);
+
+        assert_eq!(
+            matrix.to_string(),
+            "       predicted\n       0 1 2\ntrue 0 1 0 0\n     1 1 2 0\n     2 0 1 1\n"
+        );
+    }
+
+    #[test]
+    fn regression_metrics() {
+        let preds = [1.0, 2.0, 4.0];
+        let targets = [1.0, 3.0, 5.0];
+
+        let report = regression_report(&preds, &targets);
+
+        // mean 3, SS_tot = 4 + 0 + 4, SS_res = 0 + 1 + 1
+        assert_eq!(report.r2, 0.75);
+        assert!((report.rmse - (2.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!((report.mae - 2.0 / 3.0).abs() < 1e-12);
+        assert!((report.mape - (0.0 + 1.0 / 3.0 + 0.2) / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn regression_metric_over_outputs() {
+        let preds = vec![vec![1.0, 2.0], vec![4.0, 4.0]];
+        let targets = vec![vec![1.0, 2.0], vec![4.0, 6.0]];
+
+        assert_eq!(Metric::Rmse.compute(&preds, &targets), 1.0);
+    }
+
+    #[test]
+    fn roc() {
+        let scores = [0.9, 0.8, 0.7, 0.6, 0.55, 0.4];
+        let labels = [true, true, false, true, false, false];
+
+        let curve = roc_curve(&scores, &labels);
+        let rates: Vec<_> = curve
+            .iter()
+            .map(|p| (p.false_positive_rate, p.true_positive_rate))
+            .collect();
+
+        let third = 1.0 / 3.0;
+        assert_eq!(
+            rates,
+            vec![
+                (0.0, 0.0),
+                (0.0, third),
+                (0.0, 2.0 * third),
+                (third, 2.0 * third),
+                (third, 1.0),
+                (2.0 * third, 1.0),
+                (1.0, 1.0)
+            ]
+        );
+
+        // 8 of the 9 positive-negative pairs are ranked correctly
+        assert!((roc_auc(&scores, &labels) - 8.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn roc_ties() {
+        let scores = [0.5, 0.5];
+        let labels = [true, false];
+
+        assert_eq!(roc_curve(&scores, &labels).len(), 2);
+        assert_eq!(roc_auc(&scores, &labels), 0.5);
+    }
+
+    #[test]
+    fn roc_auc_metric() {
+        let metric = Metric::RocAuc { threshold: 0.5 };
+        let preds = vec![vec![0.9, 0.2], vec![0.1, 0.8], vec![0.6, 0.9]];
+        let targets = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.0, 0.0]];
+
+        // the first output ranks its positive first, the second one ranks it
+        // below one of the negatives
+        assert_eq!(metric.compute(&preds, &targets), (1.0 + 0.5) / 2.0);
+        assert_eq!(
+            metric.compute(&[vec![0.1], vec![0.9]], &[vec![0.0], vec![1.0]]),
+            1.0
+        );
+        assert_eq!(metric.compute(&[], &[]), 0.0);
+    }
+}
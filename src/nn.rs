@@ -1,7 +1,52 @@
-use rand::Rng;
+mod activation;
+mod codegen;
+mod diff;
+mod embedding;
+mod gradcheck;
+#[cfg(feature = "json")]
+pub mod json;
+mod module;
+mod moe;
+mod one_vs_rest;
+mod positional;
+mod precision;
+mod quantize;
+mod sequence;
+mod sparse;
+mod stats;
+#[cfg(feature = "torch")]
+pub mod torch;
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+use core::{
+    fmt::{self, Display},
+    mem,
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use thiserror::Error as ThisError;
 
-use crate::value::Value;
+use crate::{
+    math,
+    value::{MemoryEstimate, Value},
+};
+
+#[cfg(feature = "std")]
+pub use activation::register_activation;
+pub use activation::Activation;
+pub use diff::{diff, LayerDiff, ModelDiff};
+pub use embedding::Embedding;
+pub use gradcheck::{check_module_gradients, GradientCheck};
+pub use module::Module;
+pub use moe::MixtureOfExperts;
+pub use one_vs_rest::OneVsRest;
+pub use positional::PositionalEncoding;
+pub use precision::Precision;
+pub use quantize::{QuantizationReport, QuantizedMlp};
+pub use sequence::{causal_mask, masked_mean, masked_softmax, pad, Padded};
+pub use sparse::SparseVector;
+pub use stats::{Bucket, FlowStatus, GradientFlow, LayerStats};
 
 #[derive(Debug)]
 pub struct Neuron {
@@ -11,11 +56,80 @@ pub struct Neuron {
 
 #[derive(ThisError, Debug)]
 pub enum Error {
-    #[error("Dimension mismatch, expected {0} inputs, got {1}")]
-    DimensionMismatch(usize, usize),
+    #[error(
+        "Dimension mismatch in layer {layer} ({stage}), expected {expected} inputs, got {actual}"
+    )]
+    DimensionMismatch {
+        layer: usize,
+        stage: Stage,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Parameter count mismatch, expected {0} parameters, got {1}")]
+    ParameterMismatch(usize, usize),
+    #[error("Unknown token {0}, expected one of {1}")]
+    UnknownToken(usize, usize),
+    #[error("A model needs at least one input")]
+    NoInputs,
+    #[error("A model needs at least one layer")]
+    NoLayers,
+    #[error("Layer {0} has no neurons")]
+    EmptyLayer(usize),
+    #[error("Mask of {1} steps for a sequence of {0}")]
+    MaskMismatch(usize, usize),
+    #[error("Sequence step of width {1}, expected {0}")]
+    StepMismatch(usize, usize),
+    #[error("Architecture mismatch, layer sizes {0:?} and {1:?}")]
+    ArchitectureMismatch(Vec<usize>, Vec<usize>),
+    #[error("Sparse index {0} out of range of {1} entries")]
+    SparseIndex(usize, usize),
+    #[error("Position {0} beyond the {1} learned positions")]
+    PositionOutOfRange(usize, usize),
+    #[error("Activation {0} is built in and can't be registered")]
+    BuiltinActivation(String),
+    #[error("Activation {0} isn't a Rust identifier")]
+    ActivationIdentifier(String),
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+/// What feeds the layer a dimension mismatch happened in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// The inputs of the model, going into the first layer
+    Input,
+    /// The outputs of the previous layer
+    Hidden,
+}
+
+impl Stage {
+    fn of(layer: usize) -> Self {
+        if layer == 0 {
+            Self::Input
+        } else {
+            Self::Hidden
+        }
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Input => write!(f, "input"),
+            Self::Hidden => write!(f, "hidden"),
+        }
+    }
+}
+
+// Mismatch of the inputs of layer `layer`
+fn dimension_mismatch(layer: usize, expected: usize, actual: usize) -> Error {
+    Error::DimensionMismatch {
+        layer,
+        stage: Stage::of(layer),
+        expected,
+        actual,
+    }
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
 
 impl Neuron {
     fn new<R: Rng>(inputs: usize, rng: &mut R) -> Self {
@@ -32,17 +146,47 @@ impl Neuron {
         Self { weights, bias }
     }
 
-    fn call(&self, x: &[Value]) -> Result<Value> {
-        if x.len() != self.weights.len() {
-            return Err(Error::DimensionMismatch(self.weights.len(), x.len()));
+    // `x` has one value per weight, as checked by the layer
+    fn call(&self, x: &[Value], activation: &Activation, quantized: bool) -> Value {
+        let sum = self
+            .weights(quantized)
+            .iter()
+            .zip(x)
+            .fold(self.bias.clone(), |sum, (w, x)| sum + w.clone() * x.clone());
+
+        activation.call(sum)
+    }
+
+    // Values of `weights`, without building a graph
+    fn inference_weights(&self, quantized: bool) -> Vec<f64> {
+        let weights: Vec<_> = self.weights.iter().map(Value::value).collect();
+
+        if !quantized {
+            return weights;
         }
 
-        Ok(self
-            .weights
+        let scale = quantize::weight_scale(&weights);
+
+        weights
             .iter()
-            .zip(x)
-            .fold(self.bias.clone(), |sum, (w, x)| sum + w.clone() * x.clone())
-            .tanh())
+            .map(|&w| math::fake_quantize(w, scale))
+            .collect()
+    }
+
+    // Weights as the forward pass sees them, rounded to the levels of the int8
+    // quantization of `Mlp::quantize` in quantization-aware layers
+    fn weights(&self, quantized: bool) -> Cow<'_, [Value]> {
+        if !quantized {
+            return Cow::Borrowed(&self.weights);
+        }
+
+        let values: Vec<_> = self.weights.iter().map(Value::value).collect();
+        let scale = quantize::weight_scale(&values);
+
+        self.weights
+            .iter()
+            .map(|w| w.clone().fake_quantize(scale))
+            .collect()
     }
 }
 
@@ -50,6 +194,8 @@ impl Neuron {
 struct Layer {
     inputs: usize,
     neurons: Vec<Neuron>,
+    // trained with fake-quantized weights
+    quantized: bool,
 }
 
 impl Layer {
@@ -58,65 +204,390 @@ impl Layer {
             .map(|_| Neuron::new(inputs, rng))
             .collect::<Vec<_>>();
 
-        Self { inputs, neurons }
+        Self {
+            inputs,
+            neurons,
+            quantized: false,
+        }
     }
 
-    fn call(&self, x: &[Value]) -> Result<Vec<Value>> {
+    // `index` is the position of the layer in the model, for errors
+    fn call(&self, index: usize, x: &[Value], activation: &Activation) -> Result<Vec<Value>> {
         if x.len() != self.inputs {
-            return Err(Error::DimensionMismatch(self.inputs, x.len()));
+            return Err(dimension_mismatch(index, self.inputs, x.len()));
         }
 
-        self.neurons
+        Ok(self
+            .neurons
             .iter()
-            .map(|neuron| neuron.call(x))
-            .collect::<Result<Vec<_>>>()
+            .map(|neuron| neuron.call(x, activation, self.quantized))
+            .collect())
+    }
+
+    // Every neuron's weighted sum is a single fused node per sample, sharing
+    // the weight vectors across the batch
+    fn call_batch(
+        &self,
+        index: usize,
+        xs: &[Vec<Value>],
+        activation: &Activation,
+    ) -> Result<Vec<Vec<Value>>> {
+        xs.iter()
+            .map(|x| {
+                if x.len() != self.inputs {
+                    return Err(dimension_mismatch(index, self.inputs, x.len()));
+                }
+
+                Ok(self
+                    .neurons
+                    .iter()
+                    .map(|neuron| {
+                        let weights = neuron.weights(self.quantized);
+
+                        activation.call(neuron.bias.clone() + Value::dot(&weights, x))
+                    })
+                    .collect())
+            })
+            .collect()
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.neurons.len() * (self.inputs + 1)
+    }
+
+    fn infer(&self, x: &[f64], activation: &Activation) -> Vec<f64> {
+        // gathered once, so that the products run over contiguous memory
+        let weights: Vec<_> = self
+            .neurons
+            .iter()
+            .flat_map(|neuron| neuron.inference_weights(self.quantized))
+            .collect();
+
+        weights
+            .chunks(self.inputs.max(1))
+            .zip(&self.neurons)
+            .map(|(weights, neuron)| activation.infer(neuron.bias.value() + dot(weights, x)))
+            .collect()
+    }
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Linear(in_features={}, out_features={})",
+            self.inputs,
+            self.neurons.len(),
+        )
     }
 }
 
 pub struct Mlp {
     inputs: usize,
     layers: Vec<Layer>,
+    activation: Activation,
+    training: bool,
 }
 
 impl Mlp {
+    /// Panics unless the model has inputs and layers with neurons, see
+    /// [`Mlp::try_new`]
     pub fn new<R: Rng>(inputs: usize, layer_sizes: &[usize], rng: &mut R) -> Self {
+        Self::try_new(inputs, layer_sizes, rng).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Builds a model with `layer_sizes` outputs of every layer, failing
+    /// without inputs, without layers or for layers without neurons
+    pub fn try_new<R: Rng>(inputs: usize, layer_sizes: &[usize], rng: &mut R) -> Result<Self> {
+        if inputs == 0 {
+            return Err(Error::NoInputs);
+        }
+        if layer_sizes.is_empty() {
+            return Err(Error::NoLayers);
+        }
+        if let Some(layer) = layer_sizes.iter().position(|&size| size == 0) {
+            return Err(Error::EmptyLayer(layer));
+        }
+
         let layers = [&[inputs], layer_sizes]
             .concat()
             .windows(2)
             .map(|w| Layer::new(w[0], w[1], rng))
             .collect();
 
-        Self { inputs, layers }
+        Ok(Self {
+            inputs,
+            layers,
+            activation: Activation::tanh(),
+            training: true,
+        })
+    }
+
+    /// Builds a model with weights fully determined by `seed`
+    pub fn new_seeded(inputs: usize, layer_sizes: &[usize], seed: u64) -> Self {
+        Self::new(inputs, layer_sizes, &mut ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Replaces the activation of every layer, tanh by default
+    pub fn with_activation(mut self, activation: Activation) -> Self {
+        self.activation = activation;
+        self
+    }
+
+    pub fn activation(&self) -> &Activation {
+        &self.activation
+    }
+
+    /// Quantization-aware training of every layer, see
+    /// [`Mlp::quantization_aware_layers`]
+    pub fn quantization_aware(self) -> Self {
+        let layers: Vec<_> = (0..self.layers.len()).collect();
+
+        self.quantization_aware_layers(&layers)
+    }
+
+    /// Runs the given layers with their weights rounded like `Mlp::quantize`
+    /// does, with gradients passed straight through to the full precision
+    /// weights, so that training accounts for the rounding. Outputs then match
+    /// those of the quantized model. Unknown layer indices are ignored.
+    pub fn quantization_aware_layers(mut self, layers: &[usize]) -> Self {
+        for &index in layers {
+            if let Some(layer) = self.layers.get_mut(index) {
+                layer.quantized = true;
+            }
+        }
+
+        self
+    }
+
+    /// Values of the parameters grouped by layer like `layer_parameters`, with
+    /// the weights of quantization-aware layers rounded as `infer` uses them,
+    /// for exporting the model
+    pub(crate) fn inference_parameters(&self) -> Vec<Vec<f64>> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .neurons
+                    .iter()
+                    .flat_map(|neuron| {
+                        let mut parameters = neuron.inference_weights(layer.quantized);
+                        parameters.push(neuron.bias.value());
+                        parameters
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Indices of the layers trained quantization-aware
+    pub fn quantized_layers(&self) -> Vec<usize> {
+        (0..self.layers.len())
+            .filter(|&index| self.layers[index].quantized)
+            .collect()
+    }
+
+    /// Switches to training mode, the mode models start in. Stateful layers,
+    /// e.g. ones drawing random masks or keeping running statistics, only
+    /// update their state in training mode.
+    pub fn train(&mut self) {
+        self.training = true;
+    }
+
+    /// Switches to evaluation mode, in which stateful layers behave
+    /// deterministically for inference
+    pub fn eval(&mut self) {
+        self.training = false;
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
     }
 
     pub fn predict(&self, x: &[Value]) -> Result<Vec<Value>> {
+        self.layers
+            .iter()
+            .enumerate()
+            .try_fold(x.to_vec(), |result, (index, layer)| {
+                layer.call(index, &result, &self.activation)
+            })
+    }
+
+    /// Outputs for a whole batch of samples, equivalent to calling `predict`
+    /// on each, but with the weighted sums as fused nodes, so that the graph
+    /// has a few nodes per neuron and sample instead of two per weight
+    pub fn predict_batch(&self, xs: &[Vec<Value>]) -> Result<Vec<Vec<Value>>> {
+        self.layers
+            .iter()
+            .enumerate()
+            .try_fold(xs.to_vec(), |xs, (index, layer)| {
+                layer.call_batch(index, &xs, &self.activation)
+            })
+    }
+
+    /// Computes the outputs with plain `f64` arithmetic, without building a
+    /// graph. The results match those of `predict` up to rounding, since the
+    /// products are summed in a different order, but can't be backpropagated.
+    pub fn infer(&self, x: &[f64]) -> Result<Vec<f64>> {
         if x.len() != self.inputs {
-            return Err(Error::DimensionMismatch(self.inputs, x.len()));
+            return Err(dimension_mismatch(0, self.inputs, x.len()));
+        }
+
+        Ok(self.layers.iter().fold(x.to_vec(), |result, layer| {
+            layer.infer(&result, &self.activation)
+        }))
+    }
+
+    /// Number of inputs followed by the number of outputs of every layer
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        [self.inputs]
+            .into_iter()
+            .chain(self.layers.iter().map(|layer| layer.neurons.len()))
+            .collect()
+    }
+
+    /// Overwrites the parameter values, given in the order of `parameters`
+    pub fn load_parameters(&self, values: &[f64]) -> Result<()> {
+        let parameters = self.parameters();
+
+        if parameters.len() != values.len() {
+            return Err(Error::ParameterMismatch(parameters.len(), values.len()));
         }
 
-        let init = self.layers[0].call(x)?;
+        for (parameter, value) in parameters.iter().zip(values) {
+            parameter.set_value(*value);
+        }
+
+        Ok(())
+    }
 
-        self.layers[1..]
+    /// Approximate heap usage of the parameters and the vectors holding them.
+    /// Graphs built by `predict` are separate, see `Value::memory_estimate`.
+    pub fn memory_estimate(&self) -> MemoryEstimate {
+        let structure = self.layers.capacity() * mem::size_of::<Layer>()
+            + self
+                .layers
+                .iter()
+                .map(|layer| {
+                    layer.neurons.capacity() * mem::size_of::<Neuron>()
+                        + layer
+                            .neurons
+                            .iter()
+                            .map(|neuron| neuron.weights.capacity() * mem::size_of::<Value>())
+                            .sum::<usize>()
+                })
+                .sum::<usize>();
+
+        self.parameters().iter().map(Value::memory_estimate).fold(
+            MemoryEstimate {
+                nodes: 0,
+                bytes: structure,
+            },
+            |total, parameter| total + parameter,
+        )
+    }
+
+    pub fn parameters(&self) -> Vec<Value> {
+        self.layer_parameters().concat()
+    }
+
+    /// Parameters grouped by layer, each in the order of `parameters`
+    pub fn layer_parameters(&self) -> Vec<Vec<Value>> {
+        self.layers
             .iter()
-            .try_fold(init, |result, layer| layer.call(&result))
+            .map(|layer| {
+                layer
+                    .neurons
+                    .iter()
+                    .flat_map(|neuron| neuron.weights.iter().chain([&neuron.bias]))
+                    .cloned()
+                    .collect()
+            })
+            .collect()
     }
+}
 
-    pub fn nudge_parameters(&self, rate: f64) {
-        for layer in &self.layers {
-            for neuron in &layer.neurons {
-                neuron.bias.nudge(rate);
+/// Lists the layers like PyTorch prints a model, e.g.
+///
+/// ```text
+/// Mlp(
+///   (0): Linear(in_features=3, out_features=4) + Tanh [16 parameters]
+///   (1): Linear(in_features=4, out_features=1) + Tanh [5 parameters]
+/// )
+/// 21 parameters
+/// ```
+impl Display for Mlp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Mlp(")?;
+        for (index, layer) in self.layers.iter().enumerate() {
+            writeln!(
+                f,
+                "  ({index}): {layer} + {} [{} parameters]",
+                self.activation,
+                layer.parameter_count()
+            )?;
+        }
+        writeln!(f, ")")?;
 
-                for weight in &neuron.weights {
-                    weight.nudge(rate);
-                }
-            }
+        let parameters: usize = self.layers.iter().map(Layer::parameter_count).sum();
+
+        write!(f, "{parameters} parameters")
+    }
+}
+
+/// Probabilities of a softmax over `logits` divided by `temperature`, which
+/// sharpens the distribution below 1 and flattens it above.
+///
+/// The shift by the maximum logit is a constant, which keeps the gradients.
+pub fn softmax(logits: &[Value], temperature: f64) -> Vec<Value> {
+    let max = logits
+        .iter()
+        .map(Value::value)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let scale = Value::new(1.0 / temperature, "1/T");
+
+    let exps: Vec<_> = logits
+        .iter()
+        .map(|l| ((l.clone() - Value::new(max, "max")) * scale.clone()).exp())
+        .collect();
+    let total = exps
+        .iter()
+        .fold(Value::new(0.0, "0"), |sum, e| sum + e.clone())
+        .pow(-1.0);
+
+    exps.into_iter().map(|e| e * total.clone()).collect()
+}
+
+const LANES: usize = 4;
+
+/// Dot product accumulated in `LANES` independent sums, which the compiler can
+/// vectorize, with a scalar loop over the remainder
+pub(crate) fn dot<T: Copy + Into<f64>>(weights: &[T], x: &[f64]) -> f64 {
+    let len = weights.len().min(x.len());
+    let (weights, x) = (&weights[..len], &x[..len]);
+
+    let mut sums = [0.0; LANES];
+    let chunks = weights.chunks_exact(LANES).zip(x.chunks_exact(LANES));
+
+    for (w, x) in chunks {
+        for lane in 0..LANES {
+            sums[lane] += w[lane].into() * x[lane];
         }
     }
+
+    let tail = len - len % LANES;
+    let remainder: f64 = weights[tail..]
+        .iter()
+        .zip(&x[tail..])
+        .map(|(&w, x)| w.into() * x)
+        .sum();
+
+    (sums[0] + sums[1]) + (sums[2] + sums[3]) + remainder
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Mlp, Neuron};
+    use super::{dot, softmax, Activation, Error, Mlp, Neuron, Stage};
     use crate::value::Value;
     use rand::SeedableRng;
     use rand_chacha::ChaCha8Rng;
@@ -132,22 +603,22 @@ mod tests {
             bias: Value::new(-0.3, "b"),
         };
 
-        let out = neuron
-            .call(&[
+        let out = neuron.call(
+            &[
                 Value::new(1.0, "x_1"),
                 Value::new(2.0, "x_2"),
                 Value::new(3.0, "x_3"),
-            ])
-            .expect("neuron call should succeed");
+            ],
+            &Activation::tanh(),
+            false,
+        );
 
         assert_eq!(out.value(), 0.9640275800758169)
     }
 
     #[test]
     fn perceptron() {
-        let mut rng = ChaCha8Rng::seed_from_u64(1);
-
-        let mlp = Mlp::new(3, &[4, 4, 1], &mut rng);
+        let mlp = Mlp::new_seeded(3, &[4, 4, 1], 1);
         let x = [
             Value::new(2.0, "x_1"),
             Value::new(3.0, "x_2"),
@@ -158,4 +629,195 @@ mod tests {
 
         assert_eq!(out[0].value(), -0.5146818780021741);
     }
+
+    #[test]
+    fn display() {
+        let mlp = Mlp::new_seeded(3, &[4, 1], 0);
+
+        assert_eq!(
+            mlp.to_string(),
+            "Mlp(
+  (0): Linear(in_features=3, out_features=4) + Tanh [16 parameters]
+  (1): Linear(in_features=4, out_features=1) + Tanh [5 parameters]
+)
+21 parameters"
+        );
+    }
+
+    #[test]
+    fn infer() {
+        let mlp = Mlp::new_seeded(3, &[4, 4, 2], 1);
+        let x = [2.0, 3.0, -1.0];
+
+        let predicted: Vec<_> = mlp
+            .predict(&x.map(|x| Value::new(x, "x")))
+            .expect("should calculate")
+            .iter()
+            .map(Value::value)
+            .collect();
+
+        let inferred = mlp.infer(&x).expect("should calculate");
+
+        for (inferred, predicted) in inferred.iter().zip(predicted) {
+            assert!((inferred - predicted).abs() < 1e-12);
+        }
+        assert!(mlp.infer(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+
+        assert!(matches!(
+            mlp.predict(&[Value::new(1.0, "x")]),
+            Err(Error::DimensionMismatch {
+                layer: 0,
+                stage: Stage::Input,
+                expected: 3,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            mlp.layers[1].call(1, &[Value::new(1.0, "x")], &Activation::tanh()),
+            Err(Error::DimensionMismatch {
+                layer: 1,
+                stage: Stage::Hidden,
+                expected: 4,
+                ..
+            })
+        ));
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(matches!(
+            Mlp::try_new(0, &[1], &mut rng),
+            Err(Error::NoInputs)
+        ));
+        assert!(matches!(
+            Mlp::try_new(2, &[], &mut rng),
+            Err(Error::NoLayers)
+        ));
+        assert!(matches!(
+            Mlp::try_new(2, &[4, 0, 1], &mut rng),
+            Err(Error::EmptyLayer(1))
+        ));
+        assert!(Mlp::try_new(2, &[4, 1], &mut rng).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "Layer 0 has no neurons")]
+    fn empty_layer() {
+        Mlp::new_seeded(2, &[0], 0);
+    }
+
+    #[test]
+    fn predict_batch() {
+        let mlp = Mlp::new_seeded(3, &[8, 8, 2], 1);
+        let xs: Vec<Vec<_>> = (0..4)
+            .map(|i| {
+                (0..3)
+                    .map(|j| Value::new((i * j) as f64 * 0.3, "x"))
+                    .collect()
+            })
+            .collect();
+
+        let batch = mlp.predict_batch(&xs).expect("should calculate");
+        let loss = batch
+            .concat()
+            .into_iter()
+            .fold(Value::new(0.0, "0"), |s, y| s + y);
+        loss.backpropagate();
+        let batch_gradients: Vec<_> = mlp.parameters().iter().map(Value::gradient).collect();
+
+        for p in mlp.parameters() {
+            p.zero_gradient();
+        }
+
+        let single: Vec<_> = xs
+            .iter()
+            .map(|x| mlp.predict(x).expect("should calculate"))
+            .collect();
+        let expected = single
+            .concat()
+            .into_iter()
+            .fold(Value::new(0.0, "0"), |s, y| s + y);
+        expected.backpropagate();
+
+        assert!((loss.value() - expected.value()).abs() < 1e-12);
+        for (p, g) in mlp.parameters().iter().zip(batch_gradients) {
+            assert!((p.gradient() - g).abs() < 1e-12);
+        }
+
+        assert!(loss.memory_estimate().nodes * 2 < expected.memory_estimate().nodes);
+        assert!(mlp.predict_batch(&[vec![Value::new(1.0, "x")]]).is_err());
+    }
+
+    #[test]
+    fn memory_estimate() {
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let estimate = mlp.memory_estimate();
+
+        assert_eq!(estimate.nodes, mlp.parameters().len());
+        assert!(estimate.bytes > estimate.nodes * std::mem::size_of::<f64>() * 2);
+
+        let out = mlp
+            .predict(&[
+                Value::new(1.0, "x_1"),
+                Value::new(2.0, "x_2"),
+                Value::new(3.0, "x_3"),
+            ])
+            .expect("should calculate");
+        let graph = out[0].memory_estimate();
+
+        assert!(graph.nodes > estimate.nodes);
+        assert!(graph.bytes > estimate.bytes / 2);
+    }
+
+    #[test]
+    fn softmax_temperature() {
+        let logits = [Value::new(2.0, "a"), Value::new(0.0, "b")];
+        let p = |temperature| -> Vec<_> {
+            softmax(&logits, temperature)
+                .iter()
+                .map(Value::value)
+                .collect()
+        };
+
+        assert!((p(1.0)[0] - 1.0 / (1.0 + (-2.0f64).exp())).abs() < 1e-12);
+        assert!((p(2.0)[0] - 1.0 / (1.0 + (-1.0f64).exp())).abs() < 1e-12);
+        assert!(p(0.1)[0] > 0.999);
+        assert!((p(1e6)[0] - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn chunked_dot() {
+        for len in [0, 1, 3, 4, 7, 16, 33] {
+            let weights: Vec<_> = (0..len).map(|i| (i as f64 * 0.37).sin()).collect();
+            let x: Vec<_> = (0..len).map(|i| (i as f64 * 1.3).cos()).collect();
+
+            let expected: f64 = weights.iter().zip(&x).map(|(w, x)| w * x).sum();
+
+            assert!((dot(&weights, &x) - expected).abs() < 1e-12);
+        }
+
+        assert_eq!(dot(&[1i8, -2, 3, 4, 5], &[1.0, 1.0, 1.0, 1.0, 2.0]), 16.0);
+    }
+
+    #[test]
+    fn parameters() {
+        let mlp = Mlp::new_seeded(3, &[4, 4, 1], 1);
+
+        assert_eq!(mlp.parameters().len(), (3 + 1) * 4 + (4 + 1) * 4 + (4 + 1));
+    }
+
+    #[test]
+    fn new_seeded() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+
+        let expected = Mlp::new(3, &[4, 1], &mut rng);
+        let seeded = Mlp::new_seeded(3, &[4, 1], 7);
+
+        let values = |mlp: &Mlp| -> Vec<_> { mlp.parameters().iter().map(|p| p.value()).collect() };
+
+        assert_eq!(values(&seeded), values(&expected));
+    }
 }
@@ -0,0 +1,212 @@
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::fmt::{self, Debug, Display};
+#[cfg(feature = "std")]
+use std::{collections::BTreeMap, sync::RwLock};
+
+#[cfg(feature = "std")]
+use super::{Error, Result};
+use crate::{math, value::Value};
+
+type Function = dyn Fn(Value) -> Value + Send + Sync;
+type Inference = dyn Fn(f64) -> f64 + Send + Sync;
+
+/// Nonlinearity applied to the weighted sum of every neuron, tanh by default.
+///
+/// Models are saved with the name of their activation, custom ones have to be
+/// registered with [`register_activation`] to be loaded again.
+#[derive(Clone)]
+pub struct Activation {
+    name: String,
+    // `None` for the built-in tanh
+    custom: Option<Custom>,
+}
+
+#[derive(Clone)]
+struct Custom {
+    function: Arc<Function>,
+    inference: Arc<Inference>,
+}
+
+impl Activation {
+    pub fn tanh() -> Self {
+        Self {
+            name: "tanh".to_string(),
+            custom: None,
+        }
+    }
+
+    /// A custom activation building its output from `Value` operations, which
+    /// also define its gradient, and computing the same function on plain
+    /// floats for inference without a graph, e.g.
+    ///
+    /// ```
+    /// # use micrograd::nn::Activation;
+    /// let swish = Activation::new(
+    ///     "swish",
+    ///     |x| x.clone() * x.sigmoid(),
+    ///     |x| x / (1.0 + (-x).exp()),
+    /// );
+    /// ```
+    pub fn new<F, I>(name: &str, function: F, inference: I) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+        I: Fn(f64) -> f64 + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            custom: Some(Custom {
+                function: Arc::new(function),
+                inference: Arc::new(inference),
+            }),
+        }
+    }
+
+    /// The built-in or registered activation called `name`
+    #[cfg(feature = "std")]
+    pub fn named(name: &str) -> Option<Self> {
+        if name == "tanh" {
+            return Some(Self::tanh());
+        }
+
+        let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+
+        registry.get(name).cloned()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(super) fn call(&self, x: Value) -> Value {
+        match &self.custom {
+            Some(custom) => (custom.function)(x),
+            None => x.tanh(),
+        }
+    }
+
+    pub(super) fn infer(&self, x: f64) -> f64 {
+        match &self.custom {
+            Some(custom) => (custom.inference)(x),
+            None => math::tanh(x),
+        }
+    }
+}
+
+impl Default for Activation {
+    fn default() -> Self {
+        Self::tanh()
+    }
+}
+
+impl Debug for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Activation").field(&self.name).finish()
+    }
+}
+
+/// Capitalized like PyTorch modules for tanh, e.g. `Tanh`, the name otherwise
+impl Display for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.custom {
+            Some(_) => write!(f, "{}", self.name),
+            None => write!(f, "Tanh"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+static REGISTRY: RwLock<BTreeMap<String, Activation>> = RwLock::new(BTreeMap::new());
+
+/// Makes a custom activation available by name to [`Activation::named`], and
+/// with it to saved models and configs. Replaces an earlier registration of
+/// the same name, fails for the name of the built-in `tanh`.
+#[cfg(feature = "std")]
+pub fn register_activation(activation: Activation) -> Result<()> {
+    if activation.name == "tanh" {
+        return Err(Error::BuiltinActivation(activation.name));
+    }
+
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry.insert(activation.name.clone(), activation);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Activation;
+    use crate::{nn::Mlp, value::Value};
+
+    #[test]
+    fn custom_activation() {
+        let relu = Activation::new(
+            "test_relu",
+            |x| {
+                if x.value() > 0.0 {
+                    x
+                } else {
+                    x * Value::new(0.0, "0")
+                }
+            },
+            |x| x.max(0.0),
+        );
+        assert_eq!(relu.infer(-2.0), 0.0);
+        assert_eq!(relu.infer(1.5), 1.5);
+        // inference doesn't build values, which lazy mode leaves uncomputed
+        assert_eq!(crate::value::lazy(|| relu.infer(1.5)), 1.5);
+        assert_eq!(relu.to_string(), "test_relu");
+        assert_eq!(Activation::tanh().to_string(), "Tanh");
+
+        let mlp = Mlp::new_seeded(2, &[4, 1], 1).with_activation(relu.clone());
+        assert_eq!(mlp.activation().name(), "test_relu");
+
+        let x = [0.5, -0.5];
+        let output = mlp
+            .predict(&[Value::new(x[0], "x_1"), Value::new(x[1], "x_2")])
+            .expect("should predict");
+        let inferred = mlp.infer(&x).expect("should infer");
+        assert!((output[0].value() - inferred[0]).abs() < 1e-12);
+        assert!(inferred[0] >= 0.0);
+
+        let batch = mlp
+            .predict_batch(&[vec![Value::new(x[0], "x_1"), Value::new(x[1], "x_2")]])
+            .expect("should predict");
+        assert!((batch[0][0].value() - inferred[0]).abs() < 1e-12);
+
+        output[0].backpropagate();
+        assert!(mlp.parameters().iter().any(|p| p.gradient() != 0.0));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn registry() {
+        assert!(Activation::named("test_softsign").is_none());
+        assert!(Activation::named("tanh").is_some());
+
+        super::register_activation(Activation::new(
+            "test_softsign",
+            |x| x.clone() * (x.clone() * x + Value::new(1.0, "1")).pow(-0.5),
+            |x| x / (x * x + 1.0).sqrt(),
+        ))
+        .expect("should register");
+
+        let tanh = Activation::new("tanh", |x| x, |x| x);
+        assert!(matches!(
+            super::register_activation(tanh),
+            Err(crate::nn::Error::BuiltinActivation(name)) if name == "tanh"
+        ));
+        assert_eq!(
+            Activation::named("tanh")
+                .expect("should be built in")
+                .infer(1.0),
+            1f64.tanh()
+        );
+
+        let softsign = Activation::named("test_softsign").expect("should be registered");
+        assert_eq!(softsign.infer(0.0), 0.0);
+        assert!((softsign.infer(3.0) - 3.0 / 10f64.sqrt()).abs() < 1e-12);
+    }
+}
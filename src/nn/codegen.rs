@@ -0,0 +1,183 @@
+use alloc::{format, string::String, vec::Vec};
+
+use super::{Error, Mlp, Result};
+
+impl Mlp {
+    /// Emits standalone Rust source of a `pub fn predict(x: &[f64; N]) -> [f64; M]`
+    /// computing this model, with the current weights baked in as constants,
+    /// rounded like `infer` rounds them in quantization-aware layers.
+    ///
+    /// The source has no dependencies besides `f64::tanh`, and its outputs
+    /// match those of `infer` up to rounding. Custom activations are called as
+    /// a `fn(f64) -> f64` of their name, which the including code has to define,
+    /// and fail if the name isn't a Rust identifier.
+    pub fn to_rust_source(&self) -> Result<String> {
+        let sizes = self.layer_sizes();
+        let architecture: Vec<_> = sizes.iter().map(|size| format!("{size}")).collect();
+
+        let mut source = format!(
+            "// Generated by micrograd from a {} model\n",
+            architecture.join("-")
+        );
+
+        for (l, (layer, parameters)) in self
+            .layers
+            .iter()
+            .zip(self.inference_parameters())
+            .enumerate()
+        {
+            let (inputs, outputs) = (layer.inputs, layer.neurons.len());
+            let neurons: Vec<_> = parameters.chunks(inputs + 1).collect();
+            let biases: Vec<_> = neurons.iter().map(|neuron| neuron[inputs]).collect();
+
+            source.push_str(&format!("\nconst W{l}: [[f64; {inputs}]; {outputs}] = [\n"));
+            for neuron in &neurons {
+                source.push_str(&format!("    [{}],\n", literals(&neuron[..inputs])));
+            }
+            source.push_str("];\n");
+            source.push_str(&format!(
+                "const B{l}: [f64; {outputs}] = [{}];\n",
+                literals(&biases)
+            ));
+        }
+
+        source.push_str(&format!(
+            "\npub fn predict(x: &[f64; {}]) -> [f64; {}] {{\n",
+            sizes[0],
+            sizes[sizes.len() - 1]
+        ));
+
+        let mut input = String::from("x");
+        for l in 0..self.layers.len().saturating_sub(1) {
+            source.push_str(&format!("    let h{l} = layer({input}, &W{l}, &B{l});\n"));
+            input = format!("&h{l}");
+        }
+
+        match self.layers.len() {
+            0 => source.push_str("    *x\n}\n"),
+            1 => source.push_str("    layer(x, &W0, &B0)\n}\n"),
+            layers => {
+                let l = layers - 1;
+                source.push_str(&format!("\n    layer({input}, &W{l}, &B{l})\n}}\n"));
+            }
+        }
+
+        let activation = match self.activation.name() {
+            "tanh" => String::from("sum.tanh()"),
+            name if is_identifier(name) => format!("{name}(sum)"),
+            name => return Err(Error::ActivationIdentifier(String::from(name))),
+        };
+        source.push_str(&LAYER.replace("{activation}", &activation));
+
+        Ok(source)
+    }
+}
+
+// Keywords can't name a function, `_` isn't an identifier at all
+const KEYWORDS: &[&str] = &[
+    "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+
+    start && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !KEYWORDS.contains(&name)
+}
+
+const LAYER: &str = "
+fn layer<const I: usize, const O: usize>(x: &[f64; I], w: &[[f64; I]; O], b: &[f64; O]) -> [f64; O] {
+    core::array::from_fn(|o| {
+        let sum = w[o].iter().zip(x).fold(b[o], |sum, (w, x)| sum + w * x);
+
+        {activation}
+    })
+}
+";
+
+// Debug formatting round-trips and always includes a decimal point or exponent
+fn literals(values: &[f64]) -> String {
+    values
+        .iter()
+        .map(|&value| match value {
+            v if v.is_nan() => String::from("f64::NAN"),
+            v if v == f64::INFINITY => String::from("f64::INFINITY"),
+            v if v == f64::NEG_INFINITY => String::from("f64::NEG_INFINITY"),
+            v => format!("{v:?}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn::{Activation, Error, Mlp};
+
+    #[test]
+    fn rust_source() {
+        let mlp = Mlp::new_seeded(2, &[2, 1], 0);
+        mlp.load_parameters(&[0.5, -1.0, 0.0, 1e-20, 2.0, 3.0, -0.25, 1.5, 4.0])
+            .expect("should load");
+
+        let expected = "// Generated by micrograd from a 2-2-1 model
+
+const W0: [[f64; 2]; 2] = [
+    [0.5, -1.0],
+    [1e-20, 2.0],
+];
+const B0: [f64; 2] = [0.0, 3.0];
+
+const W1: [[f64; 2]; 1] = [
+    [-0.25, 1.5],
+];
+const B1: [f64; 1] = [4.0];
+
+pub fn predict(x: &[f64; 2]) -> [f64; 1] {
+    let h0 = layer(x, &W0, &B0);
+
+    layer(&h0, &W1, &B1)
+}
+";
+
+        let source = mlp.to_rust_source().expect("should generate");
+        assert!(source.starts_with(expected));
+        assert!(source.contains("sum.tanh()"));
+
+        let swish = Activation::new("swish", |x| x.clone() * x.sigmoid(), |x| x);
+        let source = mlp
+            .with_activation(swish)
+            .to_rust_source()
+            .expect("should generate");
+        assert!(source.starts_with(expected));
+        assert!(source.contains("        swish(sum)\n"));
+
+        // the source wouldn't compile with these called as functions
+        for name in ["leaky-relu", "my act", "2x", "fn", ""] {
+            let activation = Activation::new(name, |x| x, |x| x);
+            let mlp = Mlp::new_seeded(2, &[1], 0).with_activation(activation);
+            assert!(matches!(
+                mlp.to_rust_source(),
+                Err(Error::ActivationIdentifier(n)) if n == name
+            ));
+        }
+    }
+
+    #[test]
+    fn quantization_aware() {
+        let mlp = Mlp::new_seeded(2, &[1], 0).quantization_aware();
+        mlp.load_parameters(&[1.0, 0.3, 0.0]).expect("should load");
+
+        // rounded to the int8 levels of the layer, like `infer`
+        let source = mlp.to_rust_source().expect("should generate");
+        assert!(
+            source.contains(&format!("[1.0, {:?}]", 38.0 / 127.0)),
+            "{source}"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use super::{Error, Mlp, Result};
+
+/// Absolute differences between the parameters of the same layer of two
+/// models, biases included. A parameter NaN in only one of the models differs
+/// by infinity, while NaN or the same infinity in both doesn't differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayerDiff {
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// Differences between the parameters of two models of the same architecture,
+/// from the first layer to the last
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelDiff {
+    pub layers: Vec<LayerDiff>,
+}
+
+impl ModelDiff {
+    /// Largest absolute difference of any parameter, NaN if any layer's is
+    pub fn max(&self) -> f64 {
+        self.layers
+            .iter()
+            .map(|layer| layer.max)
+            .fold(0.0, |max, x| if x > max || x.is_nan() { x } else { max })
+    }
+
+    /// Whether no parameter differs by more than `tolerance`
+    pub fn within(&self, tolerance: f64) -> bool {
+        self.max() <= tolerance
+    }
+
+    pub fn is_identical(&self) -> bool {
+        self.within(0.0)
+    }
+}
+
+/// Lists the differences of every layer, e.g.
+///
+/// ```text
+/// layer 0: max 1.2e-5, mean 3.4e-6
+/// layer 1: max 0.0e0, mean 0.0e0
+/// ```
+impl Display for ModelDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (layer, diff) in self.layers.iter().enumerate() {
+            if layer > 0 {
+                writeln!(f)?;
+            }
+
+            write!(
+                f,
+                "layer {layer}: max {:.1e}, mean {:.1e}",
+                diff.max, diff.mean
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares the parameters of two models layer by layer, e.g. to check that a
+/// checkpoint or an imported model has the weights it should. Fails unless the
+/// models have the same layer sizes.
+pub fn diff(a: &Mlp, b: &Mlp) -> Result<ModelDiff> {
+    if a.layer_sizes() != b.layer_sizes() {
+        return Err(Error::ArchitectureMismatch(
+            a.layer_sizes(),
+            b.layer_sizes(),
+        ));
+    }
+
+    let layers = a
+        .layer_parameters()
+        .iter()
+        .zip(b.layer_parameters())
+        .map(|(a, b)| {
+            let differences: Vec<_> = a
+                .iter()
+                .zip(&b)
+                .map(|(a, b)| difference(a.value(), b.value()))
+                .collect();
+
+            LayerDiff {
+                max: differences.iter().copied().fold(0.0, f64::max),
+                mean: differences.iter().sum::<f64>() / differences.len().max(1) as f64,
+            }
+        })
+        .collect();
+
+    Ok(ModelDiff { layers })
+}
+
+fn difference(a: f64, b: f64) -> f64 {
+    if a == b || (a.is_nan() && b.is_nan()) {
+        0.0
+    } else if a.is_nan() || b.is_nan() {
+        f64::INFINITY
+    } else {
+        (a - b).abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, LayerDiff, ModelDiff};
+    use crate::nn::{Error, Mlp};
+
+    #[test]
+    fn model_diff() {
+        let a = Mlp::new_seeded(2, &[2, 1], 0);
+        let b = Mlp::new_seeded(2, &[2, 1], 0);
+
+        let same = diff(&a, &b).expect("should compare");
+        assert_eq!(same.layers.len(), 2);
+        assert!(same.is_identical());
+
+        let mut values: Vec<_> = b.parameters().iter().map(|p| p.value()).collect();
+        values[1] += 0.5;
+        values[4] -= 0.25;
+        b.load_parameters(&values).expect("should load");
+
+        let changed = diff(&a, &b).expect("should compare");
+        assert_eq!(changed.layers[0].max, 0.5);
+        assert!((changed.layers[0].mean - 0.75 / 6.0).abs() < 1e-12);
+        assert_eq!(changed.layers[1].max, 0.0);
+        assert_eq!(changed.max(), 0.5);
+        assert!(changed.within(0.5) && !changed.within(0.4));
+        assert_eq!(
+            changed.to_string(),
+            "layer 0: max 5.0e-1, mean 1.2e-1\nlayer 1: max 0.0e0, mean 0.0e0"
+        );
+
+        let other = Mlp::new_seeded(2, &[3, 1], 0);
+        assert!(matches!(
+            diff(&a, &other),
+            Err(Error::ArchitectureMismatch(a, b)) if a == [2, 2, 1] && b == [2, 3, 1]
+        ));
+    }
+
+    #[test]
+    fn non_finite_parameters() {
+        let a = Mlp::new_seeded(2, &[2, 1], 0);
+        let b = Mlp::new_seeded(2, &[2, 1], 0);
+
+        b.parameters()[1].set_value(f64::NAN);
+        let changed = diff(&a, &b).expect("should compare");
+        assert_eq!(changed.layers[0].max, f64::INFINITY);
+        assert!(!changed.within(1e6) && !changed.is_identical());
+
+        // a diverged model saved and loaded again has the same weights
+        a.parameters()[1].set_value(f64::NAN);
+        a.parameters()[2].set_value(f64::INFINITY);
+        b.parameters()[2].set_value(f64::INFINITY);
+        assert!(diff(&a, &b).expect("should compare").is_identical());
+
+        let nan = ModelDiff {
+            layers: vec![
+                LayerDiff {
+                    max: f64::NAN,
+                    mean: f64::NAN,
+                },
+                LayerDiff::default(),
+            ],
+        };
+        assert!(nan.max().is_nan());
+        assert!(!nan.within(f64::INFINITY));
+    }
+}
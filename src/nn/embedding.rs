@@ -0,0 +1,83 @@
+use alloc::{format, vec::Vec};
+
+use rand::Rng;
+
+use super::{Error, Result};
+use crate::value::Value;
+
+/// A learned vector per token of a vocabulary, e.g. for characters fed to a
+/// language model
+pub struct Embedding {
+    vectors: Vec<Vec<Value>>,
+}
+
+impl Embedding {
+    pub fn new<R: Rng>(tokens: usize, dimensions: usize, rng: &mut R) -> Self {
+        let vectors = (0..tokens)
+            .map(|token| {
+                (0..dimensions)
+                    .map(|i| Value::new(rng.gen_range(-1.0..=1.0), &format!("e_{token}_{i}")))
+                    .collect()
+            })
+            .collect();
+
+        Self { vectors }
+    }
+
+    /// The vector of `token`
+    pub fn lookup(&self, token: usize) -> Result<&[Value]> {
+        self.vectors
+            .get(token)
+            .map(Vec::as_slice)
+            .ok_or(Error::UnknownToken(token, self.vectors.len()))
+    }
+
+    /// Vectors of a sequence of tokens, concatenated
+    pub fn lookup_all(&self, tokens: &[usize]) -> Result<Vec<Value>> {
+        Ok(tokens
+            .iter()
+            .map(|&token| self.lookup(token))
+            .collect::<Result<Vec<_>>>()?
+            .concat())
+    }
+
+    pub fn tokens(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.vectors.first().map_or(0, Vec::len)
+    }
+
+    /// Every vector, one token after another
+    pub fn parameters(&self) -> Vec<Value> {
+        self.vectors.concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::Embedding;
+    use crate::nn::Error;
+
+    #[test]
+    fn embedding() {
+        let embedding = Embedding::new(4, 3, &mut ChaCha8Rng::seed_from_u64(1));
+
+        assert_eq!((embedding.tokens(), embedding.dimensions()), (4, 3));
+        assert_eq!(embedding.parameters().len(), 12);
+
+        let vectors = embedding.lookup_all(&[2, 0]).expect("should look up");
+        assert_eq!(vectors.len(), 6);
+        assert_eq!(vectors[0].label(), "e_2_0");
+        assert_eq!(vectors[3].label(), "e_0_0");
+
+        assert!(matches!(
+            embedding.lookup(4),
+            Err(Error::UnknownToken(4, 4))
+        ));
+    }
+}
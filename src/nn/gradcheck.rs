@@ -0,0 +1,119 @@
+use alloc::vec::Vec;
+
+use super::{Module, Result};
+use crate::value::Value;
+
+/// Agreement of the backpropagated gradients of a module with finite
+/// differences
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GradientCheck {
+    /// Number of checked parameters
+    pub parameters: usize,
+    /// Largest difference of a backpropagated and a numerical gradient,
+    /// relative to the larger of the two when that exceeds 1
+    pub max_error: f64,
+    /// Index of the parameter with the largest error, in the order of
+    /// `Module::parameters`
+    pub worst: usize,
+}
+
+impl GradientCheck {
+    pub fn passed(&self, tolerance: f64) -> bool {
+        self.max_error <= tolerance
+    }
+}
+
+// Step of the central differences, balancing truncation and rounding errors
+const STEP: f64 = 1e-6;
+
+/// Compares the gradient of every parameter of `module` at `sample_input`
+/// against central differences, e.g. to test a new layer. The gradients are
+/// those of the outputs summed with weights 1, 2, 3, ..., so that mixed up
+/// outputs show too.
+///
+/// Parameter values are restored and gradients zeroed afterwards. Errors below
+/// about 1e-6 are expected of correct gradients of smooth functions.
+pub fn check_module_gradients<M: Module + ?Sized>(
+    module: &M,
+    sample_input: &[f64],
+) -> Result<GradientCheck> {
+    let x: Vec<_> = sample_input.iter().map(|&x| Value::new(x, "x")).collect();
+    let objective = || -> Result<Value> {
+        let outputs = module.predict(&x)?;
+
+        Ok(outputs
+            .into_iter()
+            .enumerate()
+            .fold(Value::new(0.0, "0"), |sum, (k, y)| {
+                sum + y * Value::new((k + 1) as f64, "k")
+            }))
+    };
+
+    let parameters = module.parameters();
+    parameters.iter().for_each(Value::zero_gradient);
+
+    objective()?.backpropagate();
+    let gradients: Vec<_> = parameters.iter().map(Value::gradient).collect();
+    parameters.iter().for_each(Value::zero_gradient);
+
+    let mut check = GradientCheck {
+        parameters: parameters.len(),
+        ..Default::default()
+    };
+
+    for (i, (parameter, gradient)) in parameters.iter().zip(gradients).enumerate() {
+        let value = parameter.value();
+
+        parameter.set_value(value + STEP);
+        let above = objective()?.value();
+        parameter.set_value(value - STEP);
+        let below = objective()?.value();
+        parameter.set_value(value);
+
+        let numerical = (above - below) / (2.0 * STEP);
+        let error = (gradient - numerical).abs() / gradient.abs().max(numerical.abs()).max(1.0);
+
+        if error > check.max_error {
+            check.max_error = error;
+            check.worst = i;
+        }
+    }
+
+    Ok(check)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_module_gradients;
+    use crate::nn::{Activation, MixtureOfExperts, Mlp, OneVsRest};
+
+    #[test]
+    fn module_gradients() {
+        let x = [0.3, -0.7];
+
+        let mlp = Mlp::new_seeded(2, &[4, 4, 2], 1);
+        let check = check_module_gradients(&mlp, &x).expect("should check");
+        assert_eq!(check.parameters, mlp.parameters().len());
+        assert!(check.passed(1e-6), "{check:?}");
+        assert!(mlp.parameters().iter().all(|p| p.gradient() == 0.0));
+
+        let moe = MixtureOfExperts::new_seeded(2, &[3, 2], 3, 1);
+        assert!(check_module_gradients(&moe, &x)
+            .expect("should check")
+            .passed(1e-6));
+
+        let ovr = OneVsRest::new_seeded(2, &[3], 3, 1);
+        assert!(check_module_gradients(&ovr, &x)
+            .expect("should check")
+            .passed(1e-6));
+
+        // the square's gradient through a detached factor misses half of it
+        let square = Activation::new("broken_square", |x| x.clone() * x.detach(), |x| x * x);
+        let broken = Mlp::new_seeded(2, &[4, 1], 1).with_activation(square);
+        assert!(!check_module_gradients(&broken, &x)
+            .expect("should check")
+            .passed(1e-2));
+
+        assert!(check_module_gradients(&mlp, &[1.0]).is_err());
+    }
+}
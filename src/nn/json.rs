@@ -0,0 +1,434 @@
+use std::collections::BTreeMap;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error as ThisError;
+
+use super::{Activation, Layer, Mlp, Neuron, Precision};
+use crate::value::Value;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Not a micrograd model, found format {0:?}")]
+    Format(String),
+    #[error("Unsupported model version {0}, expected at most {VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("Invalid model, {0}")]
+    Shape(String),
+    #[error("Unknown activation {0:?}, custom activations have to be registered first")]
+    UnknownActivation(String),
+    #[error("Weight {0} isn't representable in {1}")]
+    Unrepresentable(f64, Precision),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const FORMAT: &str = "micrograd-mlp";
+const VERSION: u32 = 1;
+
+/// Free-form notes stored alongside a model, e.g. the dataset it was trained on
+pub type Metadata = BTreeMap<String, String>;
+
+// Version 1 of the model format:
+//
+// {
+//   "format": "micrograd-mlp",
+//   "version": 1,
+//   "metadata": { "dataset": "moons" },
+//   "inputs": 2,
+//   "activation": "tanh", // or the name of a registered activation
+//   "precision": "f16", // optional, "f64" when missing
+//   "quantization_aware": [0, 1], // optional, layers trained with fake quantization
+//   "layers": [
+//     [{ "weights": [0.5, -0.25], "bias": 0.1 }, ...],
+//     ...
+//   ]
+// }
+//
+// Each layer is a list of neurons with one weight per output of the previous
+// layer. JSON has no NaN or infinities, weights of diverged models are written
+// as "NaN", "inf" and "-inf". New optional fields don't bump the version,
+// changes in meaning do.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelJson {
+    format: String,
+    version: u32,
+    #[serde(default)]
+    metadata: Metadata,
+    inputs: usize,
+    activation: String,
+    #[serde(default, skip_serializing_if = "is_f64")]
+    precision: Precision,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    quantization_aware: Vec<usize>,
+    layers: Vec<Vec<NeuronJson>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NeuronJson {
+    weights: Vec<Weight>,
+    bias: Weight,
+}
+
+struct Weight(f64);
+
+impl Serialize for Weight {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            x if x.is_nan() => serializer.serialize_str("NaN"),
+            f64::INFINITY => serializer.serialize_str("inf"),
+            f64::NEG_INFINITY => serializer.serialize_str("-inf"),
+            x => serializer.serialize_f64(x),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Weight {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(x) => Ok(Weight(x)),
+            Repr::Text(text) => match text.as_str() {
+                "NaN" => Ok(Weight(f64::NAN)),
+                "inf" => Ok(Weight(f64::INFINITY)),
+                "-inf" => Ok(Weight(f64::NEG_INFINITY)),
+                _ => Err(de::Error::custom(format!(
+                    "invalid weight {text:?}, expected a number, \"NaN\", \"inf\" or \"-inf\""
+                ))),
+            },
+        }
+    }
+}
+
+impl Mlp {
+    /// Serializes the architecture and weights into the versioned, human
+    /// readable JSON model format
+    pub fn to_json(&self) -> String {
+        self.to_json_with_metadata(&Metadata::new())
+    }
+
+    pub fn to_json_with_metadata(&self, metadata: &Metadata) -> String {
+        self.to_json_with_precision(metadata, Precision::F64)
+            .expect("f64 weights should be representable")
+    }
+
+    /// Saves the weights in a lower `precision` to shrink the file, loading
+    /// them again restores each within [`Precision::tolerance`]. Fails if a
+    /// weight isn't representable that accurately, e.g. beyond the range of
+    /// f16.
+    pub fn to_json_with_precision(
+        &self,
+        metadata: &Metadata,
+        precision: Precision,
+    ) -> Result<String> {
+        let store = |value: &Value| {
+            let value = value.value();
+
+            if precision.check(value) {
+                Ok(Weight(precision.shortest(value)))
+            } else {
+                Err(Error::Unrepresentable(value, precision))
+            }
+        };
+
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .neurons
+                    .iter()
+                    .map(|neuron| {
+                        Ok(NeuronJson {
+                            weights: neuron.weights.iter().map(store).collect::<Result<_>>()?,
+                            bias: store(&neuron.bias)?,
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<_>>()?;
+
+        let model = ModelJson {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            metadata: metadata.clone(),
+            inputs: self.inputs,
+            activation: self.activation.name().to_string(),
+            precision,
+            quantization_aware: self.quantized_layers(),
+            layers,
+        };
+
+        Ok(serde_json::to_string_pretty(&model).expect("models should serialize"))
+    }
+
+    /// Restores a model written by [`Mlp::to_json`], possibly edited by hand.
+    /// Weights saved in a lower precision are rounded to the exact values
+    /// that were saved.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::from_json_with_metadata(json).map(|(model, _)| model)
+    }
+
+    pub fn from_json_with_metadata(json: &str) -> Result<(Self, Metadata)> {
+        let model: ModelJson = serde_json::from_str(json)?;
+
+        if model.format != FORMAT {
+            return Err(Error::Format(model.format));
+        }
+
+        if model.version > VERSION {
+            return Err(Error::UnsupportedVersion(model.version));
+        }
+
+        let activation = Activation::named(&model.activation)
+            .ok_or_else(|| Error::UnknownActivation(model.activation.clone()))?;
+
+        if model.inputs == 0 {
+            return Err(Error::Shape("expected at least one input".to_string()));
+        }
+
+        if model.layers.is_empty() {
+            return Err(Error::Shape("expected at least one layer".to_string()));
+        }
+
+        if let Some(l) = model
+            .quantization_aware
+            .iter()
+            .find(|&&l| l >= model.layers.len())
+        {
+            return Err(Error::Shape(format!(
+                "quantization-aware layer {l} doesn't exist"
+            )));
+        }
+
+        let precision = model.precision;
+        let mut inputs = model.inputs;
+        let mut layers = Vec::with_capacity(model.layers.len());
+
+        for (l, neurons) in model.layers.into_iter().enumerate() {
+            if neurons.is_empty() {
+                return Err(Error::Shape(format!("layer {l} has no neurons")));
+            }
+
+            let neurons = neurons
+                .into_iter()
+                .enumerate()
+                .map(|(n, neuron)| {
+                    if neuron.weights.len() != inputs {
+                        return Err(Error::Shape(format!(
+                            "neuron {n} of layer {l} has {} weights, expected {inputs}",
+                            neuron.weights.len()
+                        )));
+                    }
+
+                    let weights = neuron
+                        .weights
+                        .iter()
+                        .enumerate()
+                        .map(|(i, weight)| Value::new(precision.round(weight.0), &format!("w_{i}")))
+                        .collect();
+
+                    Ok(Neuron {
+                        weights,
+                        bias: Value::new(precision.round(neuron.bias.0), "b"),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            layers.push(Layer {
+                inputs,
+                neurons,
+                quantized: model.quantization_aware.contains(&l),
+            });
+            inputs = layers[l].neurons.len();
+        }
+
+        let mlp = Mlp {
+            inputs: model.inputs,
+            layers,
+            activation,
+            training: true,
+        };
+
+        Ok((mlp, model.metadata))
+    }
+}
+
+fn is_f64(precision: &Precision) -> bool {
+    *precision == Precision::F64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Metadata};
+    use crate::{
+        nn::{diff, register_activation, Activation, Mlp, Precision},
+        value::Value,
+    };
+
+    #[test]
+    fn json_roundtrip() {
+        let mlp = Mlp::new_seeded(2, &[3, 1], 1);
+        let metadata = Metadata::from([("dataset".to_string(), "moons".to_string())]);
+
+        let json = mlp.to_json_with_metadata(&metadata);
+        let (loaded, loaded_metadata) = Mlp::from_json_with_metadata(&json).expect("should load");
+
+        assert_eq!(loaded.layer_sizes(), [2, 3, 1]);
+        assert_eq!(loaded_metadata, metadata);
+
+        let values =
+            |mlp: &Mlp| -> Vec<f64> { mlp.parameters().iter().map(|p| p.value()).collect() };
+        assert_eq!(values(&loaded), values(&mlp));
+
+        let swish = Activation::new(
+            "json_swish",
+            |x| x.clone() * x.sigmoid(),
+            |x| x / (1.0 + (-x).exp()),
+        );
+        let custom = Mlp::new_seeded(2, &[3, 1], 1).with_activation(swish.clone());
+        let json = custom.to_json();
+        assert!(json.contains(r#""activation": "json_swish""#));
+        assert!(matches!(
+            Mlp::from_json(&json),
+            Err(Error::UnknownActivation(_))
+        ));
+
+        register_activation(swish).expect("should register");
+        let loaded = Mlp::from_json(&json).expect("should load");
+        assert_eq!(loaded.activation().name(), "json_swish");
+        let x = [Value::new(0.5, "x_1"), Value::new(-0.5, "x_2")];
+        assert_eq!(
+            loaded.predict(&x).expect("should predict")[0].value(),
+            custom.predict(&x).expect("should predict")[0].value()
+        );
+    }
+
+    #[test]
+    fn quantization_aware() {
+        let mlp = Mlp::new_seeded(2, &[3, 2, 1], 1).quantization_aware_layers(&[0, 2]);
+
+        let json = mlp.to_json();
+        assert!(json.contains(
+            r#""quantization_aware": [
+    0,
+    2
+  ]"#
+        ));
+
+        let loaded = Mlp::from_json(&json).expect("should load");
+        assert_eq!(loaded.quantized_layers(), [0, 2]);
+        assert_eq!(
+            loaded.infer(&[0.5, -0.5]).expect("should infer"),
+            mlp.infer(&[0.5, -0.5]).expect("should infer")
+        );
+
+        assert!(!Mlp::new_seeded(2, &[1], 1)
+            .to_json()
+            .contains("quantization_aware"));
+        assert!(matches!(
+            Mlp::from_json(&json.replace("2\n  ]", "3\n  ]")),
+            Err(Error::Shape(_))
+        ));
+    }
+
+    #[test]
+    fn hand_written() {
+        let json = r#"{
+            "format": "micrograd-mlp",
+            "version": 1,
+            "inputs": 2,
+            "activation": "tanh",
+            "layers": [[{ "weights": [1.0, -1.0], "bias": 0.0 }]]
+        }"#;
+
+        let mlp = Mlp::from_json(json).expect("should load");
+        let y = mlp.infer(&[0.5, 0.25]).expect("should infer");
+
+        assert_eq!(y, vec![0.25f64.tanh()]);
+
+        let shape = Mlp::from_json(&json.replace("[1.0, -1.0]", "[1.0]"));
+        assert!(matches!(shape, Err(Error::Shape(_))));
+
+        let no_inputs = json
+            .replace("\"inputs\": 2", "\"inputs\": 0")
+            .replace("[1.0, -1.0]", "[]");
+        assert!(matches!(Mlp::from_json(&no_inputs), Err(Error::Shape(_))));
+
+        let version = Mlp::from_json(&json.replace("\"version\": 1", "\"version\": 2"));
+        assert!(matches!(version, Err(Error::UnsupportedVersion(2))));
+
+        let activation = Mlp::from_json(&json.replace("tanh", "relu"));
+        assert!(matches!(activation, Err(Error::UnknownActivation(name)) if name == "relu"));
+    }
+
+    #[test]
+    fn half_precision() {
+        let mlp = Mlp::new_seeded(4, &[16, 16, 1], 1);
+        let metadata = Metadata::new();
+
+        let json = mlp
+            .to_json_with_precision(&metadata, Precision::F16)
+            .expect("should save");
+        assert!(json.contains(r#""precision": "f16""#));
+        assert!(json.len() < mlp.to_json().len() * 3 / 4);
+
+        // the weights are initialized within ±1, so the error is relative to 1
+        let loaded = Mlp::from_json(&json).expect("should load");
+        let difference = diff(&mlp, &loaded).expect("should compare");
+        assert!(
+            difference.within(Precision::F16.tolerance()),
+            "{difference}"
+        );
+        assert!(!difference.is_identical());
+
+        // weights load as the exact saved values
+        let resaved = loaded
+            .to_json_with_precision(&metadata, Precision::F16)
+            .expect("should save");
+        assert_eq!(resaved, json);
+        assert!(
+            diff(&loaded, &Mlp::from_json(&resaved).expect("should load"))
+                .expect("should compare")
+                .is_identical()
+        );
+
+        let x = [0.5, -0.25, 1.0, 0.0];
+        let (y, y_loaded) = (mlp.infer(&x), loaded.infer(&x));
+        assert!((y.expect("should infer")[0] - y_loaded.expect("should infer")[0]).abs() < 1e-2);
+
+        let large = Mlp::from_json(&json).expect("should load");
+        large.parameters()[0].set_value(1e6);
+        assert!(matches!(
+            large.to_json_with_precision(&metadata, Precision::F16),
+            Err(Error::Unrepresentable(value, Precision::F16)) if value == 1e6
+        ));
+        assert!(large
+            .to_json_with_precision(&metadata, Precision::F32)
+            .is_ok());
+
+        // a diverged model still saves and loads
+        large.parameters()[0].set_value(f64::NAN);
+        large.parameters()[1].set_value(f64::NEG_INFINITY);
+        large.parameters()[2].set_value(f64::INFINITY);
+        let json = large
+            .to_json_with_precision(&metadata, Precision::F16)
+            .expect("should save");
+        let loaded = Mlp::from_json(&json).expect("should load");
+        let values: Vec<_> = loaded.parameters().iter().map(Value::value).collect();
+        assert!(values[0].is_nan());
+        assert_eq!(values[1..3], [f64::NEG_INFINITY, f64::INFINITY]);
+
+        let invalid = json.replacen("\"-inf\"", "\"minus infinity\"", 1);
+        assert!(matches!(Mlp::from_json(&invalid), Err(Error::Json(_))));
+    }
+}
@@ -0,0 +1,124 @@
+use alloc::vec::Vec;
+
+use super::{MixtureOfExperts, Mlp, OneVsRest, Result};
+use crate::value::Value;
+
+/// A model built from `Value` operations, mapping inputs to outputs through a
+/// graph its parameters are leaves of
+pub trait Module {
+    fn predict(&self, x: &[Value]) -> Result<Vec<Value>>;
+
+    fn parameters(&self) -> Vec<Value>;
+
+    /// Switches to training mode, the mode modules start in. Containers
+    /// switch every module they hold.
+    fn train(&mut self);
+
+    /// Switches to evaluation mode, in which stateful layers behave
+    /// deterministically for inference. Containers switch every module they
+    /// hold.
+    fn eval(&mut self);
+
+    fn is_training(&self) -> bool;
+}
+
+impl Module for Mlp {
+    fn predict(&self, x: &[Value]) -> Result<Vec<Value>> {
+        Mlp::predict(self, x)
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        Mlp::parameters(self)
+    }
+
+    fn train(&mut self) {
+        Mlp::train(self)
+    }
+
+    fn eval(&mut self) {
+        Mlp::eval(self)
+    }
+
+    fn is_training(&self) -> bool {
+        Mlp::is_training(self)
+    }
+}
+
+impl Module for MixtureOfExperts {
+    fn predict(&self, x: &[Value]) -> Result<Vec<Value>> {
+        MixtureOfExperts::predict(self, x)
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        MixtureOfExperts::parameters(self)
+    }
+
+    fn train(&mut self) {
+        MixtureOfExperts::train(self)
+    }
+
+    fn eval(&mut self) {
+        MixtureOfExperts::eval(self)
+    }
+
+    fn is_training(&self) -> bool {
+        MixtureOfExperts::is_training(self)
+    }
+}
+
+impl Module for OneVsRest {
+    fn predict(&self, x: &[Value]) -> Result<Vec<Value>> {
+        OneVsRest::predict(self, x)
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        OneVsRest::parameters(self)
+    }
+
+    fn train(&mut self) {
+        OneVsRest::train(self)
+    }
+
+    fn eval(&mut self) {
+        OneVsRest::eval(self)
+    }
+
+    fn is_training(&self) -> bool {
+        OneVsRest::is_training(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Module;
+    use crate::nn::{MixtureOfExperts, Mlp, OneVsRest};
+
+    // switches the mode through the trait only
+    fn switch(module: &mut dyn Module) -> (bool, bool) {
+        module.eval();
+        let eval = module.is_training();
+        module.train();
+
+        (eval, module.is_training())
+    }
+
+    #[test]
+    fn modes_reach_children() {
+        let mut mlp = Mlp::new_seeded(2, &[1], 1);
+        assert_eq!(switch(&mut mlp), (false, true));
+
+        let mut moe = MixtureOfExperts::new_seeded(2, &[3, 2], 3, 1);
+        Module::eval(&mut moe);
+        assert!(moe.experts().iter().all(|expert| !expert.is_training()));
+        assert_eq!(switch(&mut moe), (false, true));
+        assert!(moe.experts().iter().all(Mlp::is_training));
+
+        let mut one_vs_rest = OneVsRest::new_seeded(2, &[3], 3, 1);
+        Module::eval(&mut one_vs_rest);
+        assert!(one_vs_rest
+            .models()
+            .iter()
+            .all(|model| !model.is_training()));
+        assert_eq!(switch(&mut one_vs_rest), (false, true));
+    }
+}
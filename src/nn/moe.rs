@@ -0,0 +1,144 @@
+use alloc::vec::Vec;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{softmax, Mlp, Result};
+use crate::value::Value;
+
+/// Expert networks of the same shape whose outputs are mixed by the softmax
+/// of a gating network, which learns which experts to trust for which inputs.
+///
+/// Both the gate and the experts are trained through the mixed outputs.
+pub struct MixtureOfExperts {
+    gate: Mlp,
+    experts: Vec<Mlp>,
+    temperature: f64,
+}
+
+impl MixtureOfExperts {
+    /// Builds `experts` networks like `Mlp::new(inputs, layer_sizes, ..)` and
+    /// a single layer gate with an output per expert
+    pub fn new<R: Rng>(inputs: usize, layer_sizes: &[usize], experts: usize, rng: &mut R) -> Self {
+        Self {
+            gate: Mlp::new(inputs, &[experts], rng),
+            experts: (0..experts)
+                .map(|_| Mlp::new(inputs, layer_sizes, rng))
+                .collect(),
+            temperature: 1.0,
+        }
+    }
+
+    /// Builds a mixture with weights fully determined by `seed`
+    pub fn new_seeded(inputs: usize, layer_sizes: &[usize], experts: usize, seed: u64) -> Self {
+        Self::new(
+            inputs,
+            layer_sizes,
+            experts,
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        )
+    }
+
+    /// Temperature of the gate's softmax, 1 by default. Lower temperatures
+    /// route every input to fewer experts.
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Switches the gate and every expert to training mode
+    pub fn train(&mut self) {
+        self.gate.train();
+        self.experts.iter_mut().for_each(Mlp::train);
+    }
+
+    /// Switches the gate and every expert to evaluation mode
+    pub fn eval(&mut self) {
+        self.gate.eval();
+        self.experts.iter_mut().for_each(Mlp::eval);
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.gate.is_training()
+    }
+
+    pub fn experts(&self) -> &[Mlp] {
+        &self.experts
+    }
+
+    /// Weights of the experts for `x`, positive and summing to 1
+    pub fn gate(&self, x: &[Value]) -> Result<Vec<Value>> {
+        Ok(softmax(&self.gate.predict(x)?, self.temperature))
+    }
+
+    /// Outputs of the experts for `x`, weighted by the gate and summed
+    pub fn predict(&self, x: &[Value]) -> Result<Vec<Value>> {
+        let gate = self.gate(x)?;
+        let outputs = self
+            .experts
+            .iter()
+            .map(|expert| expert.predict(x))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((0..outputs.first().map_or(0, Vec::len))
+            .map(|i| {
+                let column: Vec<_> = outputs.iter().map(|output| output[i].clone()).collect();
+
+                Value::dot(&gate, &column)
+            })
+            .collect())
+    }
+
+    /// Parameters of the gate followed by those of every expert
+    pub fn parameters(&self) -> Vec<Value> {
+        [self.gate.parameters()]
+            .into_iter()
+            .chain(self.experts.iter().map(Mlp::parameters))
+            .collect::<Vec<_>>()
+            .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MixtureOfExperts;
+    use crate::value::Value;
+
+    #[test]
+    fn mixture_of_experts() {
+        let mut moe = MixtureOfExperts::new_seeded(2, &[3, 2], 3, 1);
+        assert!(moe.is_training());
+        moe.eval();
+        assert!(!moe.is_training());
+        assert!(moe.experts().iter().all(|expert| !expert.is_training()));
+
+        let x = [Value::new(0.5, "x_1"), Value::new(-1.0, "x_2")];
+
+        let gate = moe.gate(&x).expect("should gate");
+        assert_eq!(gate.len(), 3);
+        assert!((gate.iter().map(Value::value).sum::<f64>() - 1.0).abs() < 1e-12);
+
+        let output = moe.predict(&x).expect("should predict");
+        let expected: f64 = moe
+            .experts()
+            .iter()
+            .zip(&gate)
+            .map(|(expert, g)| g.value() * expert.infer(&[0.5, -1.0]).expect("should infer")[1])
+            .sum();
+        assert_eq!(output.len(), 2);
+        assert!((output[1].value() - expected).abs() < 1e-12);
+
+        output[1].backpropagate();
+        let parameters = moe.parameters();
+        assert_eq!(parameters.len(), 3 * 3 + 3 * (3 * 3 + 2 * 4));
+        // the gate comes first, then the experts
+        assert!(parameters[..9].iter().all(|p| p.gradient() != 0.0));
+        assert!(parameters[9..].iter().any(|p| p.gradient() != 0.0));
+
+        assert!(moe.predict(&x[..1]).is_err());
+
+        let largest = |gate: Vec<Value>| gate.iter().map(Value::value).fold(0.0, f64::max);
+        let sharp = MixtureOfExperts::new_seeded(2, &[3, 2], 3, 1).temperature(0.1);
+        assert!(largest(sharp.gate(&x).expect("should gate")) > largest(gate));
+    }
+}
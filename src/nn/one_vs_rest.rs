@@ -0,0 +1,188 @@
+use alloc::vec::Vec;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{Mlp, Result};
+use crate::value::Value;
+
+/// A binary classifier per class, each telling its class apart from all the
+/// others, with the most confident one picking the class. An alternative to a
+/// single model with a softmax over all classes.
+///
+/// Every classifier has a single tanh output `y`, read as the probability
+/// `(y + 1) / 2`, which is the sigmoid of twice the output neuron's weighted
+/// sum. Training all of them at once with a binary cross-entropy against
+/// one-hot targets trains each on its own binary problem, since every
+/// probability only depends on the parameters of its classifier.
+pub struct OneVsRest {
+    models: Vec<Mlp>,
+}
+
+impl OneVsRest {
+    /// Builds `classes` models like `Mlp::new(inputs, [hidden, 1], ..)`
+    pub fn new<R: Rng>(inputs: usize, hidden: &[usize], classes: usize, rng: &mut R) -> Self {
+        let layer_sizes = [hidden, &[1]].concat();
+
+        Self {
+            models: (0..classes)
+                .map(|_| Mlp::new(inputs, &layer_sizes, rng))
+                .collect(),
+        }
+    }
+
+    /// Builds the classifiers with weights fully determined by `seed`
+    pub fn new_seeded(inputs: usize, hidden: &[usize], classes: usize, seed: u64) -> Self {
+        Self::new(
+            inputs,
+            hidden,
+            classes,
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        )
+    }
+
+    pub fn classes(&self) -> usize {
+        self.models.len()
+    }
+
+    /// The binary classifier of every class
+    pub fn models(&self) -> &[Mlp] {
+        &self.models
+    }
+
+    /// Switches every classifier to training mode
+    pub fn train(&mut self) {
+        self.models.iter_mut().for_each(Mlp::train);
+    }
+
+    /// Switches every classifier to evaluation mode
+    pub fn eval(&mut self) {
+        self.models.iter_mut().for_each(Mlp::eval);
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.models.iter().any(Mlp::is_training)
+    }
+
+    /// Probability of every class according to its classifier. The
+    /// probabilities are independent, so they don't sum to 1.
+    pub fn predict(&self, x: &[Value]) -> Result<Vec<Value>> {
+        self.models
+            .iter()
+            .map(|model| {
+                let output = model.predict(x)?.swap_remove(0);
+
+                Ok((output + Value::new(1.0, "1")) * Value::new(0.5, "1/2"))
+            })
+            .collect()
+    }
+
+    /// Computes the probabilities of `predict` without building a graph
+    pub fn infer(&self, x: &[f64]) -> Result<Vec<f64>> {
+        self.models
+            .iter()
+            .map(|model| Ok((model.infer(x)?[0] + 1.0) / 2.0))
+            .collect()
+    }
+
+    /// The class whose classifier is the most confident, the first one on ties
+    pub fn classify(&self, x: &[f64]) -> Result<usize> {
+        let probabilities = self.infer(x)?;
+
+        Ok((0..probabilities.len()).fold(0, |best, class| {
+            if probabilities[class] > probabilities[best] {
+                class
+            } else {
+                best
+            }
+        }))
+    }
+
+    /// Parameters of every classifier, in the order of the classes
+    pub fn parameters(&self) -> Vec<Value> {
+        self.models
+            .iter()
+            .map(Mlp::parameters)
+            .collect::<Vec<_>>()
+            .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OneVsRest;
+    use crate::value::Value;
+
+    #[test]
+    fn one_vs_rest() {
+        let ovr = OneVsRest::new_seeded(2, &[4], 3, 1);
+        assert_eq!(ovr.classes(), 3);
+        assert_eq!(ovr.parameters().len(), 3 * (4 * 3 + 5));
+
+        let x = [Value::new(0.5, "x_1"), Value::new(-1.0, "x_2")];
+        let probabilities = ovr.predict(&x).expect("should predict");
+        let inferred = ovr.infer(&[0.5, -1.0]).expect("should infer");
+        for (p, expected) in probabilities.iter().zip(&inferred) {
+            assert!((p.value() - expected).abs() < 1e-12);
+            assert!((0.0..=1.0).contains(expected));
+        }
+
+        // every probability only reaches the parameters of its own classifier
+        probabilities[1].backpropagate();
+        let parameters = ovr.parameters();
+        assert!(parameters[..17].iter().all(|p| p.gradient() == 0.0));
+        assert!(parameters[17..34].iter().any(|p| p.gradient() != 0.0));
+        assert!(parameters[34..].iter().all(|p| p.gradient() == 0.0));
+
+        assert!(ovr.predict(&x[..1]).is_err());
+        assert!(ovr.classify(&[1.0]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn learns_classes() {
+        use crate::{
+            loss::binary_cross_entropy,
+            optim::{Optimizer, Sgd},
+        };
+
+        let centers = [[1.0, 1.0], [-1.0, 1.0], [0.0, -1.0]];
+        let samples: Vec<_> = (0..12)
+            .map(|i| {
+                let class = i % 3;
+                let jitter = (i / 3) as f64 * 0.1 - 0.15;
+
+                (
+                    vec![centers[class][0] + jitter, centers[class][1] - jitter],
+                    class,
+                )
+            })
+            .collect();
+
+        let ovr = OneVsRest::new_seeded(2, &[4], 3, 2);
+        let mut sgd = Sgd::new(ovr.parameters(), 0.1);
+
+        for _ in 0..100 {
+            let (ys, ypred): (Vec<_>, Vec<_>) = samples
+                .iter()
+                .map(|(x, class)| {
+                    let mut y = vec![0.0; 3];
+                    y[*class] = 1.0;
+                    let x: Vec<_> = x.iter().map(|&x| Value::new(x, "x")).collect();
+
+                    (y, ovr.predict(&x).expect("should predict"))
+                })
+                .unzip();
+
+            binary_cross_entropy(&ys, &ypred, None, None)
+                .expect("should compute")
+                .backpropagate();
+            sgd.step();
+            sgd.zero_grad();
+        }
+
+        for (x, class) in &samples {
+            assert_eq!(ovr.classify(x).expect("should classify"), *class);
+        }
+    }
+}
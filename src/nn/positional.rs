@@ -0,0 +1,169 @@
+use alloc::{format, vec::Vec};
+
+use rand::Rng;
+
+use super::{Error, Result};
+use crate::{math, value::Value};
+
+/// Adds information about the position of every step to a sequence of vectors,
+/// e.g. embeddings, which models that treat the steps alike can't otherwise
+/// tell apart
+pub struct PositionalEncoding {
+    dimensions: usize,
+    // a learned vector per position, `None` for the fixed sinusoidal encoding
+    positions: Option<Vec<Vec<Value>>>,
+}
+
+impl PositionalEncoding {
+    /// The fixed encoding of "Attention Is All You Need", the sine and cosine
+    /// of the position at wavelengths growing geometrically from 2π towards
+    /// 10000·2π over pairs of dimensions. Works for sequences of any length
+    /// and has no parameters.
+    pub fn sinusoidal(dimensions: usize) -> Self {
+        Self {
+            dimensions,
+            positions: None,
+        }
+    }
+
+    /// A trained vector per position, for sequences of up to `max_len` steps
+    pub fn learned<R: Rng>(max_len: usize, dimensions: usize, rng: &mut R) -> Self {
+        let positions = (0..max_len)
+            .map(|position| {
+                (0..dimensions)
+                    .map(|i| Value::new(rng.gen_range(-1.0..=1.0), &format!("p_{position}_{i}")))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            dimensions,
+            positions: Some(positions),
+        }
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Longest supported sequence, unlimited for the sinusoidal encoding
+    pub fn max_len(&self) -> Option<usize> {
+        self.positions.as_ref().map(Vec::len)
+    }
+
+    /// The vector added to the step at `position`
+    pub fn encoding(&self, position: usize) -> Result<Vec<Value>> {
+        match &self.positions {
+            Some(positions) => positions
+                .get(position)
+                .cloned()
+                .ok_or(Error::PositionOutOfRange(position, positions.len())),
+            None => Ok((0..self.dimensions)
+                .map(|i| {
+                    let frequency =
+                        math::powf(10000.0, -((i - i % 2) as f64) / self.dimensions as f64);
+                    let angle = position as f64 * frequency;
+                    let value = if i % 2 == 0 {
+                        math::sin(angle)
+                    } else {
+                        math::cos(angle)
+                    };
+
+                    Value::new(value, &format!("pe_{position}_{i}"))
+                })
+                .collect()),
+        }
+    }
+
+    /// `sequence` with the encoding of its position added to every step
+    pub fn apply(&self, sequence: &[Vec<Value>]) -> Result<Vec<Vec<Value>>> {
+        sequence
+            .iter()
+            .enumerate()
+            .map(|(position, step)| {
+                if step.len() != self.dimensions {
+                    return Err(Error::StepMismatch(self.dimensions, step.len()));
+                }
+
+                Ok(step
+                    .iter()
+                    .zip(self.encoding(position)?)
+                    .map(|(x, p)| x.clone() + p)
+                    .collect())
+            })
+            .collect()
+    }
+
+    /// Every learned vector, one position after another, none for the
+    /// sinusoidal encoding
+    pub fn parameters(&self) -> Vec<Value> {
+        self.positions
+            .as_ref()
+            .map_or_else(Vec::new, |positions| positions.concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::PositionalEncoding;
+    use crate::{nn::Error, value::Value};
+
+    fn values(step: &[Value]) -> Vec<f64> {
+        step.iter().map(Value::value).collect()
+    }
+
+    #[test]
+    fn sinusoidal() {
+        let encoding = PositionalEncoding::sinusoidal(4);
+        assert_eq!(encoding.max_len(), None);
+        assert!(encoding.parameters().is_empty());
+
+        assert_eq!(
+            values(&encoding.encoding(0).expect("should encode")),
+            [0.0, 1.0, 0.0, 1.0]
+        );
+        let third = values(&encoding.encoding(3).expect("should encode"));
+        let expected = [3f64.sin(), 3f64.cos(), 0.03f64.sin(), 0.03f64.cos()];
+        for (value, expected) in third.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert!(encoding.encoding(10_000).is_ok());
+
+        // the same step gets a different vector at every position
+        let step = vec![Value::new(0.5, "x"); 4];
+        let encoded = encoding
+            .apply(&[step.clone(), step.clone(), step])
+            .expect("should encode");
+        assert_ne!(values(&encoded[1]), values(&encoded[2]));
+        assert_eq!(values(&encoded[0]), [0.5, 1.5, 0.5, 1.5]);
+
+        assert!(matches!(
+            encoding.apply(&[vec![Value::new(0.5, "x"); 3]]),
+            Err(Error::StepMismatch(4, 3))
+        ));
+    }
+
+    #[test]
+    fn learned() {
+        let encoding = PositionalEncoding::learned(3, 2, &mut ChaCha8Rng::seed_from_u64(1));
+        assert_eq!((encoding.max_len(), encoding.dimensions()), (Some(3), 2));
+        assert_eq!(encoding.parameters().len(), 6);
+
+        let sequence = vec![vec![Value::new(0.0, "x"), Value::new(1.0, "x")]; 2];
+        let encoded = encoding.apply(&sequence).expect("should encode");
+        encoded[1][0].backpropagate();
+
+        let parameters = encoding.parameters();
+        assert_eq!(encoded[1][0].value(), parameters[2].value());
+        assert_eq!(parameters[2].gradient(), 1.0);
+        assert_eq!(parameters[0].gradient(), 0.0);
+
+        assert!(matches!(
+            encoding.apply(&vec![vec![Value::new(0.0, "x"); 2]; 4]),
+            Err(Error::PositionOutOfRange(3, 3))
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+use alloc::format;
+use core::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::math;
+
+/// Precision the weights of a model are saved in, to trade accuracy for
+/// smaller checkpoints and exported models. Loaded weights are computed in f64
+/// whatever their precision.
+///
+/// Weights are stored as the shortest decimal rounding to the same value in
+/// the precision, so an f16 weight takes at most 5 significant digits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "json",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Precision {
+    #[default]
+    F64,
+    F32,
+    F16,
+}
+
+// Largest finite f16 and the spacing of f16 values below 2^-14
+const F16_MAX: f64 = 65504.0;
+const F16_MIN_EXPONENT: i32 = -14;
+const F16_MANTISSA_BITS: i32 = 10;
+
+impl Precision {
+    /// Bound of the relative error of a weight saved and loaded again, half
+    /// the relative spacing of the values of the precision: 2^-53, 2^-24 and
+    /// 2^-11 (about 4.9e-4) respectively.
+    ///
+    /// For f16 the bound holds between 2^-14 and 65504 in magnitude. Smaller
+    /// weights are off by at most 2^-25 absolutely, larger ones overflow and
+    /// fail [`Precision::check`].
+    pub fn tolerance(self) -> f64 {
+        match self {
+            Precision::F64 => f64::EPSILON / 2.0,
+            Precision::F32 => f64::from(f32::EPSILON) / 2.0,
+            Precision::F16 => 1.0 / 2048.0,
+        }
+    }
+
+    /// Whether `x` survives the round trip through the precision within its
+    /// [`tolerance`](Precision::tolerance). NaN and infinities, e.g. weights of
+    /// a diverged model, are kept as they are in every precision.
+    pub fn check(self, x: f64) -> bool {
+        if !x.is_finite() {
+            return true;
+        }
+
+        let smallest_normal = match self {
+            Precision::F64 => f64::MIN_POSITIVE,
+            Precision::F32 => f64::from(f32::MIN_POSITIVE),
+            Precision::F16 => exp2(F16_MIN_EXPONENT),
+        };
+
+        (self.round(x) - x).abs() <= self.tolerance() * x.abs().max(smallest_normal)
+    }
+
+    /// Rounds `x` to the nearest value of the precision, ties to even, to
+    /// infinity when it's out of range
+    pub fn round(self, x: f64) -> f64 {
+        match self {
+            Precision::F64 => x,
+            Precision::F32 => f64::from(x as f32),
+            Precision::F16 => round_f16(x),
+        }
+    }
+
+    /// The decimal with the fewest significant digits rounding to the same
+    /// value of the precision as `x`, for writing `x` out
+    pub fn shortest(self, x: f64) -> f64 {
+        let rounded = self.round(x);
+
+        if self == Precision::F64 || !rounded.is_finite() {
+            return rounded;
+        }
+
+        (0..17)
+            .filter_map(|digits| format!("{rounded:.digits$e}").parse().ok())
+            .find(|&decimal| self.round(decimal) == rounded)
+            .unwrap_or(rounded)
+    }
+}
+
+fn round_f16(x: f64) -> f64 {
+    if !x.is_finite() {
+        return x;
+    }
+
+    // the f64 exponent of x, -1023 for zero and subnormals
+    let exponent = ((x.to_bits() >> 52) & 0x7ff) as i32 - 1023;
+    let spacing = exp2(exponent.max(F16_MIN_EXPONENT) - F16_MANTISSA_BITS);
+    let rounded = math::round_ties_even(x / spacing) * spacing;
+
+    if rounded.abs() > F16_MAX {
+        f64::INFINITY.copysign(x)
+    } else {
+        rounded
+    }
+}
+
+// 2^exponent for exponents of normal f64 values
+fn exp2(exponent: i32) -> f64 {
+    f64::from_bits(((exponent + 1023) as u64) << 52)
+}
+
+impl Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Precision::F64 => write!(f, "f64"),
+            Precision::F32 => write!(f, "f32"),
+            Precision::F16 => write!(f, "f16"),
+        }
+    }
+}
+
+impl FromStr for Precision {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f64" => Ok(Precision::F64),
+            "f32" => Ok(Precision::F32),
+            "f16" => Ok(Precision::F16),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Precision;
+
+    #[test]
+    fn half_precision() {
+        let f16 = Precision::F16;
+
+        assert_eq!(f16.round(1.0 / 3.0), 0.333251953125);
+        assert_eq!(f16.shortest(1.0 / 3.0), 0.3333);
+        assert_eq!(f16.round(f16.shortest(1.0 / 3.0)), f16.round(1.0 / 3.0));
+        assert_eq!(f16.round(-2.0), -2.0);
+        // ties round to even
+        assert_eq!(f16.round(1.0 + 1.0 / 2048.0), 1.0);
+        assert_eq!(f16.round(1.0 + 3.0 / 2048.0), 1.0 + 2.0 / 1024.0);
+        // subnormals
+        assert_eq!(f16.round(1e-7), 2f64.powi(-24) * 2.0);
+        assert_eq!(f16.round(65504.0), 65504.0);
+        assert_eq!(f16.round(-70000.0), f64::NEG_INFINITY);
+
+        for x in [0.1, -0.7, 1.0 / 3.0, 123.456, 3e-5, 1e-9, 0.0] {
+            assert!(f16.check(x), "{x}");
+            assert!((f16.round(x) - x).abs() <= f16.tolerance() * x.abs().max(2f64.powi(-14)));
+        }
+        assert!(!f16.check(1e5));
+
+        for precision in [Precision::F64, Precision::F32, f16] {
+            assert!(precision.check(f64::NAN));
+            assert!(precision.check(f64::NEG_INFINITY));
+            assert!(precision.round(f64::INFINITY).is_infinite());
+        }
+
+        assert_eq!(Precision::F32.round(0.1), f64::from(0.1f32));
+        assert_eq!(Precision::F32.shortest(0.1), 0.1);
+        assert_eq!(Precision::F64.shortest(0.1), 0.1);
+
+        assert_eq!("f16".parse(), Ok(f16));
+        assert_eq!(f16.to_string(), "f16");
+    }
+}
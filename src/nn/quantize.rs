@@ -0,0 +1,237 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use super::{dimension_mismatch, dot, Activation, Mlp, Result};
+use crate::math;
+#[cfg(feature = "std")]
+use crate::{data::Dataset, metrics::Metric};
+
+/// A model with its weights quantized to 8 bit integers, for inference only.
+///
+/// Every neuron's weights share a symmetric scale mapping the largest absolute
+/// weight to 127. Biases are kept in full precision, and the weights are
+/// dequantized on the fly during inference.
+#[derive(Debug, Clone)]
+pub struct QuantizedMlp {
+    inputs: usize,
+    layers: Vec<QuantizedLayer>,
+    activation: Activation,
+}
+
+#[derive(Debug, Clone)]
+struct QuantizedLayer {
+    inputs: usize,
+    // row per neuron
+    weights: Vec<i8>,
+    scales: Vec<f64>,
+    biases: Vec<f64>,
+}
+
+/// Quality of a quantized model compared to the original one
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizationReport {
+    /// Metric of the original model
+    pub original: f64,
+    /// Metric of the quantized model
+    pub quantized: f64,
+    /// Largest absolute difference between any output of the two models
+    pub max_output_error: f64,
+}
+
+impl Mlp {
+    /// Quantizes the current weights into an inference-only model
+    pub fn quantize(&self) -> QuantizedMlp {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                let mut quantized = QuantizedLayer {
+                    inputs: layer.inputs,
+                    weights: Vec::with_capacity(layer.inputs * layer.neurons.len()),
+                    scales: Vec::with_capacity(layer.neurons.len()),
+                    biases: Vec::with_capacity(layer.neurons.len()),
+                };
+
+                for neuron in &layer.neurons {
+                    let weights: Vec<_> = neuron.weights.iter().map(|w| w.value()).collect();
+                    let scale = weight_scale(&weights);
+
+                    quantized.weights.extend(
+                        weights
+                            .iter()
+                            .map(|w| math::round(w / scale).clamp(-127.0, 127.0) as i8),
+                    );
+                    quantized.scales.push(scale);
+                    quantized.biases.push(neuron.bias.value());
+                }
+
+                quantized
+            })
+            .collect();
+
+        QuantizedMlp {
+            inputs: self.inputs,
+            layers,
+            activation: self.activation.clone(),
+        }
+    }
+}
+
+// Symmetric scale mapping the largest absolute weight to 127
+pub(super) fn weight_scale(weights: &[f64]) -> f64 {
+    let max = weights.iter().fold(0.0f64, |max, w| max.max(w.abs()));
+
+    if max > 0.0 {
+        max / 127.0
+    } else {
+        1.0
+    }
+}
+
+impl QuantizedMlp {
+    pub fn infer(&self, x: &[f64]) -> Result<Vec<f64>> {
+        if x.len() != self.inputs {
+            return Err(dimension_mismatch(0, self.inputs, x.len()));
+        }
+
+        Ok(self
+            .layers
+            .iter()
+            .fold(x.to_vec(), |x, layer| layer.infer(&x, &self.activation)))
+    }
+
+    /// Number of inputs followed by the number of outputs of every layer
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        [self.inputs]
+            .into_iter()
+            .chain(self.layers.iter().map(|layer| layer.biases.len()))
+            .collect()
+    }
+
+    /// Size of the quantized weights in bytes, with a 4 byte scale and bias
+    /// per neuron as if stored in single precision
+    pub fn size_bytes(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| layer.weights.len() + 8 * layer.biases.len())
+            .sum()
+    }
+
+    /// Compares `metric` of this model and of the `original` it was quantized
+    /// from on a dataset
+    #[cfg(feature = "std")]
+    pub fn report<D: Dataset + ?Sized>(
+        &self,
+        original: &Mlp,
+        data: &D,
+        metric: &Metric,
+    ) -> Result<QuantizationReport> {
+        let mut targets = Vec::with_capacity(data.len());
+        let mut original_preds = Vec::with_capacity(data.len());
+        let mut quantized_preds = Vec::with_capacity(data.len());
+
+        for i in 0..data.len() {
+            let (x, y) = data.get(i);
+
+            original_preds.push(original.infer(&x)?);
+            quantized_preds.push(self.infer(&x)?);
+            targets.push(y);
+        }
+
+        let max_output_error = original_preds
+            .iter()
+            .flatten()
+            .zip(quantized_preds.iter().flatten())
+            .fold(0.0f64, |max, (o, q)| max.max((o - q).abs()));
+
+        Ok(QuantizationReport {
+            original: metric.compute(&original_preds, &targets),
+            quantized: metric.compute(&quantized_preds, &targets),
+            max_output_error,
+        })
+    }
+}
+
+impl QuantizedLayer {
+    fn infer(&self, x: &[f64], activation: &Activation) -> Vec<f64> {
+        self.weights
+            .chunks(self.inputs)
+            .zip(self.scales.iter().zip(&self.biases))
+            .map(|(weights, (scale, bias))| activation.infer(bias + scale * dot(weights, x)))
+            .collect()
+    }
+}
+
+impl Display for QuantizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "original {:.4}, quantized {:.4} ({:+.4}), max output error {:.4}",
+            self.original,
+            self.quantized,
+            self.quantized - self.original,
+            self.max_output_error
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{generators::moons, Dataset},
+        metrics::Metric,
+        nn::Mlp,
+        value::Value,
+    };
+
+    #[test]
+    fn quantized_inference() {
+        let mlp = Mlp::new_seeded(2, &[8, 8, 1], 1);
+        let quantized = mlp.quantize();
+
+        assert_eq!(quantized.layer_sizes(), mlp.layer_sizes());
+        assert_eq!(quantized.size_bytes(), 2 * 8 + 8 * 8 + 8 + 8 * 17);
+
+        let data = moons(50, 0.1, 1);
+        let report = quantized
+            .report(&mlp, &data, &Metric::Accuracy { threshold: 0.0 })
+            .expect("should compare");
+
+        assert!(report.max_output_error > 0.0);
+        assert!(report.max_output_error < 0.05);
+        assert!((report.original - report.quantized).abs() <= 0.1);
+
+        let (x, _) = data.get(0);
+        let original = mlp.infer(&x).expect("should calculate");
+        let quantized = quantized.infer(&x).expect("should calculate");
+        assert!((original[0] - quantized[0]).abs() <= report.max_output_error);
+
+        assert!(mlp.quantize().infer(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn quantization_aware() {
+        let mlp = Mlp::new_seeded(2, &[8, 8, 1], 1).quantization_aware();
+        let quantized = mlp.quantize();
+        let x = [0.3, -0.7];
+
+        let expected = quantized.infer(&x).expect("should calculate");
+        let inferred = mlp.infer(&x).expect("should calculate");
+        let output = mlp
+            .predict(&[Value::new(x[0], "x0"), Value::new(x[1], "x1")])
+            .expect("should predict");
+
+        assert!((inferred[0] - expected[0]).abs() < 1e-9);
+        assert!((output[0].value() - expected[0]).abs() < 1e-9);
+
+        // gradients pass straight through the rounding to every parameter
+        output[0].backpropagate();
+        assert!(mlp.parameters().iter().all(|p| p.gradient() != 0.0));
+
+        let partial = Mlp::new_seeded(2, &[8, 8, 1], 1).quantization_aware_layers(&[2, 5]);
+        let full = Mlp::new_seeded(2, &[8, 8, 1], 1);
+        let partial = partial.infer(&x).expect("should calculate")[0];
+        assert_ne!(partial, full.infer(&x).expect("should calculate")[0]);
+        assert!((partial - inferred[0]).abs() > 0.0);
+    }
+}
@@ -0,0 +1,193 @@
+use alloc::{vec, vec::Vec};
+
+use super::{Error, Result};
+use crate::value::Value;
+
+/// Sequences padded to a common length, with a mask marking the positions
+/// holding real elements rather than padding
+#[derive(Debug, Clone, PartialEq)]
+pub struct Padded<T> {
+    pub sequences: Vec<Vec<T>>,
+    pub mask: Vec<Vec<bool>>,
+}
+
+impl<T> Padded<T> {
+    /// Common length of the sequences
+    pub fn len(&self) -> usize {
+        self.mask.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of real elements over all sequences, e.g. to average a loss
+    /// computed with `Loss::compute_masked`
+    pub fn positions(&self) -> usize {
+        self.mask.iter().flatten().filter(|&&real| real).count()
+    }
+}
+
+/// Pads `sequences`, e.g. of values or of per-step input vectors, at the end
+/// with clones of `padding` to the length of the longest one
+pub fn pad<T: Clone>(sequences: &[Vec<T>], padding: T) -> Padded<T> {
+    let len = sequences.iter().map(Vec::len).max().unwrap_or(0);
+
+    let (sequences, mask) = sequences
+        .iter()
+        .map(|sequence| {
+            let mut padded = sequence.clone();
+            padded.resize(len, padding.clone());
+
+            let mut mask = vec![true; sequence.len()];
+            mask.resize(len, false);
+
+            (padded, mask)
+        })
+        .unzip();
+
+    Padded { sequences, mask }
+}
+
+/// Mean of the vectors of the steps marked in `mask`, e.g. to pool the outputs
+/// of a padded sequence. Padded steps get no gradient, and a fully masked
+/// sequence pools to zeros.
+pub fn masked_mean(steps: &[Vec<Value>], mask: &[bool]) -> Result<Vec<Value>> {
+    if steps.len() != mask.len() {
+        return Err(Error::MaskMismatch(steps.len(), mask.len()));
+    }
+
+    let width = steps.first().map_or(0, Vec::len);
+    let real: Vec<_> = steps
+        .iter()
+        .zip(mask)
+        .filter(|(_, &real)| real)
+        .map(|(step, _)| step)
+        .collect();
+
+    if let Some(step) = real.iter().find(|step| step.len() != width) {
+        return Err(Error::StepMismatch(width, step.len()));
+    }
+
+    let scale = Value::new(1.0 / real.len().max(1) as f64, "1/n");
+
+    Ok((0..width)
+        .map(|i| {
+            real.iter()
+                .fold(Value::new(0.0, "0"), |sum, step| sum + step[i].clone())
+                * scale.clone()
+        })
+        .collect())
+}
+
+/// Mask of causal attention over `len` steps, each step attending to itself
+/// and the steps before it but not to later ones, as in autoregressive
+/// language models. Row `i` is the mask of the scores of step `i`.
+pub fn causal_mask(len: usize) -> Vec<Vec<bool>> {
+    (0..len)
+        .map(|i| (0..len).map(|j| j <= i).collect())
+        .collect()
+}
+
+/// Softmax over the `logits` marked in `mask`, like `softmax`. Masked
+/// positions get a probability of exactly zero and pass no gradient to their
+/// logits, and a fully masked row gives zeros.
+pub fn masked_softmax(logits: &[Value], mask: &[bool], temperature: f64) -> Result<Vec<Value>> {
+    if logits.len() != mask.len() {
+        return Err(Error::MaskMismatch(logits.len(), mask.len()));
+    }
+
+    let real: Vec<_> = logits
+        .iter()
+        .zip(mask)
+        .filter(|(_, &real)| real)
+        .map(|(logit, _)| logit.clone())
+        .collect();
+    let mut probabilities = super::softmax(&real, temperature).into_iter();
+
+    Ok(mask
+        .iter()
+        .map(|&real| {
+            if real {
+                probabilities
+                    .next()
+                    .expect("one probability per real logit")
+            } else {
+                Value::new(0.0, "0")
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{causal_mask, masked_mean, masked_softmax, pad};
+    use crate::value::Value;
+
+    #[test]
+    fn padding() {
+        let padded = pad(&[vec![1, 2, 3], vec![4], vec![]], 0);
+
+        assert_eq!(
+            padded.sequences,
+            [vec![1, 2, 3], vec![4, 0, 0], vec![0, 0, 0]]
+        );
+        assert_eq!(padded.mask[1], [true, false, false]);
+        assert_eq!(padded.len(), 3);
+        assert_eq!(padded.positions(), 4);
+        assert!(pad::<f64>(&[], 0.0).is_empty());
+    }
+
+    #[test]
+    fn masked_pooling() {
+        let steps: Vec<_> = [1.0, 3.0, 100.0]
+            .iter()
+            .map(|&x| vec![Value::new(x, "x")])
+            .collect();
+        let pooled = masked_mean(&steps, &[true, true, false]).expect("should pool");
+
+        assert_eq!(pooled[0].value(), 2.0);
+        pooled[0].backpropagate();
+        assert_eq!(steps[0][0].gradient(), 0.5);
+        assert_eq!(steps[2][0].gradient(), 0.0);
+
+        assert_eq!(
+            masked_mean(&steps, &[false; 3]).expect("should pool")[0].value(),
+            0.0
+        );
+        assert!(masked_mean(&steps, &[true]).is_err());
+    }
+
+    #[test]
+    fn causal_masking() {
+        assert_eq!(
+            causal_mask(3),
+            [
+                vec![true, false, false],
+                vec![true, true, false],
+                vec![true, true, true]
+            ]
+        );
+
+        let logits: Vec<_> = [1.0, 2.0, 50.0]
+            .iter()
+            .map(|&x| Value::new(x, "l"))
+            .collect();
+        let mask = &causal_mask(3)[1];
+        let p = masked_softmax(&logits, mask, 1.0).expect("should mask");
+
+        let expected = 1.0 / (1.0 + 1f64.exp());
+        assert!((p[0].value() - expected).abs() < 1e-12);
+        assert!((p[0].value() + p[1].value() - 1.0).abs() < 1e-12);
+        assert_eq!(p[2].value(), 0.0);
+
+        // the future logit gets no gradient, whatever the loss
+        (p[0].clone() + p[1].clone() * Value::new(2.0, "2") + p[2].clone()).backpropagate();
+        assert!(logits[0].gradient() != 0.0);
+        assert_eq!(logits[2].gradient(), 0.0);
+
+        let none = masked_softmax(&logits, &[false; 3], 1.0).expect("should mask");
+        assert!(none.iter().all(|p| p.value() == 0.0));
+        assert!(masked_softmax(&logits, &[true], 1.0).is_err());
+    }
+}
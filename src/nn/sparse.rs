@@ -0,0 +1,216 @@
+use alloc::{vec, vec::Vec};
+
+use super::{dimension_mismatch, dot, quantize, Activation, Error, Layer, Mlp, Neuron, Result};
+use crate::{math, value::Value};
+
+/// A vector of mostly zeros as index/value pairs of its other entries, e.g.
+/// bag-of-words counts over a large vocabulary. Repeated indices add up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseVector {
+    len: usize,
+    entries: Vec<(usize, f64)>,
+}
+
+impl SparseVector {
+    /// Fails if an index isn't below `len`
+    pub fn new(len: usize, entries: Vec<(usize, f64)>) -> Result<Self> {
+        if let Some(&(index, _)) = entries.iter().find(|(index, _)| *index >= len) {
+            return Err(Error::SparseIndex(index, len));
+        }
+
+        Ok(Self { len, entries })
+    }
+
+    /// The nonzero entries of `x`
+    pub fn from_dense(x: &[f64]) -> Self {
+        Self {
+            len: x.len(),
+            entries: x
+                .iter()
+                .enumerate()
+                .filter(|(_, &x)| x != 0.0)
+                .map(|(i, &x)| (i, x))
+                .collect(),
+        }
+    }
+
+    /// Length of the dense vector
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn entries(&self) -> &[(usize, f64)] {
+        &self.entries
+    }
+
+    pub fn to_dense(&self) -> Vec<f64> {
+        let mut x = vec![0.0; self.len];
+
+        for &(i, value) in &self.entries {
+            x[i] += value;
+        }
+
+        x
+    }
+}
+
+impl Neuron {
+    // Only the weights of the entries of `x` enter the graph, quantization-aware
+    // layers still scale by all of them
+    fn call_sparse(&self, x: &SparseVector, activation: &Activation, quantized: bool) -> Value {
+        let scale = quantized.then(|| {
+            let values: Vec<_> = self.weights.iter().map(Value::value).collect();
+
+            quantize::weight_scale(&values)
+        });
+
+        let sum = x.entries.iter().fold(self.bias.clone(), |sum, &(i, x)| {
+            let weight = match scale {
+                Some(scale) => self.weights[i].clone().fake_quantize(scale),
+                None => self.weights[i].clone(),
+            };
+
+            sum + weight * Value::new(x, "x")
+        });
+
+        activation.call(sum)
+    }
+}
+
+impl Layer {
+    fn call_sparse(
+        &self,
+        index: usize,
+        x: &SparseVector,
+        activation: &Activation,
+    ) -> Result<Vec<Value>> {
+        if x.len != self.inputs {
+            return Err(dimension_mismatch(index, self.inputs, x.len));
+        }
+
+        Ok(self
+            .neurons
+            .iter()
+            .map(|neuron| neuron.call_sparse(x, activation, self.quantized))
+            .collect())
+    }
+
+    fn infer_sparse(&self, x: &SparseVector, activation: &Activation) -> Vec<f64> {
+        let (indices, values): (Vec<_>, Vec<_>) = x.entries.iter().copied().unzip();
+
+        self.neurons
+            .iter()
+            .map(|neuron| {
+                let mut weights: Vec<_> =
+                    indices.iter().map(|&i| neuron.weights[i].value()).collect();
+
+                if self.quantized {
+                    let all: Vec<_> = neuron.weights.iter().map(Value::value).collect();
+                    let scale = quantize::weight_scale(&all);
+
+                    weights
+                        .iter_mut()
+                        .for_each(|w| *w = math::fake_quantize(*w, scale));
+                }
+
+                activation.infer(neuron.bias.value() + dot(&weights, &values))
+            })
+            .collect()
+    }
+}
+
+impl Mlp {
+    /// Like `predict`, with the first layer only reading the weights of the
+    /// entries of `x`, so that the graph grows with the nonzero inputs rather
+    /// than with all of them
+    pub fn predict_sparse(&self, x: &SparseVector) -> Result<Vec<Value>> {
+        let (first, rest) = self.layers.split_first().ok_or(Error::NoLayers)?;
+        let outputs = first.call_sparse(0, x, &self.activation)?;
+
+        rest.iter()
+            .enumerate()
+            .try_fold(outputs, |result, (index, layer)| {
+                layer.call(index + 1, &result, &self.activation)
+            })
+    }
+
+    /// Like `infer`, with the first layer only reading the weights of the
+    /// entries of `x`
+    pub fn infer_sparse(&self, x: &SparseVector) -> Result<Vec<f64>> {
+        if x.len != self.inputs {
+            return Err(dimension_mismatch(0, self.inputs, x.len));
+        }
+
+        let (first, rest) = self.layers.split_first().ok_or(Error::NoLayers)?;
+        let outputs = first.infer_sparse(x, &self.activation);
+
+        Ok(rest.iter().fold(outputs, |result, layer| {
+            layer.infer(&result, &self.activation)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseVector;
+    use crate::{
+        nn::{Error, Mlp},
+        value::Value,
+    };
+
+    #[test]
+    fn sparse_inputs() {
+        let mlp = Mlp::new_seeded(100, &[4, 2], 1);
+        let x = SparseVector::new(100, vec![(3, 1.0), (42, 2.0), (3, 0.5)]).expect("should build");
+        let dense = x.to_dense();
+        assert_eq!(
+            (dense[3], dense[42], dense.iter().sum::<f64>()),
+            (1.5, 2.0, 3.5)
+        );
+        assert_eq!(
+            SparseVector::from_dense(&dense).entries(),
+            [(3, 1.5), (42, 2.0)]
+        );
+
+        let sparse = mlp.predict_sparse(&x).expect("should predict");
+        let inputs: Vec<_> = dense.iter().map(|&x| Value::new(x, "x")).collect();
+        let expected = mlp.predict(&inputs).expect("should predict");
+        let inferred = mlp.infer_sparse(&x).expect("should infer");
+        for ((y, expected), inferred) in sparse.iter().zip(&expected).zip(&inferred) {
+            assert!((y.value() - expected.value()).abs() < 1e-12);
+            assert!((inferred - expected.value()).abs() < 1e-12);
+        }
+
+        // the first layer only touches the weights of the referenced inputs
+        sparse[0].backpropagate();
+        let first = &mlp.layer_parameters()[0];
+        for (i, p) in first.iter().enumerate() {
+            let input = i % 101;
+            if input != 3 && input != 42 && input != 100 {
+                assert_eq!(p.gradient(), 0.0);
+            }
+        }
+        assert!(first[3].gradient() != 0.0);
+        assert!(sparse[0].nodes().len() < expected[0].nodes().len() / 10);
+
+        assert!(matches!(
+            SparseVector::new(100, vec![(100, 1.0)]),
+            Err(Error::SparseIndex(100, 100))
+        ));
+        let short = SparseVector::new(10, vec![(3, 1.0)]).expect("should build");
+        assert!(matches!(
+            mlp.predict_sparse(&short),
+            Err(Error::DimensionMismatch {
+                layer: 0,
+                expected: 100,
+                actual: 10,
+                ..
+            })
+        ));
+        assert!(mlp.infer_sparse(&short).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use alloc::{vec, vec::Vec};
+use core::fmt::{self, Display};
+
+use super::{Layer, Mlp};
+use crate::math;
+
+/// Summary of the weights of a layer, biases excluded
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayerStats {
+    pub count: usize,
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Equal width buckets spanning `min` to `max`, empty unless requested
+    pub histogram: Vec<Bucket>,
+}
+
+/// Number of values in the range from `start` to `end`, which includes `end`
+/// for the last bucket of a histogram
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+impl LayerStats {
+    fn new(weights: &[f64], buckets: usize) -> Self {
+        if weights.is_empty() {
+            return Self::default();
+        }
+
+        let count = weights.len();
+        let mean = weights.iter().sum::<f64>() / count as f64;
+        let variance = weights.iter().map(|w| (w - mean) * (w - mean)).sum::<f64>() / count as f64;
+        let min = weights.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        Self {
+            count,
+            mean,
+            std: math::powf(variance, 0.5),
+            min,
+            max,
+            histogram: histogram(weights, min, max, buckets),
+        }
+    }
+}
+
+fn histogram(weights: &[f64], min: f64, max: f64, buckets: usize) -> Vec<Bucket> {
+    if buckets == 0 {
+        return Vec::new();
+    }
+
+    let width = (max - min) / buckets as f64;
+    let mut counts = vec![0; buckets];
+
+    for weight in weights {
+        // all weights land in the first bucket when they're equal
+        let bucket = if width > 0.0 {
+            ((weight - min) / width) as usize
+        } else {
+            0
+        };
+
+        counts[bucket.min(buckets - 1)] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| Bucket {
+            start: min + i as f64 * width,
+            end: if i + 1 == buckets {
+                max
+            } else {
+                min + (i + 1) as f64 * width
+            },
+            count,
+        })
+        .collect()
+}
+
+/// Mean absolute gradient of the parameters of every layer, from the first
+/// layer to the last
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GradientFlow {
+    pub layers: Vec<f64>,
+}
+
+/// How the gradients change on their way from the last layer to the first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowStatus {
+    Healthy,
+    /// The first layer gets less than a hundredth of the gradient of the last,
+    /// e.g. because saturated tanh units block it
+    Vanishing,
+    /// The first layer gets over a hundred times the gradient of the last
+    Exploding,
+}
+
+impl GradientFlow {
+    /// Mean absolute gradient of the first layer divided by the one of the
+    /// last, `None` without layers
+    pub fn ratio(&self) -> Option<f64> {
+        Some(self.layers.first()? / self.layers.last()?)
+    }
+
+    /// Classifies the ratio, a network without any gradient counts as healthy
+    pub fn status(&self) -> FlowStatus {
+        match self.ratio() {
+            Some(ratio) if ratio < 1e-2 => FlowStatus::Vanishing,
+            Some(ratio) if ratio > 1e2 => FlowStatus::Exploding,
+            _ => FlowStatus::Healthy,
+        }
+    }
+}
+
+/// Lists the gradient of every layer followed by the ratio, e.g.
+///
+/// ```text
+/// layer 0: 1.2e-5
+/// layer 1: 3.4e-2
+/// first/last: 3.5e-4 (vanishing)
+/// ```
+impl Display for GradientFlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (layer, gradient) in self.layers.iter().enumerate() {
+            writeln!(f, "layer {layer}: {gradient:.1e}")?;
+        }
+
+        match (self.ratio(), self.status()) {
+            (None, _) => Ok(()),
+            (Some(ratio), FlowStatus::Healthy) => write!(f, "first/last: {ratio:.1e}"),
+            (Some(ratio), FlowStatus::Vanishing) => {
+                write!(f, "first/last: {ratio:.1e} (vanishing)")
+            }
+            (Some(ratio), FlowStatus::Exploding) => {
+                write!(f, "first/last: {ratio:.1e} (exploding)")
+            }
+        }
+    }
+}
+
+impl Layer {
+    fn stats(&self, buckets: usize) -> LayerStats {
+        let weights: Vec<_> = self
+            .neurons
+            .iter()
+            .flat_map(|neuron| &neuron.weights)
+            .map(|weight| weight.value())
+            .collect();
+
+        LayerStats::new(&weights, buckets)
+    }
+}
+
+impl Mlp {
+    /// Mean, standard deviation and range of the weights of every layer, e.g.
+    /// to watch them drift during training
+    pub fn parameter_stats(&self) -> Vec<LayerStats> {
+        self.parameter_histograms(0)
+    }
+
+    /// Like [`Mlp::parameter_stats`], with a histogram of `buckets` buckets
+    /// for every layer
+    pub fn parameter_histograms(&self, buckets: usize) -> Vec<LayerStats> {
+        self.layers
+            .iter()
+            .map(|layer| layer.stats(buckets))
+            .collect()
+    }
+
+    /// Mean absolute gradient of every layer, biases included, as left by the
+    /// last backpropagation, to spot vanishing or exploding gradients
+    pub fn gradient_flow(&self) -> GradientFlow {
+        let layers = self
+            .layer_parameters()
+            .iter()
+            .map(|parameters| {
+                parameters.iter().map(|p| p.gradient().abs()).sum::<f64>()
+                    / parameters.len().max(1) as f64
+            })
+            .collect();
+
+        GradientFlow { layers }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlowStatus;
+    use crate::{nn::Mlp, value::Value};
+
+    #[test]
+    fn parameter_stats() {
+        let mlp = Mlp::new_seeded(2, &[2, 1], 0);
+        mlp.load_parameters(&[1.0, 2.0, 9.0, 3.0, 4.0, 9.0, -1.0, 1.0, 9.0])
+            .expect("should load");
+
+        let stats = mlp.parameter_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].count, 4);
+        assert_eq!(stats[0].mean, 2.5);
+        assert_eq!(stats[0].std, 1.25f64.sqrt());
+        assert_eq!((stats[0].min, stats[0].max), (1.0, 4.0));
+        assert!(stats[0].histogram.is_empty());
+        assert_eq!((stats[1].mean, stats[1].std), (0.0, 1.0));
+
+        let stats = mlp.parameter_histograms(3);
+        let counts: Vec<_> = stats[0].histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, [1, 1, 2]);
+        assert_eq!(stats[0].histogram[1].start, 2.0);
+        assert_eq!(stats[0].histogram[2].end, 4.0);
+
+        mlp.load_parameters(&[0.5; 9]).expect("should load");
+        let counts: Vec<_> = mlp.parameter_histograms(2)[0]
+            .histogram
+            .iter()
+            .map(|b| b.count)
+            .collect();
+        assert_eq!(counts, [4, 0]);
+    }
+
+    #[test]
+    fn gradient_flow() {
+        let mlp = Mlp::new_seeded(1, &[1, 1, 1], 0);
+        assert_eq!(mlp.gradient_flow().layers, [0.0; 3]);
+        assert_eq!(mlp.gradient_flow().status(), FlowStatus::Healthy);
+
+        // saturated units pass almost no gradient back to the first layer
+        mlp.load_parameters(&[10.0, 10.0, 10.0, 10.0, 1.0, 0.0])
+            .expect("should load");
+        let output = mlp
+            .predict(&[Value::new(1.0, "x")])
+            .expect("should predict");
+        output[0].backpropagate();
+
+        let flow = mlp.gradient_flow();
+        assert_eq!(flow.layers.len(), 3);
+        assert!(flow.ratio().expect("should have layers") < 1e-2);
+        assert_eq!(flow.status(), FlowStatus::Vanishing);
+        assert!(flow.to_string().ends_with("(vanishing)"));
+        assert_eq!(flow.to_string().lines().count(), 4);
+    }
+}
@@ -0,0 +1,235 @@
+use std::{cmp::Ordering, fs, io, path::Path};
+
+use thiserror::Error as ThisError;
+
+use super::Mlp;
+use crate::numpy::{self, Array};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Numpy(#[from] numpy::Error),
+    #[error(transparent)]
+    Model(#[from] super::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Unknown state dict format {0:?}, expected .npz or .json")]
+    UnknownFormat(String),
+    #[error("Invalid state dict, {0}")]
+    Format(String),
+    #[error("Expected {0} linear layers, the state dict has {1}")]
+    LayerCount(usize, usize),
+    #[error("Expected {key} of shape {expected:?}, found {found:?}")]
+    ShapeMismatch {
+        key: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Reads a PyTorch `state_dict` dumped as an `.npz` archive or a JSON object
+/// of nested lists, e.g. from Python with
+///
+/// ```python
+/// numpy.savez("model.npz", **{k: v.numpy() for k, v in model.state_dict().items()})
+/// json.dump({k: v.tolist() for k, v in model.state_dict().items()}, file)
+/// ```
+pub fn load_state_dict<P: AsRef<Path>>(path: P) -> Result<Vec<(String, Array)>> {
+    let path = path.as_ref();
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("npz") => Ok(numpy::load_npz(path)?),
+        Some("json") => {
+            let json: serde_json::Map<_, _> = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+            json.into_iter()
+                .map(|(key, value)| {
+                    let array = json_array(&value)
+                        .ok_or_else(|| Error::Format(format!("{key} isn't an array of numbers")))?;
+
+                    Ok((key, array))
+                })
+                .collect()
+        }
+        other => Err(Error::UnknownFormat(other.unwrap_or_default().to_string())),
+    }
+}
+
+impl Mlp {
+    /// Copies the weights of a PyTorch model saved with [`load_state_dict`]
+    /// into this model, which needs the same architecture, e.g.
+    /// `nn.Sequential(nn.Linear(2, 4), nn.Tanh(), nn.Linear(4, 1), nn.Tanh())`
+    /// for `Mlp::new(2, &[4, 1], ..)`.
+    pub fn load_torch<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.load_state_dict(&load_state_dict(path)?)
+    }
+
+    /// Copies the `<layer>.weight` and `<layer>.bias` entries of a PyTorch
+    /// `state_dict` into the layers of this model, in the numeric order of the
+    /// layer names. The model is left untouched if any entry doesn't fit.
+    pub fn load_state_dict(&self, state_dict: &[(String, Array)]) -> Result<()> {
+        let mut layers: Vec<(&str, Option<&Array>, Option<&Array>)> = Vec::new();
+
+        for (key, array) in state_dict {
+            let (layer, is_weight) = match key.rsplit_once('.') {
+                Some((layer, "weight")) => (layer, true),
+                Some((layer, "bias")) => (layer, false),
+                _ => return Err(Error::Format(format!("unexpected entry {key}"))),
+            };
+
+            let index = match layers.iter().position(|(name, ..)| *name == layer) {
+                Some(index) => index,
+                None => {
+                    layers.push((layer, None, None));
+                    layers.len() - 1
+                }
+            };
+
+            let slot = if is_weight {
+                &mut layers[index].1
+            } else {
+                &mut layers[index].2
+            };
+            *slot = Some(array);
+        }
+
+        layers.sort_by(|(a, ..), (b, ..)| natural_order(a, b));
+
+        if layers.len() != self.layers.len() {
+            return Err(Error::LayerCount(self.layers.len(), layers.len()));
+        }
+
+        let mut values = Vec::new();
+
+        for ((name, weight, bias), layer) in layers.iter().zip(&self.layers) {
+            let outputs = layer.neurons.len();
+            let weight = entry(name, "weight", *weight, vec![outputs, layer.inputs])?;
+            let bias = entry(name, "bias", *bias, vec![outputs])?;
+
+            // PyTorch keeps a row of weights per output, like our neurons
+            for (row, bias) in weight.data.chunks(layer.inputs.max(1)).zip(&bias.data) {
+                values.extend_from_slice(&row[..layer.inputs]);
+                values.push(*bias);
+            }
+        }
+
+        Ok(self.load_parameters(&values)?)
+    }
+}
+
+fn entry<'a>(
+    layer: &str,
+    suffix: &str,
+    array: Option<&'a Array>,
+    expected: Vec<usize>,
+) -> Result<&'a Array> {
+    let key = format!("{layer}.{suffix}");
+    let array = array.ok_or_else(|| Error::Format(format!("missing {key}")))?;
+
+    if array.shape != expected {
+        return Err(Error::ShapeMismatch {
+            key,
+            expected,
+            found: array.shape.clone(),
+        });
+    }
+
+    Ok(array)
+}
+
+fn json_array(value: &serde_json::Value) -> Option<Array> {
+    match value {
+        serde_json::Value::Number(number) => Some(Array {
+            shape: vec![],
+            data: vec![number.as_f64()?],
+        }),
+        serde_json::Value::Array(items) => {
+            let items = items.iter().map(json_array).collect::<Option<Vec<_>>>()?;
+
+            let inner = items.first().map_or(vec![], |item| item.shape.clone());
+            if items.iter().any(|item| item.shape != inner) {
+                return None;
+            }
+
+            Some(Array {
+                shape: [vec![items.len()], inner].concat(),
+                data: items.into_iter().flat_map(|item| item.data).collect(),
+            })
+        }
+        _ => None,
+    }
+}
+
+// Orders names like `layers.2` before `layers.10`
+fn natural_order(a: &str, b: &str) -> Ordering {
+    let parts = |name: &str| -> Vec<(u64, String)> {
+        name.split('.')
+            .map(|part| match part.parse() {
+                Ok(number) => (number, String::new()),
+                Err(_) => (u64::MAX, part.to_string()),
+            })
+            .collect()
+    };
+
+    parts(a).cmp(&parts(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_state_dict, Error};
+    use crate::nn::Mlp;
+
+    #[test]
+    fn json_state_dict() {
+        let path =
+            std::env::temp_dir().join(format!("micrograd-torch-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{
+                "10.weight": [[1.0, -1.0, 0.5]],
+                "10.bias": [-0.5],
+                "2.weight": [[0.5, -1.0], [2.0, 3.0], [-0.25, 1.0]],
+                "2.bias": [0.1, 0.2, 0.3]
+            }"#,
+        )
+        .expect("should write");
+
+        let mlp = Mlp::new_seeded(2, &[3, 1], 0);
+        let loaded = mlp.load_torch(&path);
+        let state_dict = load_state_dict(&path);
+        std::fs::remove_file(&path).expect("should clean up");
+        loaded.expect("should load");
+
+        let parameters: Vec<_> = mlp.parameters().iter().map(|p| p.value()).collect();
+        assert_eq!(
+            parameters,
+            [0.5, -1.0, 0.1, 2.0, 3.0, 0.2, -0.25, 1.0, 0.3, 1.0, -1.0, 0.5, -0.5]
+        );
+
+        let state_dict = state_dict.expect("should read");
+        let smaller = Mlp::new_seeded(2, &[2, 1], 0);
+        let before: Vec<_> = smaller.parameters().iter().map(|p| p.value()).collect();
+
+        assert!(matches!(
+            smaller.load_state_dict(&state_dict),
+            Err(Error::ShapeMismatch { key, .. }) if key == "2.weight"
+        ));
+        assert_eq!(
+            smaller
+                .parameters()
+                .iter()
+                .map(|p| p.value())
+                .collect::<Vec<_>>(),
+            before
+        );
+
+        assert!(matches!(
+            Mlp::new_seeded(2, &[3], 0).load_state_dict(&state_dict),
+            Err(Error::LayerCount(1, 2))
+        ));
+    }
+}
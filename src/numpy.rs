@@ -0,0 +1,531 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use flate2::read::DeflateDecoder;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid npy file, {0}")]
+    Format(String),
+    #[error("Unsupported npy data type {0:?}, expected a float, int, unsigned int or bool")]
+    UnsupportedType(String),
+    #[error("Invalid npz archive, {0}")]
+    Archive(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An n-dimensional array read from NumPy's `.npy` format, with its elements
+/// converted to `f64` and stored in row-major order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array {
+    pub shape: Vec<usize>,
+    pub data: Vec<f64>,
+}
+
+impl Array {
+    /// Rows of a 2 dimensional array, or the single row of a 1 dimensional one
+    pub fn rows(&self) -> Option<Vec<Vec<f64>>> {
+        match self.shape[..] {
+            [columns] => Some(vec![self.data[..columns].to_vec()]),
+            [_, columns] => Some(
+                self.data
+                    .chunks(columns.max(1))
+                    .map(<[f64]>::to_vec)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a `.npy` file
+pub fn load_npy<P: AsRef<Path>>(path: P) -> Result<Array> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    parse_npy(&bytes)
+}
+
+/// Reads every array of an `.npz` archive, as written by `numpy.savez` or
+/// `numpy.savez_compressed`, in archive order. Names don't include the
+/// `.npy` extension.
+pub fn load_npz<P: AsRef<Path>>(path: P) -> Result<Vec<(String, Array)>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    zip_entries(&bytes)?
+        .into_iter()
+        .map(|(name, data)| {
+            let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+
+            Ok((name, parse_npy(&data)?))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Float,
+    Int,
+    Unsigned,
+}
+
+// The header is a Python dict literal, e.g.
+// {'descr': '<f8', 'fortran_order': False, 'shape': (3, 4), }
+pub(crate) fn parse_npy(bytes: &[u8]) -> Result<Array> {
+    if bytes.len() < 10 || &bytes[..6] != b"\x93NUMPY" {
+        return Err(Error::Format("missing magic string".to_string()));
+    }
+
+    let (header_len, start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (read_u32(bytes, 8) as usize, 12),
+        version => return Err(Error::Format(format!("unsupported version {version}"))),
+    };
+
+    let header = bytes
+        .get(start..start + header_len)
+        .and_then(|header| std::str::from_utf8(header).ok())
+        .ok_or_else(|| Error::Format("truncated header".to_string()))?;
+
+    let descr = header_value(header, "descr")?
+        .trim_matches(|c| c == '\'' || c == '"')
+        .to_string();
+    let fortran_order = header_value(header, "fortran_order")? == "True";
+    let shape = header_value(header, "shape")?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| {
+            dim.parse()
+                .map_err(|_| Error::Format(format!("invalid dimension {dim:?}")))
+        })
+        .collect::<Result<Vec<usize>>>()?;
+
+    let (little_endian, kind, size) = parse_descr(&descr)?;
+    let count: usize = shape.iter().product();
+    let data = &bytes[start + header_len..];
+
+    if data.len() != count * size {
+        return Err(Error::Format(format!(
+            "expected {} bytes of data, found {}",
+            count * size,
+            data.len()
+        )));
+    }
+
+    let values: Vec<f64> = data
+        .chunks_exact(size)
+        .map(|chunk| {
+            let mut bytes = [0; 8];
+            bytes[..size].copy_from_slice(chunk);
+            if !little_endian {
+                bytes[..size].reverse();
+            }
+
+            decode(kind, size, bytes)
+        })
+        .collect();
+
+    let data = if fortran_order {
+        transpose(&values, &shape)
+    } else {
+        values
+    };
+
+    Ok(Array { shape, data })
+}
+
+// Byte order, kind and size of a type description such as `<f8` or `|u1`
+fn parse_descr(descr: &str) -> Result<(bool, Kind, usize)> {
+    let unsupported = || Error::UnsupportedType(descr.to_string());
+
+    let mut chars = descr.chars();
+    let little_endian = match chars.next() {
+        Some('<' | '|') => true,
+        Some('>') => false,
+        Some('=') => cfg!(target_endian = "little"),
+        _ => return Err(unsupported()),
+    };
+
+    let kind = match chars.next() {
+        Some('f') => Kind::Float,
+        Some('i') => Kind::Int,
+        Some('u') => Kind::Unsigned,
+        Some('b') if chars.as_str() == "1" => Kind::Unsigned,
+        _ => return Err(unsupported()),
+    };
+
+    let size = chars.as_str().parse().map_err(|_| unsupported())?;
+
+    match (kind, size) {
+        (Kind::Float, 4 | 8) | (Kind::Int | Kind::Unsigned, 1 | 2 | 4 | 8) => {
+            Ok((little_endian, kind, size))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+fn decode(kind: Kind, size: usize, bytes: [u8; 8]) -> f64 {
+    let [b0, b1, b2, b3, ..] = bytes;
+
+    match (kind, size) {
+        (Kind::Float, 4) => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+        (Kind::Float, _) => f64::from_le_bytes(bytes),
+        (Kind::Int, 1) => b0 as i8 as f64,
+        (Kind::Int, 2) => i16::from_le_bytes([b0, b1]) as f64,
+        (Kind::Int, 4) => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+        (Kind::Int, _) => i64::from_le_bytes(bytes) as f64,
+        (Kind::Unsigned, 1) => b0 as f64,
+        (Kind::Unsigned, 2) => u16::from_le_bytes([b0, b1]) as f64,
+        (Kind::Unsigned, 4) => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+        (Kind::Unsigned, _) => u64::from_le_bytes(bytes) as f64,
+    }
+}
+
+// Reorders column-major values into row-major order
+fn transpose(values: &[f64], shape: &[usize]) -> Vec<f64> {
+    // strides of the column-major layout, first dimension fastest
+    let strides: Vec<usize> = shape
+        .iter()
+        .scan(1, |stride, dim| {
+            let current = *stride;
+            *stride *= dim;
+            Some(current)
+        })
+        .collect();
+
+    (0..values.len())
+        .map(|mut index| {
+            let mut offset = 0;
+
+            for (dim, stride) in shape.iter().zip(&strides).rev() {
+                offset += index % dim * stride;
+                index /= dim;
+            }
+
+            values[offset]
+        })
+        .collect()
+}
+
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str> {
+    let missing = || Error::Format(format!("header has no {key}"));
+
+    let start = header.find(&format!("'{key}'")).ok_or_else(missing)? + key.len() + 2;
+    let rest = header[start..]
+        .trim_start()
+        .strip_prefix(':')
+        .ok_or_else(missing)?
+        .trim_start();
+
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|end| end + 1)
+    } else {
+        rest.find([',', '}'])
+    };
+
+    Ok(rest[..end.ok_or_else(missing)?].trim())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(
+        bytes[offset..offset + 8]
+            .try_into()
+            .expect("should be 8 bytes"),
+    )
+}
+
+// Entries of a zip archive found through its central directory, which holds
+// the sizes even when entries were streamed, as NumPy does
+fn zip_entries(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let invalid = |reason: &str| Error::Archive(reason.to_string());
+
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_u32(bytes, i) == 0x0605_4b50)
+        .ok_or_else(|| invalid("missing end of central directory"))?;
+
+    let count = read_u16(bytes, end + 10) as usize;
+    let mut offset = read_u32(bytes, end + 16) as usize;
+    let mut entries = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        if offset + 46 > bytes.len() || read_u32(bytes, offset) != 0x0201_4b50 {
+            return Err(invalid("invalid central directory entry"));
+        }
+
+        let method = read_u16(bytes, offset + 10);
+        let mut compressed = read_u32(bytes, offset + 20) as u64;
+        let mut uncompressed = read_u32(bytes, offset + 24) as u64;
+        let name_len = read_u16(bytes, offset + 28) as usize;
+        let extra_len = read_u16(bytes, offset + 30) as usize;
+        let comment_len = read_u16(bytes, offset + 32) as usize;
+        let mut local = read_u32(bytes, offset + 42) as u64;
+
+        let name_start = offset + 46;
+        let extra_start = name_start + name_len;
+        let extra = bytes
+            .get(extra_start..extra_start + extra_len)
+            .ok_or_else(|| invalid("truncated central directory"))?;
+        let name = String::from_utf8_lossy(&bytes[name_start..extra_start]).into_owned();
+
+        // zip64 sizes and offset replace the ones saturated at u32::MAX, in order
+        let mut field = 0;
+        while field + 4 <= extra.len() {
+            let id = read_u16(extra, field);
+            let size = read_u16(extra, field + 2) as usize;
+            let mut value = field + 4;
+
+            if id == 1 {
+                for target in [&mut uncompressed, &mut compressed, &mut local] {
+                    if *target == u32::MAX as u64 && value + 8 <= extra.len() {
+                        *target = read_u64(extra, value);
+                        value += 8;
+                    }
+                }
+            }
+
+            field += 4 + size;
+        }
+
+        let local = local as usize;
+        if local + 30 > bytes.len() || read_u32(bytes, local) != 0x0403_4b50 {
+            return Err(invalid("invalid local file header"));
+        }
+
+        let data_start = local
+            + 30
+            + read_u16(bytes, local + 26) as usize
+            + read_u16(bytes, local + 28) as usize;
+        let data = bytes
+            .get(data_start..data_start + compressed as usize)
+            .ok_or_else(|| invalid("truncated entry"))?;
+
+        let data = match method {
+            0 => data.to_vec(),
+            8 => {
+                // deflate expands at most 1032 times, a larger size is bogus
+                let capacity = (uncompressed as usize).min(data.len().saturating_mul(1032));
+                let mut inflated = Vec::with_capacity(capacity);
+                DeflateDecoder::new(data).read_to_end(&mut inflated)?;
+                inflated
+            }
+            method => {
+                return Err(Error::Archive(format!(
+                    "unsupported compression method {method}"
+                )))
+            }
+        };
+
+        entries.push((name, data));
+        offset = extra_start + extra_len + comment_len;
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Write;
+
+    use flate2::{write::DeflateEncoder, Compression};
+
+    use super::{load_npz, parse_npy, zip_entries, Array, Error};
+
+    pub(crate) fn npy(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+        let fortran_order = if fortran_order { "True" } else { "False" };
+        let mut header =
+            format!("{{'descr': '{descr}', 'fortran_order': {fortran_order}, 'shape': {shape}, }}");
+        // padded so that the data is aligned to 64 bytes
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+
+        bytes
+    }
+
+    pub(crate) fn bytes<const N: usize, T: Copy>(
+        values: &[T],
+        to_bytes: fn(T) -> [u8; N],
+    ) -> Vec<u8> {
+        values.iter().flat_map(|value| to_bytes(*value)).collect()
+    }
+
+    // A zip archive with the first entry stored and the rest deflated
+    pub(crate) fn zip(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+
+        for (i, (name, data)) in entries.iter().enumerate() {
+            let (method, compressed) = if i == 0 {
+                (0u16, data.clone())
+            } else {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data).expect("should compress");
+                (8, encoder.finish().expect("should compress"))
+            };
+
+            let offset = archive.len() as u32;
+            let sizes = [
+                0u32.to_le_bytes(), // crc, unchecked
+                (compressed.len() as u32).to_le_bytes(),
+                (data.len() as u32).to_le_bytes(),
+            ]
+            .concat();
+
+            archive.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            archive.extend_from_slice(&[20, 0, 0, 0]);
+            archive.extend_from_slice(&method.to_le_bytes());
+            archive.extend_from_slice(&[0; 4]);
+            archive.extend_from_slice(&sizes);
+            archive.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            archive.extend_from_slice(&[0, 0]);
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(&compressed);
+
+            directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            directory.extend_from_slice(&method.to_le_bytes());
+            directory.extend_from_slice(&[0; 4]);
+            directory.extend_from_slice(&sizes);
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+
+        let directory_offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        archive.extend_from_slice(&[0; 4]);
+        archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&directory_offset.to_le_bytes());
+        archive.extend_from_slice(&[0, 0]);
+
+        archive
+    }
+
+    #[test]
+    fn npy_arrays() {
+        let floats = npy(
+            "<f8",
+            false,
+            "(2, 3)",
+            &bytes(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.5], f64::to_le_bytes),
+        );
+        let array = parse_npy(&floats).expect("should parse");
+
+        assert_eq!(array.shape, [2, 3]);
+        assert_eq!(
+            array.rows(),
+            Some(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.5]])
+        );
+
+        // the same matrix stored column by column
+        let fortran = npy(
+            "<f4",
+            true,
+            "(2, 3)",
+            &bytes(&[1.0f32, 4.0, 2.0, 5.0, 3.0, 6.5], f32::to_le_bytes),
+        );
+        assert_eq!(parse_npy(&fortran).expect("should parse"), array);
+
+        let ints = npy(">i4", false, "(3,)", &bytes(&[-1, 0, 7], i32::to_be_bytes));
+        assert_eq!(
+            parse_npy(&ints).expect("should parse"),
+            Array {
+                shape: vec![3],
+                data: vec![-1.0, 0.0, 7.0]
+            }
+        );
+
+        let complex = npy("<c16", false, "(1,)", &[0; 16]);
+        assert!(matches!(
+            parse_npy(&complex),
+            Err(Error::UnsupportedType(_))
+        ));
+
+        let truncated = npy("<f8", false, "(2,)", &[0; 8]);
+        assert!(matches!(parse_npy(&truncated), Err(Error::Format(_))));
+    }
+
+    #[test]
+    fn npz_archive() {
+        let path = std::env::temp_dir().join(format!("micrograd-{}.npz", std::process::id()));
+        let weight = npy(
+            "<f4",
+            false,
+            "(1, 2)",
+            &bytes(&[0.5f32, -2.0], f32::to_le_bytes),
+        );
+        let bias = npy("<f4", false, "(1,)", &bytes(&[0.25f32], f32::to_le_bytes));
+
+        std::fs::write(
+            &path,
+            zip(&[("0.weight.npy", weight), ("0.bias.npy", bias)]),
+        )
+        .expect("should write");
+        let arrays = load_npz(&path);
+        std::fs::remove_file(&path).expect("should clean up");
+        let arrays = arrays.expect("should load");
+
+        assert_eq!(arrays[0].0, "0.weight");
+        assert_eq!(arrays[0].1.data, vec![0.5, -2.0]);
+        assert_eq!(arrays[1].0, "0.bias");
+        assert_eq!(arrays[1].1.shape, [1]);
+    }
+
+    #[test]
+    fn bogus_uncompressed_size() {
+        let mut archive = zip(&[("a", vec![1]), ("b", vec![2; 100])]);
+
+        // claim a zip64 uncompressed size of 2^62 bytes for the deflated entry
+        let directory = u32::from_le_bytes(
+            archive[archive.len() - 6..][..4]
+                .try_into()
+                .expect("should be 4 bytes"),
+        ) as usize;
+        let second = directory + 46 + 1;
+        archive[second + 24..second + 28].copy_from_slice(&u32::MAX.to_le_bytes());
+        archive[second + 30..second + 32].copy_from_slice(&12u16.to_le_bytes());
+        let extra = [
+            &1u16.to_le_bytes()[..],
+            &8u16.to_le_bytes(),
+            &(1u64 << 62).to_le_bytes(),
+        ];
+        archive.splice(second + 47..second + 47, extra.concat());
+
+        let entries = zip_entries(&archive).expect("should read");
+        assert_eq!(entries[1], ("b".to_string(), vec![2; 100]));
+    }
+}
@@ -0,0 +1,303 @@
+//! Export of models to [ONNX](https://onnx.ai), so they can be served by any
+//! ONNX runtime.
+//!
+//! Every layer becomes a `MatMul`, an `Add` and an activation node, with the
+//! weights stored as single precision initializers. The graph takes a
+//! `[batch, inputs]` tensor named `input` and produces `output`.
+//!
+//! Besides tanh, custom activations registered as `relu`, `sigmoid`,
+//! `softplus` or `softsign` map to the ONNX operator of that name, others
+//! can't be exported.
+
+use std::{fs, io, path::Path};
+
+use thiserror::Error as ThisError;
+
+use crate::{
+    nn::Mlp,
+    protobuf::{encode_bytes, encode_varint_field},
+};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Activation {0:?} has no ONNX operator")]
+    UnsupportedActivation(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const IR_VERSION: u64 = 8;
+const OPSET_VERSION: u64 = 13;
+const FLOAT: u64 = 1;
+
+/// Serializes the model into an ONNX `ModelProto`
+pub fn encode(model: &Mlp) -> Result<Vec<u8>> {
+    let sizes = model.layer_sizes();
+    let layers = model.inference_parameters();
+
+    let mut graph = Vec::new();
+    let mut previous = "input".to_string();
+    let activation = match model.activation().name() {
+        "tanh" => "Tanh",
+        "relu" => "Relu",
+        "sigmoid" => "Sigmoid",
+        "softplus" => "Softplus",
+        "softsign" => "Softsign",
+        name => return Err(Error::UnsupportedActivation(name.to_string())),
+    };
+
+    for (i, (parameters, window)) in layers.iter().zip(sizes.windows(2)).enumerate() {
+        let (inputs, outputs) = (window[0], window[1]);
+        let (weights, biases) = split_parameters(parameters, inputs, outputs);
+
+        let (w, b) = (format!("w_{i}"), format!("b_{i}"));
+        let (matmul, add) = (format!("matmul_{i}"), format!("add_{i}"));
+        let output = if i + 1 == layers.len() {
+            "output".to_string()
+        } else {
+            format!("layer_{i}")
+        };
+
+        encode_bytes(&mut graph, 1, &node("MatMul", &[&previous, &w], &matmul));
+        encode_bytes(&mut graph, 1, &node("Add", &[&matmul, &b], &add));
+        encode_bytes(&mut graph, 1, &node(activation, &[&add], &output));
+
+        encode_bytes(&mut graph, 5, &tensor(&w, &[inputs, outputs], &weights));
+        encode_bytes(&mut graph, 5, &tensor(&b, &[outputs], &biases));
+
+        previous = output;
+    }
+
+    encode_bytes(&mut graph, 2, b"micrograd");
+    encode_bytes(&mut graph, 11, &value_info("input", sizes[0]));
+    encode_bytes(
+        &mut graph,
+        12,
+        &value_info("output", sizes[sizes.len() - 1]),
+    );
+
+    let mut opset = Vec::new();
+    encode_varint_field(&mut opset, 2, OPSET_VERSION);
+
+    let mut model = Vec::new();
+    encode_varint_field(&mut model, 1, IR_VERSION);
+    encode_bytes(&mut model, 2, b"micrograd");
+    encode_bytes(&mut model, 7, &graph);
+    encode_bytes(&mut model, 8, &opset);
+
+    Ok(model)
+}
+
+/// Writes the model into an `.onnx` file
+pub fn save<P: AsRef<Path>>(model: &Mlp, path: P) -> Result<()> {
+    fs::write(path, encode(model)?)?;
+
+    Ok(())
+}
+
+// Neuron parameters are their weights followed by the bias. MatMul needs the
+// weights transposed, with a column per neuron.
+fn split_parameters(parameters: &[f64], inputs: usize, outputs: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut weights = vec![0.0; inputs * outputs];
+    let mut biases = Vec::with_capacity(outputs);
+
+    for (neuron, parameters) in parameters.chunks(inputs + 1).enumerate() {
+        for (input, weight) in parameters[..inputs].iter().enumerate() {
+            weights[input * outputs + neuron] = *weight;
+        }
+
+        biases.push(parameters[inputs]);
+    }
+
+    (weights, biases)
+}
+
+fn node(op_type: &str, inputs: &[&str], output: &str) -> Vec<u8> {
+    let mut node = Vec::new();
+
+    for input in inputs {
+        encode_bytes(&mut node, 1, input.as_bytes());
+    }
+    encode_bytes(&mut node, 2, output.as_bytes());
+    encode_bytes(&mut node, 3, output.as_bytes());
+    encode_bytes(&mut node, 4, op_type.as_bytes());
+
+    node
+}
+
+fn tensor(name: &str, dims: &[usize], values: &[f64]) -> Vec<u8> {
+    let mut tensor = Vec::new();
+
+    for dim in dims {
+        encode_varint_field(&mut tensor, 1, *dim as u64);
+    }
+    encode_varint_field(&mut tensor, 2, FLOAT);
+    encode_bytes(&mut tensor, 8, name.as_bytes());
+
+    let raw: Vec<u8> = values
+        .iter()
+        .flat_map(|value| (*value as f32).to_le_bytes())
+        .collect();
+    encode_bytes(&mut tensor, 9, &raw);
+
+    tensor
+}
+
+// A float tensor of shape [batch, features]
+fn value_info(name: &str, features: usize) -> Vec<u8> {
+    let mut batch = Vec::new();
+    encode_bytes(&mut batch, 2, b"batch");
+
+    let mut width = Vec::new();
+    encode_varint_field(&mut width, 1, features as u64);
+
+    let mut shape = Vec::new();
+    encode_bytes(&mut shape, 1, &batch);
+    encode_bytes(&mut shape, 1, &width);
+
+    let mut tensor_type = Vec::new();
+    encode_varint_field(&mut tensor_type, 1, FLOAT);
+    encode_bytes(&mut tensor_type, 2, &shape);
+
+    let mut type_proto = Vec::new();
+    encode_bytes(&mut type_proto, 1, &tensor_type);
+
+    let mut info = Vec::new();
+    encode_bytes(&mut info, 1, name.as_bytes());
+    encode_bytes(&mut info, 2, &type_proto);
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, Error};
+    use crate::nn::{Activation, Mlp};
+
+    enum Field<'a> {
+        Varint(u64),
+        Bytes(&'a [u8]),
+    }
+
+    fn varint(bytes: &[u8], at: &mut usize) -> u64 {
+        let mut value = 0;
+
+        for shift in (0..).step_by(7) {
+            let byte = bytes[*at];
+            *at += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte < 0x80 {
+                break;
+            }
+        }
+
+        value
+    }
+
+    fn fields(bytes: &[u8]) -> Vec<(u64, Field<'_>)> {
+        let mut fields = Vec::new();
+        let mut at = 0;
+
+        while at < bytes.len() {
+            let key = varint(bytes, &mut at);
+
+            let field = match key & 7 {
+                0 => Field::Varint(varint(bytes, &mut at)),
+                2 => {
+                    let len = varint(bytes, &mut at) as usize;
+                    at += len;
+
+                    Field::Bytes(&bytes[at - len..at])
+                }
+                wire => panic!("unexpected wire type {wire}"),
+            };
+
+            fields.push((key >> 3, field));
+        }
+
+        fields
+    }
+
+    fn bytes<'a>(fields: &[(u64, Field<'a>)], number: u64) -> Vec<&'a [u8]> {
+        fields
+            .iter()
+            .filter_map(|(n, field)| match field {
+                Field::Bytes(bytes) if *n == number => Some(*bytes),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn encodes_layers() {
+        let mlp = Mlp::new_seeded(3, &[4, 2], 1);
+        let model = encode(&mlp).expect("should encode");
+        let model = fields(&model);
+
+        assert!(matches!(model[0], (1, Field::Varint(8))));
+
+        let graph = fields(bytes(&model, 7)[0]);
+        let ops: Vec<_> = bytes(&graph, 1)
+            .into_iter()
+            .map(|node| bytes(&fields(node), 4)[0])
+            .collect();
+
+        assert_eq!(
+            ops,
+            [
+                b"MatMul" as &[u8],
+                b"Add",
+                b"Tanh",
+                b"MatMul",
+                b"Add",
+                b"Tanh"
+            ]
+        );
+
+        let initializers = bytes(&graph, 5);
+        let first = fields(initializers[0]);
+        let raw = bytes(&first, 9)[0];
+
+        assert_eq!(initializers.len(), 4);
+        assert_eq!(raw.len(), 3 * 4 * 4);
+
+        // the second weight of the first neuron is in the second row
+        let parameters = mlp.parameters();
+        let value = f32::from_le_bytes(raw[16..20].try_into().expect("should be 4 bytes"));
+        assert_eq!(value, parameters[1].value() as f32);
+    }
+
+    #[test]
+    fn quantization_aware() {
+        let mlp = Mlp::new_seeded(2, &[1], 1).quantization_aware();
+        mlp.load_parameters(&[1.0, 0.3, 0.0]).expect("should load");
+
+        let model = encode(&mlp).expect("should encode");
+        let graph = fields(bytes(&fields(&model), 7)[0]);
+        let weights = fields(bytes(&graph, 5)[0]);
+        let raw = bytes(&weights, 9)[0];
+
+        // rounded to the int8 levels of the layer, like `infer`
+        let value = f32::from_le_bytes(raw[4..8].try_into().expect("should be 4 bytes"));
+        assert_eq!(value, (38.0 / 127.0) as f32);
+    }
+
+    #[test]
+    fn activations() {
+        let sigmoid = Activation::new("sigmoid", |x| x.sigmoid(), |x| 1.0 / (1.0 + (-x).exp()));
+        let mlp = Mlp::new_seeded(2, &[1], 1).with_activation(sigmoid);
+        let model = encode(&mlp).expect("should encode");
+        let graph = fields(bytes(&fields(&model), 7)[0]);
+        let nodes = bytes(&graph, 1);
+        assert_eq!(bytes(&fields(nodes[2]), 4)[0], b"Sigmoid");
+
+        let custom = Activation::new("gelu_custom", |x| x, |x| x);
+        assert!(matches!(
+            encode(&Mlp::new_seeded(2, &[1], 1).with_activation(custom)),
+            Err(Error::UnsupportedActivation(name)) if name == "gelu_custom"
+        ));
+    }
+}
@@ -0,0 +1,464 @@
+pub mod scheduler;
+
+use thiserror::Error as ThisError;
+
+use crate::value::Value;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("Optimizer state mismatch, expected {0} buffers of {1} values")]
+    StateMismatch(usize, usize),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Snapshot of an optimizer's internal state, enough to continue training
+/// exactly where it left off
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OptimizerState {
+    pub learning_rate: f64,
+    pub steps: usize,
+    /// Per-parameter buffers, e.g. Adam's moment estimates
+    pub buffers: Vec<Vec<f64>>,
+}
+
+/// An update rule for a fixed list of parameters.
+///
+/// Optimizers capture the parameters when constructed (e.g. from `Mlp::parameters`),
+/// so after backpropagating the loss, a training loop only needs to call `step`
+/// followed by `zero_grad`.
+pub trait Optimizer {
+    /// Updates every parameter using its current gradient
+    fn step(&mut self);
+
+    /// Resets the gradients of all parameters ahead of the next backpropagation
+    fn zero_grad(&mut self);
+
+    fn learning_rate(&self) -> f64;
+
+    /// Overrides the learning rate used by subsequent steps, e.g. from a scheduler
+    fn set_learning_rate(&mut self, learning_rate: f64);
+
+    fn state(&self) -> OptimizerState;
+
+    /// Restores a snapshot taken by `state` of an optimizer of the same kind
+    /// and parameter count
+    fn load_state(&mut self, state: OptimizerState) -> Result<()>;
+}
+
+/// Gradient clipping applied by an optimizer at the start of each step
+#[derive(Debug, Clone, Copy)]
+enum Clipping {
+    Norm(f64),
+    Value(f64),
+}
+
+impl Clipping {
+    fn apply(&self, parameters: &[Value]) {
+        match *self {
+            Clipping::Norm(max) => {
+                clip_grad_norm(parameters, max);
+            }
+            Clipping::Value(max) => clip_grad_value(parameters, max),
+        }
+    }
+}
+
+/// Global L2 norm of the gradients of `parameters`
+pub fn grad_norm(parameters: &[Value]) -> f64 {
+    parameters
+        .iter()
+        .map(|p| p.gradient().powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Rescales the gradients of `parameters` so that their global L2 norm is at most `max`.
+///
+/// Returns the norm before clipping.
+pub fn clip_grad_norm(parameters: &[Value], max: f64) -> f64 {
+    let norm = grad_norm(parameters);
+
+    if norm > max {
+        let scale = max / norm;
+
+        for parameter in parameters {
+            parameter.set_gradient(parameter.gradient() * scale);
+        }
+    }
+
+    norm
+}
+
+/// Clamps each gradient of `parameters` into `[-|max|, |max|]`. A NaN `max`
+/// leaves them as they are.
+pub fn clip_grad_value(parameters: &[Value], max: f64) {
+    let max = max.abs();
+
+    for parameter in parameters {
+        parameter.set_gradient(parameter.gradient().max(-max).min(max));
+    }
+}
+
+/// Plain stochastic gradient descent
+#[derive(Debug)]
+pub struct Sgd {
+    parameters: Vec<Value>,
+    learning_rate: f64,
+    clipping: Option<Clipping>,
+}
+
+impl Sgd {
+    pub fn new(parameters: Vec<Value>, learning_rate: f64) -> Self {
+        Self {
+            parameters,
+            learning_rate,
+            clipping: None,
+        }
+    }
+
+    /// Clips the global gradient norm to `max` before every step
+    pub fn clip_grad_norm(mut self, max: f64) -> Self {
+        self.clipping = Some(Clipping::Norm(max));
+        self
+    }
+
+    /// Clamps every gradient into `[-max, max]` before every step
+    pub fn clip_grad_value(mut self, max: f64) -> Self {
+        self.clipping = Some(Clipping::Value(max));
+        self
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self) {
+        if let Some(clipping) = self.clipping {
+            clipping.apply(&self.parameters);
+        }
+
+        for parameter in &self.parameters {
+            parameter.set_value(parameter.value() - self.learning_rate * parameter.gradient());
+        }
+    }
+
+    fn zero_grad(&mut self) {
+        for parameter in &self.parameters {
+            parameter.zero_gradient();
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState {
+            learning_rate: self.learning_rate,
+            ..Default::default()
+        }
+    }
+
+    fn load_state(&mut self, state: OptimizerState) -> Result<()> {
+        if !state.buffers.is_empty() {
+            return Err(Error::StateMismatch(0, self.parameters.len()));
+        }
+
+        self.learning_rate = state.learning_rate;
+
+        Ok(())
+    }
+}
+
+/// Adam with decoupled weight decay (Loshchilov & Hutter).
+///
+/// Weight decay shrinks the parameters directly instead of being added to the
+/// gradient, so it isn't rescaled by the adaptive learning rate. With a weight
+/// decay of 0.0 this is plain Adam.
+#[derive(Debug)]
+pub struct AdamW {
+    parameters: Vec<Value>,
+    learning_rate: f64,
+    weight_decay: f64,
+    betas: (f64, f64),
+    epsilon: f64,
+    first_moments: Vec<f64>,
+    second_moments: Vec<f64>,
+    steps: usize,
+    clipping: Option<Clipping>,
+}
+
+impl AdamW {
+    pub fn new(parameters: Vec<Value>, learning_rate: f64, weight_decay: f64) -> Self {
+        let count = parameters.len();
+
+        Self {
+            parameters,
+            learning_rate,
+            weight_decay,
+            betas: (0.9, 0.999),
+            epsilon: 1e-8,
+            first_moments: vec![0.0; count],
+            second_moments: vec![0.0; count],
+            steps: 0,
+            clipping: None,
+        }
+    }
+
+    /// Decay rates of the running averages of the gradient and its square
+    pub fn betas(mut self, beta1: f64, beta2: f64) -> Self {
+        self.betas = (beta1, beta2);
+        self
+    }
+
+    /// Clips the global gradient norm to `max` before every step
+    pub fn clip_grad_norm(mut self, max: f64) -> Self {
+        self.clipping = Some(Clipping::Norm(max));
+        self
+    }
+
+    /// Clamps every gradient into `[-max, max]` before every step
+    pub fn clip_grad_value(mut self, max: f64) -> Self {
+        self.clipping = Some(Clipping::Value(max));
+        self
+    }
+}
+
+impl Optimizer for AdamW {
+    fn step(&mut self) {
+        if let Some(clipping) = self.clipping {
+            clipping.apply(&self.parameters);
+        }
+
+        let (beta1, beta2) = self.betas;
+
+        self.steps += 1;
+
+        let correction1 = 1.0 - beta1.powf(self.steps as f64);
+        let correction2 = 1.0 - beta2.powf(self.steps as f64);
+
+        for ((parameter, m), v) in self
+            .parameters
+            .iter()
+            .zip(&mut self.first_moments)
+            .zip(&mut self.second_moments)
+        {
+            let grad = parameter.gradient();
+
+            *m = beta1 * *m + (1.0 - beta1) * grad;
+            *v = beta2 * *v + (1.0 - beta2) * grad * grad;
+
+            let m_hat = *m / correction1;
+            let v_hat = *v / correction2;
+
+            let decayed = parameter.value() * (1.0 - self.learning_rate * self.weight_decay);
+
+            parameter
+                .set_value(decayed - self.learning_rate * m_hat / (v_hat.sqrt() + self.epsilon));
+        }
+    }
+
+    fn zero_grad(&mut self) {
+        for parameter in &self.parameters {
+            parameter.zero_gradient();
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState {
+            learning_rate: self.learning_rate,
+            steps: self.steps,
+            buffers: vec![self.first_moments.clone(), self.second_moments.clone()],
+        }
+    }
+
+    fn load_state(&mut self, state: OptimizerState) -> Result<()> {
+        let count = self.parameters.len();
+
+        match <[_; 2]>::try_from(state.buffers) {
+            Ok([first, second]) if first.len() == count && second.len() == count => {
+                self.learning_rate = state.learning_rate;
+                self.steps = state.steps;
+                self.first_moments = first;
+                self.second_moments = second;
+
+                Ok(())
+            }
+            _ => Err(Error::StateMismatch(2, count)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clip_grad_norm, clip_grad_value, AdamW, Error, Optimizer, OptimizerState, Sgd};
+    use crate::value::Value;
+
+    #[test]
+    fn sgd_step() {
+        let x = Value::new(1.0, "x");
+        let mut sgd = Sgd::new(vec![x.clone()], 0.1);
+
+        let loss = (x.clone() - Value::new(3.0, "3")).pow(2.0);
+        loss.backpropagate();
+        sgd.step();
+
+        assert_eq!(x.value(), 1.4);
+        assert_eq!(x.gradient(), -4.0);
+
+        sgd.zero_grad();
+
+        assert_eq!(x.gradient(), 0.0);
+    }
+
+    #[test]
+    fn sgd_converges() {
+        let x = Value::new(1.0, "x");
+        let mut sgd = Sgd::new(vec![x.clone()], 0.1);
+
+        for _ in 0..100 {
+            let loss = (x.clone() - Value::new(3.0, "3")).pow(2.0);
+
+            loss.backpropagate();
+            sgd.step();
+            sgd.zero_grad();
+        }
+
+        assert!((x.value() - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn adamw_first_step() {
+        let x = Value::new(1.0, "x");
+        let mut adam = AdamW::new(vec![x.clone()], 0.1, 0.0);
+
+        let loss = (x.clone() - Value::new(3.0, "3")).pow(2.0);
+        loss.backpropagate();
+        adam.step();
+
+        // the bias-corrected first step moves by the learning rate against the gradient
+        assert!((x.value() - 1.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn adamw_decoupled_weight_decay() {
+        let x = Value::new(2.0, "x");
+        let mut adam = AdamW::new(vec![x.clone()], 0.1, 0.5);
+
+        // with a zero gradient only the weight decay applies
+        adam.step();
+
+        assert!((x.value() - 2.0 * (1.0 - 0.1 * 0.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn adamw_converges() {
+        let x = Value::new(1.0, "x");
+        let mut adam = AdamW::new(vec![x.clone()], 0.05, 0.0);
+
+        for _ in 0..1000 {
+            let loss = (x.clone() - Value::new(3.0, "3")).pow(2.0);
+
+            loss.backpropagate();
+            adam.step();
+            adam.zero_grad();
+        }
+
+        assert!((x.value() - 3.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn clipping_by_norm() {
+        let a = Value::new(0.0, "a");
+        let b = Value::new(0.0, "b");
+        a.set_gradient(3.0);
+        b.set_gradient(4.0);
+
+        let norm = clip_grad_norm(&[a.clone(), b.clone()], 1.0);
+
+        assert_eq!(norm, 5.0);
+        assert!((a.gradient() - 0.6).abs() < 1e-12);
+        assert!((b.gradient() - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sgd_clips_before_step() {
+        let a = Value::new(0.0, "a");
+        let b = Value::new(0.0, "b");
+        let mut sgd = Sgd::new(vec![a.clone(), b.clone()], 1.0).clip_grad_value(0.5);
+
+        a.set_gradient(3.0);
+        b.set_gradient(-0.25);
+        sgd.step();
+
+        assert_eq!(a.value(), -0.5);
+        assert_eq!(b.value(), 0.25);
+    }
+
+    #[test]
+    fn clips_by_magnitude() {
+        let parameters = [Value::new(0.0, "a")];
+        parameters[0].set_gradient(-3.0);
+
+        clip_grad_value(&parameters, -0.5);
+        assert_eq!(parameters[0].gradient(), -0.5);
+
+        clip_grad_value(&parameters, f64::NAN);
+        assert_eq!(parameters[0].gradient(), -0.5);
+    }
+
+    #[test]
+    fn adamw_state_round_trip() {
+        let x = Value::new(1.0, "x");
+        let mut adam = AdamW::new(vec![x.clone()], 0.1, 0.0);
+
+        x.set_gradient(2.0);
+        adam.step();
+
+        let state = adam.state();
+        let mut restored = AdamW::new(vec![Value::new(1.0, "y")], 0.5, 0.0);
+        restored
+            .load_state(state.clone())
+            .expect("state should load");
+
+        assert_eq!(restored.state(), state);
+        assert_eq!(state.steps, 1);
+
+        let mut sgd = Sgd::new(vec![x], 0.1);
+        assert!(matches!(
+            sgd.load_state(state),
+            Err(Error::StateMismatch(0, 1))
+        ));
+    }
+
+    #[test]
+    fn adamw_long_runs() {
+        let x = Value::new(1.0, "x");
+        let mut adam = AdamW::new(vec![x.clone()], 0.1, 0.0);
+        adam.load_state(OptimizerState {
+            learning_rate: 0.1,
+            steps: i32::MAX as usize,
+            buffers: vec![vec![0.0], vec![0.0]],
+        })
+        .expect("state should load");
+
+        // the bias corrections have long vanished
+        x.set_gradient(2.0);
+        adam.step();
+
+        let expected = 1.0 - 0.1 * 0.2 / (0.004f64.sqrt() + 1e-8);
+        assert!((x.value() - expected).abs() < 1e-12);
+        assert_eq!(adam.state().steps, i32::MAX as usize + 1);
+    }
+}
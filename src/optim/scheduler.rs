@@ -0,0 +1,247 @@
+use std::f64::consts::PI;
+
+use super::Optimizer;
+
+/// A learning rate schedule driving an optimizer.
+///
+/// Schedulers are stepped separately from the optimizer, typically once per
+/// epoch, and set the optimizer's learning rate for the steps that follow.
+pub trait LrScheduler {
+    /// Advances the schedule by one step and updates the optimizer's learning rate
+    fn step(&mut self, optimizer: &mut dyn Optimizer);
+}
+
+impl<S: LrScheduler + ?Sized> LrScheduler for Box<S> {
+    fn step(&mut self, optimizer: &mut dyn Optimizer) {
+        (**self).step(optimizer);
+    }
+}
+
+/// Decays the learning rate by `gamma` every `step_size` steps
+#[derive(Debug)]
+pub struct StepLR {
+    step_size: usize,
+    gamma: f64,
+    steps: usize,
+}
+
+impl StepLR {
+    /// A `step_size` of 0 decays every step, like 1
+    pub fn new(step_size: usize, gamma: f64) -> Self {
+        Self {
+            step_size: step_size.max(1),
+            gamma,
+            steps: 0,
+        }
+    }
+}
+
+impl LrScheduler for StepLR {
+    fn step(&mut self, optimizer: &mut dyn Optimizer) {
+        self.steps += 1;
+
+        if self.steps.is_multiple_of(self.step_size) {
+            optimizer.set_learning_rate(optimizer.learning_rate() * self.gamma);
+        }
+    }
+}
+
+/// Anneals the learning rate from the optimizer's initial rate down to `eta_min`
+/// along half a cosine period over `t_max` steps, then holds it at `eta_min`
+#[derive(Debug)]
+pub struct CosineAnnealingLR {
+    t_max: usize,
+    eta_min: f64,
+    base_lr: Option<f64>,
+    steps: usize,
+}
+
+impl CosineAnnealingLR {
+    pub fn new(t_max: usize, eta_min: f64) -> Self {
+        Self {
+            t_max,
+            eta_min,
+            base_lr: None,
+            steps: 0,
+        }
+    }
+}
+
+impl LrScheduler for CosineAnnealingLR {
+    fn step(&mut self, optimizer: &mut dyn Optimizer) {
+        let base_lr = *self.base_lr.get_or_insert(optimizer.learning_rate());
+
+        self.steps = (self.steps + 1).min(self.t_max);
+
+        let progress = if self.t_max == 0 {
+            1.0
+        } else {
+            self.steps as f64 / self.t_max as f64
+        };
+
+        optimizer.set_learning_rate(
+            self.eta_min + (base_lr - self.eta_min) * (1.0 + (PI * progress).cos()) / 2.0,
+        );
+    }
+}
+
+/// Cycles the learning rate linearly from `base_lr` up to `max_lr` and back
+/// down every `cycle_length` steps, the triangular policy of Smith's cyclical
+/// learning rates. The optimizer should start at `base_lr`.
+#[derive(Debug)]
+pub struct CyclicLR {
+    base_lr: f64,
+    max_lr: f64,
+    cycle_length: usize,
+    steps: usize,
+}
+
+impl CyclicLR {
+    pub fn new(base_lr: f64, max_lr: f64, cycle_length: usize) -> Self {
+        Self {
+            base_lr,
+            max_lr,
+            cycle_length: cycle_length.max(1),
+            steps: 0,
+        }
+    }
+}
+
+impl LrScheduler for CyclicLR {
+    fn step(&mut self, optimizer: &mut dyn Optimizer) {
+        self.steps += 1;
+
+        let position = (self.steps % self.cycle_length) as f64 / self.cycle_length as f64;
+        let height = 1.0 - (2.0 * position - 1.0).abs();
+
+        optimizer.set_learning_rate(self.base_lr + (self.max_lr - self.base_lr) * height);
+    }
+}
+
+/// Linearly ramps the learning rate up to the optimizer's initial rate over the
+/// first `warmup_steps` steps, then hands over to the `inner` scheduler.
+///
+/// Takes the optimizer on construction, so that the very first steps already
+/// run at the reduced rate.
+#[derive(Debug)]
+pub struct Warmup<S> {
+    warmup_steps: usize,
+    inner: S,
+    base_lr: f64,
+    steps: usize,
+}
+
+impl<S: LrScheduler> Warmup<S> {
+    pub fn new(warmup_steps: usize, inner: S, optimizer: &mut dyn Optimizer) -> Self {
+        let base_lr = optimizer.learning_rate();
+
+        if warmup_steps > 0 {
+            optimizer.set_learning_rate(base_lr / warmup_steps as f64);
+        }
+
+        Self {
+            warmup_steps,
+            inner,
+            base_lr,
+            steps: 0,
+        }
+    }
+}
+
+impl<S: LrScheduler> LrScheduler for Warmup<S> {
+    fn step(&mut self, optimizer: &mut dyn Optimizer) {
+        self.steps += 1;
+
+        if self.steps < self.warmup_steps {
+            let fraction = (self.steps + 1) as f64 / self.warmup_steps as f64;
+
+            optimizer.set_learning_rate(self.base_lr * fraction);
+        } else {
+            self.inner.step(optimizer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CosineAnnealingLR, CyclicLR, LrScheduler, StepLR, Warmup};
+    use crate::optim::{Optimizer, Sgd};
+
+    #[test]
+    fn step_lr() {
+        let mut sgd = Sgd::new(vec![], 1.0);
+        let mut scheduler = StepLR::new(2, 0.5);
+
+        let rates: Vec<_> = (0..6)
+            .map(|_| {
+                scheduler.step(&mut sgd);
+                sgd.learning_rate()
+            })
+            .collect();
+
+        assert_eq!(rates, vec![1.0, 0.5, 0.5, 0.25, 0.25, 0.125]);
+
+        let mut scheduler = StepLR::new(0, 0.5);
+        scheduler.step(&mut sgd);
+        scheduler.step(&mut sgd);
+        assert_eq!(sgd.learning_rate(), 0.125 / 4.0);
+    }
+
+    #[test]
+    fn cosine_annealing_lr() {
+        let mut sgd = Sgd::new(vec![], 1.0);
+        let mut scheduler = CosineAnnealingLR::new(4, 0.1);
+
+        let rates: Vec<_> = (0..6)
+            .map(|_| {
+                scheduler.step(&mut sgd);
+                sgd.learning_rate()
+            })
+            .collect();
+
+        let expected = [
+            0.1 + 0.9 * (1.0 + (std::f64::consts::PI / 4.0).cos()) / 2.0,
+            0.55,
+            0.1 + 0.9 * (1.0 + (3.0 * std::f64::consts::PI / 4.0).cos()) / 2.0,
+            0.1,
+            0.1,
+            0.1,
+        ];
+
+        for (rate, expected) in rates.iter().zip(expected) {
+            assert!((rate - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn cyclic_lr() {
+        let mut sgd = Sgd::new(vec![], 0.1);
+        let mut scheduler = CyclicLR::new(0.1, 1.1, 4);
+
+        let rates: Vec<_> = (0..8)
+            .map(|_| {
+                scheduler.step(&mut sgd);
+                sgd.learning_rate()
+            })
+            .collect();
+
+        let expected = [0.6, 1.1, 0.6, 0.1, 0.6, 1.1, 0.6, 0.1];
+        for (rate, expected) in rates.iter().zip(expected) {
+            assert!((rate - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn warmup() {
+        let mut sgd = Sgd::new(vec![], 1.0);
+        let mut scheduler = Warmup::new(4, StepLR::new(2, 0.5), &mut sgd);
+
+        let mut rates = vec![sgd.learning_rate()];
+        for _ in 0..6 {
+            scheduler.step(&mut sgd);
+            rates.push(sgd.learning_rate());
+        }
+
+        assert_eq!(rates, vec![0.25, 0.5, 0.75, 1.0, 1.0, 0.5, 0.5]);
+    }
+}
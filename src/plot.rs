@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use plotters::prelude::*;
+use thiserror::Error as ThisError;
+
+use crate::train::History;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("Nothing to plot, the history is empty")]
+    EmptyHistory,
+    #[error("Failed to draw plot, {0}")]
+    Drawing(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const SIZE: (u32, u32) = (800, 600);
+
+impl History {
+    /// Draws the loss curve, and below it the curves of all recorded metrics,
+    /// into a PNG image at `path`
+    pub fn plot<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if self.is_empty() {
+            return Err(Error::EmptyHistory);
+        }
+
+        let root = BitMapBackend::new(path.as_ref(), SIZE).into_drawing_area();
+        root.fill(&WHITE).map_err(drawing)?;
+
+        let names = self.metric_names();
+        let (loss_area, metrics_area) = if names.is_empty() {
+            (root.clone(), None)
+        } else {
+            let (top, bottom) = root.split_vertically(SIZE.1 / 2);
+            (top, Some(bottom))
+        };
+
+        let loss = self.epochs.iter().copied().zip(self.loss.iter().copied());
+        draw_curves(&loss_area, "Loss", vec![("loss", loss.collect())])?;
+
+        if let Some(area) = metrics_area {
+            let curves = names
+                .iter()
+                .map(|name| {
+                    let points = self
+                        .epochs
+                        .iter()
+                        .zip(self.metric(name))
+                        .filter_map(|(&epoch, value)| Some((epoch, value?)))
+                        .collect();
+
+                    (*name, points)
+                })
+                .collect();
+
+            draw_curves(&area, "Metrics", curves)?;
+        }
+
+        root.present().map_err(drawing)
+    }
+}
+
+fn draw_curves<DB: DrawingBackend>(
+    area: &DrawingArea<DB, plotters::coord::Shift>,
+    caption: &str,
+    curves: Vec<(&str, Vec<(usize, f64)>)>,
+) -> Result<()> {
+    let points = curves.iter().flat_map(|(_, points)| points);
+
+    let first = points.clone().map(|p| p.0).min().unwrap_or(0);
+    let last = points
+        .clone()
+        .map(|p| p.0)
+        .max()
+        .unwrap_or(0)
+        .max(first + 1);
+
+    let finite = points.map(|p| p.1).filter(|y| y.is_finite());
+    let low = finite.clone().fold(f64::INFINITY, f64::min);
+    let high = finite.fold(f64::NEG_INFINITY, f64::max);
+    let (low, high) = if low > high {
+        (0.0, 1.0)
+    } else {
+        let margin = ((high - low) * 0.05).max(1e-9);
+        (low - margin, high + margin)
+    };
+
+    let mut chart = ChartBuilder::on(area)
+        .caption(caption, ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(50)
+        .build_cartesian_2d(first..last, low..high)
+        .map_err(drawing)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("epoch")
+        .draw()
+        .map_err(drawing)?;
+
+    for (i, (name, points)) in curves.into_iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+
+        chart
+            .draw_series(LineSeries::new(points, color))
+            .map_err(drawing)?
+            .label(name)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(drawing)
+}
+
+fn drawing<E: std::error::Error>(err: E) -> Error {
+    Error::Drawing(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Error;
+    use crate::train::{callback::EpochLog, History};
+
+    #[test]
+    fn plots_history() {
+        let path = std::env::temp_dir().join(format!("micrograd-plot-{}.png", std::process::id()));
+
+        let mut history = History::default();
+        for epoch in 0..10 {
+            history.push(
+                &EpochLog {
+                    epoch,
+                    loss: 1.0 / (epoch + 1) as f64,
+                    metrics: BTreeMap::from([("accuracy".to_string(), epoch as f64 / 10.0)]),
+                    ..Default::default()
+                },
+                0.1,
+            );
+        }
+
+        history.plot(&path).expect("should plot");
+
+        let bytes = std::fs::read(&path).expect("should write image");
+        std::fs::remove_file(&path).expect("should clean up");
+
+        assert_eq!(&bytes[1..4], b"PNG");
+    }
+
+    #[test]
+    fn empty_history() {
+        let result = History::default().plot("unused.png");
+
+        assert!(matches!(result, Err(Error::EmptyHistory)));
+    }
+}
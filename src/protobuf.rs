@@ -0,0 +1,35 @@
+//! Minimal protobuf encoding of the few message fields written by the
+//! TensorBoard logger and the ONNX exporter
+
+pub(crate) fn encode_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+pub(crate) fn encode_key(buffer: &mut Vec<u8>, field: u64, wire_type: u64) {
+    encode_varint(buffer, (field << 3) | wire_type);
+}
+
+pub(crate) fn encode_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    encode_key(buffer, field, 0);
+    encode_varint(buffer, value);
+}
+
+pub(crate) fn encode_double(buffer: &mut Vec<u8>, field: u64, value: f64) {
+    encode_key(buffer, field, 1);
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn encode_bytes(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    encode_key(buffer, field, 2);
+    encode_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+pub(crate) fn encode_float(buffer: &mut Vec<u8>, field: u64, value: f32) {
+    encode_key(buffer, field, 5);
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
@@ -0,0 +1,357 @@
+//! Python bindings, built into the `micrograd_rs` extension module with
+//! `maturin build`, so the engine can be compared side by side with the
+//! original micrograd:
+//!
+//! ```python
+//! from micrograd_rs import MLP, Trainer
+//!
+//! trainer = Trainer(MLP(3, [4, 4, 1], seed=1), learning_rate=0.05)
+//! losses = trainer.fit([([2.0, 3.0, -1.0], [1.0])], epochs=100)
+//! ```
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rand::{thread_rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    data::InMemoryDataset,
+    loss::Loss,
+    nn::Mlp,
+    optim::{AdamW, Optimizer, Sgd},
+    train::{TrainConfig, Trainer},
+    value::Value,
+};
+
+fn value_error<E: ToString>(error: E) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+/// A scalar in the expression graph
+#[pyclass(name = "Value", unsendable)]
+#[derive(Clone)]
+struct PyValue(Value);
+
+/// Either a `Value` or a plain number, which becomes a constant
+#[derive(FromPyObject)]
+enum Operand {
+    Value(PyValue),
+    Number(f64),
+}
+
+impl From<Operand> for Value {
+    fn from(operand: Operand) -> Self {
+        match operand {
+            Operand::Value(value) => value.0,
+            Operand::Number(number) => Value::new(number, &number.to_string()),
+        }
+    }
+}
+
+#[pymethods]
+impl PyValue {
+    #[new]
+    #[pyo3(signature = (data, label = ""))]
+    fn new(data: f64, label: &str) -> Self {
+        Self(Value::new(data, label))
+    }
+
+    #[getter]
+    fn data(&self) -> f64 {
+        self.0.value()
+    }
+
+    #[setter]
+    fn set_data(&self, data: f64) {
+        self.0.set_value(data);
+    }
+
+    #[getter]
+    fn grad(&self) -> f64 {
+        self.0.gradient()
+    }
+
+    #[getter]
+    fn label(&self) -> String {
+        self.0.label()
+    }
+
+    fn __add__(&self, other: Operand) -> Self {
+        Self(self.0.clone() + other.into())
+    }
+
+    fn __radd__(&self, other: Operand) -> Self {
+        Self(Value::from(other) + self.0.clone())
+    }
+
+    fn __sub__(&self, other: Operand) -> Self {
+        Self(self.0.clone() - other.into())
+    }
+
+    fn __rsub__(&self, other: Operand) -> Self {
+        Self(Value::from(other) - self.0.clone())
+    }
+
+    fn __mul__(&self, other: Operand) -> Self {
+        Self(self.0.clone() * other.into())
+    }
+
+    fn __rmul__(&self, other: Operand) -> Self {
+        Self(Value::from(other) * self.0.clone())
+    }
+
+    fn __truediv__(&self, other: Operand) -> Self {
+        Self(self.0.clone() * Value::from(other).pow(-1.0))
+    }
+
+    fn __neg__(&self) -> Self {
+        Self(-self.0.clone())
+    }
+
+    fn __pow__(&self, exponent: f64, _modulo: Option<PyObject>) -> Self {
+        Self(self.0.clone().pow(exponent))
+    }
+
+    fn tanh(&self) -> Self {
+        Self(self.0.clone().tanh())
+    }
+
+    fn exp(&self) -> Self {
+        Self(self.0.clone().exp())
+    }
+
+    fn log(&self) -> Self {
+        Self(self.0.clone().ln())
+    }
+
+    fn sigmoid(&self) -> Self {
+        Self(self.0.clone().sigmoid())
+    }
+
+    fn backward(&self) {
+        self.0.backpropagate();
+    }
+
+    fn zero_grad(&self) {
+        self.0.zero_gradient();
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Value(data={}, grad={})", self.0.value(), self.0.gradient())
+    }
+}
+
+/// A multi-layer perceptron with tanh activations
+#[pyclass(name = "MLP", unsendable)]
+struct PyMlp(Option<Mlp>);
+
+impl PyMlp {
+    fn model(&self) -> PyResult<&Mlp> {
+        self.0
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("the model was moved into a Trainer"))
+    }
+}
+
+#[pymethods]
+impl PyMlp {
+    #[new]
+    #[pyo3(signature = (nin, nouts, seed = None))]
+    fn new(nin: usize, nouts: Vec<usize>, seed: Option<u64>) -> PyResult<Self> {
+        let mlp = match seed {
+            Some(seed) => Mlp::try_new(nin, &nouts, &mut ChaCha8Rng::seed_from_u64(seed)),
+            None => Mlp::try_new(nin, &nouts, &mut thread_rng()),
+        };
+
+        Ok(Self(Some(mlp.map_err(value_error)?)))
+    }
+
+    fn __call__(&self, x: Vec<Operand>) -> PyResult<Vec<PyValue>> {
+        let x: Vec<Value> = x.into_iter().map(Value::from).collect();
+        let outputs = self.model()?.predict(&x).map_err(value_error)?;
+
+        Ok(outputs.into_iter().map(PyValue).collect())
+    }
+
+    fn parameters(&self) -> PyResult<Vec<PyValue>> {
+        Ok(self
+            .model()?
+            .parameters()
+            .into_iter()
+            .map(PyValue)
+            .collect())
+    }
+}
+
+fn parameters(parameters: Vec<PyValue>) -> Vec<Value> {
+    parameters.into_iter().map(|p| p.0).collect()
+}
+
+/// Stochastic gradient descent over a list of values
+#[pyclass(name = "SGD", unsendable)]
+struct PySgd(Sgd);
+
+#[pymethods]
+impl PySgd {
+    #[new]
+    fn new(params: Vec<PyValue>, lr: f64) -> Self {
+        Self(Sgd::new(parameters(params), lr))
+    }
+
+    fn step(&mut self) {
+        self.0.step();
+    }
+
+    fn zero_grad(&mut self) {
+        self.0.zero_grad();
+    }
+}
+
+/// AdamW over a list of values, with the default betas
+#[pyclass(name = "AdamW", unsendable)]
+struct PyAdamW(AdamW);
+
+#[pymethods]
+impl PyAdamW {
+    #[new]
+    #[pyo3(signature = (params, lr, weight_decay = 0.0))]
+    fn new(params: Vec<PyValue>, lr: f64, weight_decay: f64) -> Self {
+        Self(AdamW::new(parameters(params), lr, weight_decay))
+    }
+
+    fn step(&mut self) {
+        self.0.step();
+    }
+
+    fn zero_grad(&mut self) {
+        self.0.zero_grad();
+    }
+}
+
+/// Trains an `MLP`, which is moved into the trainer. `optimizer` is `"sgd"` or
+/// `"adamw"`, the latter without weight decay.
+#[pyclass(name = "Trainer", unsendable)]
+struct PyTrainer(Trainer);
+
+#[pymethods]
+impl PyTrainer {
+    #[new]
+    #[pyo3(signature = (model, optimizer = "sgd", learning_rate = 0.05, loss = "squared_error", batch_size = None, seed = None))]
+    fn new(
+        mut model: PyRefMut<PyMlp>,
+        optimizer: &str,
+        learning_rate: f64,
+        loss: &str,
+        batch_size: Option<usize>,
+        seed: Option<u64>,
+    ) -> PyResult<Self> {
+        let loss = match loss {
+            "squared_error" => Loss::SquaredError,
+            "binary_cross_entropy" => Loss::BinaryCrossEntropy {
+                class_weights: None,
+            },
+            "cross_entropy" => Loss::CrossEntropy {
+                class_weights: None,
+            },
+            other => return Err(value_error(format!("unknown loss {other}"))),
+        };
+
+        model.model()?;
+        let mlp = model.0.take().expect("model should be present");
+        let parameters = mlp.parameters();
+
+        let trainer = match optimizer {
+            "sgd" => Trainer::new(mlp, Sgd::new(parameters, learning_rate), loss),
+            "adamw" => Trainer::new(mlp, AdamW::new(parameters, learning_rate, 0.0), loss),
+            other => {
+                model.0 = Some(mlp);
+                return Err(value_error(format!("unknown optimizer {other}")));
+            }
+        };
+
+        Ok(Self(trainer.config(TrainConfig {
+            batch_size,
+            shuffle: batch_size.is_some(),
+            seed,
+            ..Default::default()
+        })))
+    }
+
+    /// Trains on `(input, target)` pairs, returning the loss of every epoch
+    fn fit(&mut self, data: Vec<(Vec<f64>, Vec<f64>)>, epochs: usize) -> PyResult<Vec<f64>> {
+        let history = self
+            .0
+            .fit(&InMemoryDataset::from(data), epochs)
+            .map_err(value_error)?;
+
+        Ok(history.loss)
+    }
+
+    fn predict(&self, x: Vec<f64>) -> PyResult<Vec<f64>> {
+        self.0.model().infer(&x).map_err(value_error)
+    }
+
+    #[getter]
+    fn epoch(&self) -> usize {
+        self.0.epoch()
+    }
+}
+
+#[pymodule]
+fn micrograd_rs(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyValue>()?;
+    module.add_class::<PyMlp>()?;
+    module.add_class::<PySgd>()?;
+    module.add_class::<PyAdamW>()?;
+    module.add_class::<PyTrainer>()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::{prelude::*, types::IntoPyDict};
+
+    use super::micrograd_rs;
+
+    #[test]
+    fn python_api() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| -> PyResult<()> {
+            let module = PyModule::new(py, "micrograd_rs")?;
+            micrograd_rs(&module)?;
+            let locals = [("mg", module)].into_py_dict(py)?;
+
+            py.run(
+                cr#"
+a = mg.Value(2.0, "a")
+b = mg.Value(-3.0, "b")
+c = (a * b + 1) ** 2 / 5
+c.backward()
+assert c.data == 5.0
+assert a.grad == 2 * -5 * -3 / 5
+
+mlp = mg.MLP(3, [4, 1], seed=1)
+assert len(mlp.parameters()) == 21
+assert -1 < mlp([1.0, a, 0.5])[0].data < 1
+
+data = [([2.0, 3.0, -1.0], [1.0]), ([3.0, -1.0, 0.5], [-1.0])]
+trainer = mg.Trainer(mlp, learning_rate=0.05)
+losses = trainer.fit(data, 50)
+assert losses[-1] < losses[0]
+assert trainer.epoch == 50
+assert trainer.predict([2.0, 3.0, -1.0])[0] > 0
+
+try:
+    mlp.parameters()
+    assert False
+except ValueError:
+    pass
+"#,
+                None,
+                Some(&locals),
+            )
+        })
+        .expect("python code should run");
+    }
+}
@@ -0,0 +1,141 @@
+//! Policy-gradient reinforcement learning with REINFORCE.
+//!
+//! A policy, e.g. an `Mlp` scoring every action, turns logits into
+//! log-probabilities with [`loss::log_softmax`](crate::loss::log_softmax).
+//! An episode samples actions with [`sample_action`], keeps the
+//! log-probability of every chosen action and collects the rewards, then
+//! [`reinforce`] updates the policy towards actions followed by high returns.
+
+use rand::Rng;
+use thiserror::Error as ThisError;
+
+use crate::{optim::Optimizer, value::Value};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("Step count mismatch, got {0} log-probabilities and {1} returns")]
+    StepMismatch(usize, usize),
+    #[error("Empty episode")]
+    EmptyEpisode,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Returns of every step of an episode, the rewards from that step on,
+/// discounted by `gamma` per step
+pub fn discounted_returns(rewards: &[f64], gamma: f64) -> Vec<f64> {
+    let mut returns = vec![0.0; rewards.len()];
+    let mut future = 0.0;
+
+    for (step, reward) in rewards.iter().enumerate().rev() {
+        future = reward + gamma * future;
+        returns[step] = future;
+    }
+
+    returns
+}
+
+/// Returns shifted to a mean of 0 and scaled to a standard deviation of 1, a
+/// simple baseline which reduces the variance of the gradients
+pub fn normalize(returns: &[f64]) -> Vec<f64> {
+    let count = returns.len().max(1) as f64;
+    let mean = returns.iter().sum::<f64>() / count;
+    let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / count).sqrt();
+
+    returns
+        .iter()
+        .map(|r| (r - mean) / std.max(f64::EPSILON))
+        .collect()
+}
+
+/// The policy-gradient loss `-Σ log π(a_t) G_t` of the log-probabilities of
+/// the chosen actions and the returns following them
+pub fn reinforce_loss(log_probs: &[Value], returns: &[f64]) -> Result<Value> {
+    if log_probs.len() != returns.len() {
+        return Err(Error::StepMismatch(log_probs.len(), returns.len()));
+    }
+
+    if log_probs.is_empty() {
+        return Err(Error::EmptyEpisode);
+    }
+
+    Ok(log_probs
+        .iter()
+        .zip(returns)
+        .fold(Value::new(0.0, "0"), |sum, (log_p, g)| {
+            sum + log_p.clone() * Value::new(-g, "-G")
+        }))
+}
+
+/// Backpropagates the [`reinforce_loss`] of an episode and steps the
+/// optimizer of the policy parameters, returning the loss
+pub fn reinforce<O: Optimizer + ?Sized>(
+    optimizer: &mut O,
+    log_probs: &[Value],
+    returns: &[f64],
+) -> Result<f64> {
+    let loss = reinforce_loss(log_probs, returns)?;
+
+    optimizer.zero_grad();
+    loss.backpropagate();
+    optimizer.step();
+
+    Ok(loss.value())
+}
+
+/// Samples an action from the log-probabilities of a policy
+pub fn sample_action<R: Rng + ?Sized>(log_probs: &[Value], rng: &mut R) -> usize {
+    let mut threshold: f64 = rng.gen();
+
+    for (action, log_p) in log_probs.iter().enumerate() {
+        threshold -= log_p.value().exp();
+
+        if threshold < 0.0 {
+            return action;
+        }
+    }
+
+    // rounding can leave a sliver of probability unassigned
+    log_probs.len().saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{discounted_returns, normalize, reinforce, reinforce_loss, sample_action, Error};
+    use crate::{loss::log_softmax, optim::Sgd, value::Value};
+
+    #[test]
+    fn returns() {
+        assert_eq!(discounted_returns(&[1.0, 0.0, 2.0], 0.5), [1.5, 1.0, 2.0]);
+
+        let normalized = normalize(&[1.0, 2.0, 3.0]);
+        assert!(normalized.iter().sum::<f64>().abs() < 1e-12);
+        assert!(normalized[2] > normalized[0]);
+
+        assert!(matches!(
+            reinforce_loss(&[Value::new(0.0, "log_p")], &[]),
+            Err(Error::StepMismatch(1, 0))
+        ));
+    }
+
+    #[test]
+    fn two_armed_bandit() {
+        let logits = [Value::new(0.0, "left"), Value::new(0.0, "right")];
+        let mut sgd = Sgd::new(logits.to_vec(), 0.1);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        for _ in 0..200 {
+            let log_probs = log_softmax(&logits);
+            let action = sample_action(&log_probs, &mut rng);
+            let reward = if action == 1 { 1.0 } else { 0.0 };
+
+            reinforce(&mut sgd, &[log_probs[action].clone()], &[reward]).expect("should update");
+        }
+
+        let right = log_softmax(&logits)[1].value().exp();
+        assert!(right > 0.9, "right arm probability {right}");
+    }
+}
@@ -0,0 +1,243 @@
+//! A minimal HTTP server answering predictions of a trained model, to demo it
+//! as a service:
+//!
+//! ```no_run
+//! micrograd::serve::serve("model.json", "127.0.0.1:8080")?;
+//! # Ok::<(), micrograd::serve::Error>(())
+//! ```
+//!
+//! `POST /predict` takes a JSON feature vector, or a batch of them, and
+//! answers with the model outputs in the same shape:
+//!
+//! ```sh
+//! curl -d '{"inputs": [2.0, 3.0, -1.0]}' localhost:8080/predict
+//! {"outputs":[0.731...]}
+//! ```
+//!
+//! Requests are handled one at a time, this is not a production server.
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
+
+use crate::nn::{self, Mlp};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Model(#[from] nn::json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Largest accepted request body
+const MAX_BODY: usize = 1 << 20;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Inputs {
+    Single(Vec<f64>),
+    Batch(Vec<Vec<f64>>),
+}
+
+#[derive(Deserialize)]
+struct PredictRequest {
+    inputs: Inputs,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Outputs {
+    Single(Vec<f64>),
+    Batch(Vec<Vec<f64>>),
+}
+
+#[derive(Serialize)]
+struct PredictResponse {
+    outputs: Outputs,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Loads a model saved with `Mlp::to_json` and serves it on `addr` until the
+/// process is stopped
+pub fn serve<P: AsRef<Path>, A: ToSocketAddrs>(model: P, addr: A) -> Result<()> {
+    let mlp = Mlp::from_json(&fs::read_to_string(model)?)?;
+
+    serve_model(&mlp, TcpListener::bind(addr)?)
+}
+
+/// Serves `mlp` on connections accepted by `listener`, e.g. one bound to
+/// port 0 to let the OS pick a free port
+pub fn serve_model(mlp: &Mlp, listener: TcpListener) -> Result<()> {
+    for stream in listener.incoming() {
+        // a misbehaving client shouldn't bring the server down
+        let _ = handle(mlp, stream?);
+    }
+
+    Ok(())
+}
+
+fn handle(mlp: &Mlp, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let (status, body) = match (method, path) {
+        (_, "/predict") if content_length > MAX_BODY => (
+            413,
+            error(format!("bodies are limited to {MAX_BODY} bytes")),
+        ),
+        ("POST", "/predict") => {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+
+            match predict(mlp, &body) {
+                Ok(outputs) => (200, json(&PredictResponse { outputs })),
+                Err(message) => (400, error(message)),
+            }
+        }
+        (_, "/predict") => (405, error("use POST".to_string())),
+        _ => (404, error(format!("no such endpoint {path:?}"))),
+    };
+
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Payload Too Large",
+    };
+
+    write!(
+        &stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+
+    (&stream).flush()
+}
+
+fn predict(mlp: &Mlp, body: &[u8]) -> std::result::Result<Outputs, String> {
+    let request: PredictRequest =
+        serde_json::from_slice(body).map_err(|err| format!("invalid request, {err}"))?;
+
+    let outputs = match request.inputs {
+        Inputs::Single(x) => Outputs::Single(mlp.infer(&x).map_err(|err| err.to_string())?),
+        Inputs::Batch(xs) => Outputs::Batch(
+            xs.iter()
+                .map(|x| mlp.infer(x))
+                .collect::<nn::Result<_>>()
+                .map_err(|err| err.to_string())?,
+        ),
+    };
+
+    Ok(outputs)
+}
+
+fn json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("responses should serialize")
+}
+
+fn error(error: String) -> String {
+    json(&ErrorResponse { error })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use super::serve_model;
+    use crate::nn::Mlp;
+
+    fn request(addr: &str, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).expect("should connect");
+        stream.write_all(request.as_bytes()).expect("should send");
+
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("should receive");
+
+        response
+    }
+
+    fn post(addr: &str, body: &str) -> String {
+        request(
+            addr,
+            &format!(
+                "POST /predict HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            ),
+        )
+    }
+
+    #[test]
+    fn predict() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("should bind");
+        let addr = listener
+            .local_addr()
+            .expect("should have an address")
+            .to_string();
+        let json = Mlp::new_seeded(2, &[3, 1], 0).to_json();
+
+        // models aren't Send, so the server builds its own
+        thread::spawn(move || {
+            let mlp = Mlp::from_json(&json).expect("should load");
+            serve_model(&mlp, listener)
+        });
+
+        let expected = Mlp::new_seeded(2, &[3, 1], 0)
+            .infer(&[0.5, -1.0])
+            .expect("should infer");
+
+        let single = post(&addr, r#"{"inputs": [0.5, -1.0]}"#);
+        assert!(single.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(single.ends_with(&format!(r#"{{"outputs":[{:?}]}}"#, expected[0])));
+
+        let batch = post(&addr, r#"{"inputs": [[0.5, -1.0], [0.5, -1.0]]}"#);
+        assert!(batch.ends_with(&format!(r#"{{"outputs":[[{0:?}],[{0:?}]]}}"#, expected[0])));
+
+        let mismatch = post(&addr, r#"{"inputs": [1.0]}"#);
+        assert!(mismatch.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(mismatch.contains("expected 2 inputs, got 1"));
+
+        let get = request(&addr, "GET /predict HTTP/1.1\r\n\r\n");
+        assert!(get.starts_with("HTTP/1.1 405 "));
+
+        let missing = request(&addr, "POST /train HTTP/1.1\r\n\r\n");
+        assert!(missing.starts_with("HTTP/1.1 404 "));
+    }
+}
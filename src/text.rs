@@ -0,0 +1,49 @@
+//! Text preprocessing for language models: a character-level vocabulary with
+//! special tokens, turning words into token ids for an [`Embedding`] and the
+//! tokens following them into one-hot targets for [`cross_entropy`].
+//!
+//! The tokenizer is the one the models of [`lm`](crate::lm) are trained with.
+//!
+//! [`Embedding`]: crate::nn::Embedding
+//! [`cross_entropy`]: crate::loss::cross_entropy
+
+pub use crate::lm::{contexts, Error, Result, Tokenizer, UNKNOWN};
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{contexts, Tokenizer, UNKNOWN};
+    use crate::{
+        loss::cross_entropy,
+        nn::{Embedding, Mlp},
+    };
+
+    #[test]
+    fn feeds_a_model() {
+        let tokenizer = Tokenizer::fit(&["abab"]).with_specials(&[UNKNOWN]);
+        let examples = contexts(&tokenizer, &["abab", "abc"], 2).expect("should build");
+
+        let embedding =
+            Embedding::new(tokenizer.vocab_size(), 3, &mut ChaCha8Rng::seed_from_u64(1));
+        let mlp = Mlp::new_seeded(6, &[tokenizer.vocab_size()], 1);
+
+        let (targets, logits): (Vec<_>, Vec<_>) = examples
+            .iter()
+            .map(|(context, next)| {
+                let x = embedding.lookup_all(context).expect("should embed");
+
+                (
+                    tokenizer.one_hot(*next).expect("should encode"),
+                    mlp.predict(&x).expect("should predict"),
+                )
+            })
+            .unzip();
+
+        let loss = cross_entropy(&targets, &logits, None, None).expect("should compute");
+        loss.backpropagate();
+        assert!(loss.value() > 0.0);
+        assert!(embedding.parameters().iter().any(|p| p.gradient() != 0.0));
+    }
+}
@@ -0,0 +1,1286 @@
+pub mod callback;
+pub mod distill;
+pub mod gan;
+mod history;
+#[cfg(feature = "json")]
+pub mod manifest;
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "progress")]
+pub mod progress;
+pub mod tensorboard;
+
+#[cfg(feature = "json")]
+use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    io,
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error as ThisError;
+
+use callback::{BatchLog, Callback, EpochLog, TrainState};
+pub use history::History;
+
+use crate::{
+    checkpoint::{self, Checkpoint},
+    data::{
+        transform::{Phase, Pipeline, Sample, Transformed},
+        Batch, DataLoader, Dataset, Subset,
+    },
+    loss::{self, Loss, Reduction},
+    metrics::Metric,
+    nn::{self, GradientFlow, Mlp},
+    optim::{self, grad_norm, scheduler::LrScheduler, Optimizer},
+    value::Value,
+};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Model(#[from] nn::Error),
+    #[error(transparent)]
+    Loss(#[from] loss::Error),
+    #[error("Cannot split {1} samples into {0} folds")]
+    InvalidFolds(usize, usize),
+    #[error(transparent)]
+    Checkpoint(#[from] checkpoint::Error),
+    #[error(transparent)]
+    Optimizer(#[from] optim::Error),
+    #[error("Checkpoint architecture {1:?} doesn't match the model's {0:?}")]
+    ArchitectureMismatch(Vec<usize>, Vec<usize>),
+    #[error("Cannot train on an empty batch")]
+    EmptyBatch,
+    #[error("Failed to write the run manifest: {0}")]
+    Manifest(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Settings of a training run
+#[derive(Debug, Clone, Default)]
+pub struct TrainConfig {
+    /// Print the loss after every epoch. With the `tracing` feature, epochs and
+    /// batches are always reported as `tracing` events instead.
+    pub verbose: bool,
+    /// Per-sample weights passed to the loss
+    pub sample_weights: Option<Vec<f64>>,
+    /// Number of samples per optimizer step, the whole dataset when `None`
+    pub batch_size: Option<usize>,
+    /// Shuffle the samples every epoch
+    pub shuffle: bool,
+    /// Seed of the randomness used during training, currently the shuffling
+    /// order. Training is reproducible when set, together with a model built
+    /// by `Mlp::new_seeded`. A random seed is picked for every trainer otherwise.
+    pub seed: Option<u64>,
+    /// Whether the loss of each batch is summed or averaged over its samples
+    pub reduction: Reduction,
+    /// Metrics evaluated on the training data at the end of every epoch
+    pub metrics: Vec<Metric>,
+    /// Number of buckets of the weight histograms passed to callbacks in
+    /// `EpochLog::parameter_stats`, no histograms when `None`
+    pub histogram_buckets: Option<usize>,
+}
+
+/// Owns a model together with everything needed to train it
+pub struct Trainer {
+    model: Mlp,
+    optimizer: Box<dyn Optimizer>,
+    scheduler: Option<Box<dyn LrScheduler>>,
+    loss: Loss,
+    config: TrainConfig,
+    callbacks: Vec<Box<dyn Callback>>,
+    epoch: usize,
+    history: History,
+    seed: u64,
+    transform: Option<Rc<Pipeline>>,
+    online_rng: Option<ChaCha8Rng>,
+    elapsed: Duration,
+    #[cfg(feature = "json")]
+    manifest: Option<PathBuf>,
+    #[cfg(feature = "parallel")]
+    workers: Option<parallel::Workers>,
+}
+
+impl Trainer {
+    /// The optimizer is expected to hold the model's parameters, e.g.
+    /// `Sgd::new(model.parameters(), 0.05)`
+    pub fn new<O: Optimizer + 'static>(model: Mlp, optimizer: O, loss: Loss) -> Self {
+        Self {
+            model,
+            optimizer: Box::new(optimizer),
+            scheduler: None,
+            loss,
+            config: TrainConfig::default(),
+            callbacks: Vec::new(),
+            epoch: 0,
+            history: History::default(),
+            seed: rand::random(),
+            transform: None,
+            online_rng: None,
+            elapsed: Duration::ZERO,
+            #[cfg(feature = "json")]
+            manifest: None,
+            #[cfg(feature = "parallel")]
+            workers: None,
+        }
+    }
+
+    pub fn config(mut self, config: TrainConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Learning rate schedule, stepped at the end of every epoch
+    pub fn scheduler<S: LrScheduler + 'static>(mut self, scheduler: S) -> Self {
+        self.scheduler = Some(Box::new(scheduler));
+        self
+    }
+
+    /// Passes every sample through `pipeline`, including its augmentations
+    /// while training, and without them when computing losses and metrics
+    pub fn transform(mut self, pipeline: Pipeline) -> Self {
+        self.transform = Some(Rc::new(pipeline));
+        self
+    }
+
+    /// Writes a [`manifest::RunManifest`] of the run to `path` at the end of
+    /// every `fit`, e.g. next to the checkpoints of a `ModelCheckpoint`
+    #[cfg(feature = "json")]
+    pub fn manifest<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.manifest = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Registers a callback notified of training events, in registration order
+    pub fn callback<C: Callback + 'static>(mut self, callback: C) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    pub fn model(&self) -> &Mlp {
+        &self.model
+    }
+
+    pub fn into_model(self) -> Mlp {
+        self.model
+    }
+
+    /// Number of epochs completed so far, across calls to `fit`
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// Epochs trained so far, across calls to `fit`
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Snapshot of the model, optimizer and epoch counter
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.epoch, &self.model, self.optimizer.state())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(self.checkpoint().save(path)?)
+    }
+
+    /// Restores the model weights, optimizer state and epoch counter from a
+    /// checkpoint file, so that the next `fit` continues where the checkpointed
+    /// run left off.
+    ///
+    /// The trainer has to be set up the same way as the original one (model
+    /// architecture, optimizer kind, scheduler and config, including the seed
+    /// when shuffling). The scheduler is fast-forwarded to the checkpointed epoch
+    /// and shuffling resumes with the order of that epoch.
+    pub fn resume<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let checkpoint = Checkpoint::load(path)?;
+
+        if checkpoint.layer_sizes != self.model.layer_sizes() {
+            return Err(Error::ArchitectureMismatch(
+                self.model.layer_sizes(),
+                checkpoint.layer_sizes,
+            ));
+        }
+
+        if let Some(scheduler) = &mut self.scheduler {
+            for _ in 0..checkpoint.epoch {
+                scheduler.step(self.optimizer.as_mut());
+            }
+        }
+
+        self.optimizer.load_state(checkpoint.optimizer)?;
+        self.model.load_parameters(&checkpoint.parameters)?;
+        self.epoch = checkpoint.epoch;
+
+        Ok(())
+    }
+
+    /// Trains the model on a dataset, stepping the optimizer once per batch of
+    /// `config.batch_size` samples.
+    ///
+    /// The model is in training mode for the batches of every epoch and in
+    /// evaluation mode for the metrics and callbacks, which it's left in.
+    ///
+    /// Returns the history of this call, which is also appended to [`Trainer::history`].
+    pub fn fit<D: Dataset + ?Sized>(&mut self, train_data: &D, epochs: usize) -> Result<History> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("fit", start_epoch = self.epoch, epochs).entered();
+
+        if let Some(weights) = &self.config.sample_weights {
+            if weights.len() != train_data.len() {
+                return Err(loss::Error::WeightMismatch(train_data.len(), weights.len()).into());
+            }
+        }
+
+        let batch_size = self.config.batch_size.unwrap_or(train_data.len());
+        let seed = self.config.seed.unwrap_or(self.seed);
+        let transform = self.transform.clone();
+        let mut loader = DataLoader::new(train_data, batch_size);
+
+        if self.config.shuffle {
+            loader = loader.shuffle(seed);
+        }
+
+        if let Some(pipeline) = &transform {
+            // augmentations draw from a stream independent of the shuffling
+            loader = loader.transform(pipeline, seed.wrapping_add(1));
+        }
+
+        // resumed runs continue with the shuffling order and augmentations of their next epoch
+        loader.skip_epochs(self.epoch);
+
+        let mut history = History::default();
+        let started = Instant::now();
+
+        let end_epoch = self.epoch + epochs;
+
+        for epoch in self.epoch..end_epoch {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("epoch", epoch).entered();
+
+            let state = TrainState {
+                epoch,
+                end_epoch,
+                batches: loader.len(),
+                reduction: self.config.reduction,
+                model: &self.model,
+                optimizer: self.optimizer.as_ref(),
+            };
+
+            for callback in &mut self.callbacks {
+                callback.on_epoch_start(&state);
+            }
+
+            let learning_rate = self.optimizer.learning_rate();
+
+            self.model.train();
+            let mut log = self.train_epoch(epoch, end_epoch, &mut loader)?;
+            self.model.eval();
+
+            if !self.config.metrics.is_empty() {
+                log.metrics = self.evaluate(train_data)?;
+            }
+
+            self.report(&log);
+
+            let state = TrainState {
+                epoch,
+                end_epoch,
+                batches: loader.len(),
+                reduction: self.config.reduction,
+                model: &self.model,
+                optimizer: self.optimizer.as_ref(),
+            };
+
+            for callback in &mut self.callbacks {
+                callback.on_epoch_end(&log, &state);
+            }
+
+            history.push(&log, learning_rate);
+            self.epoch += 1;
+        }
+
+        let state = TrainState {
+            epoch: self.epoch.saturating_sub(1),
+            end_epoch,
+            batches: loader.len(),
+            reduction: self.config.reduction,
+            model: &self.model,
+            optimizer: self.optimizer.as_ref(),
+        };
+
+        for callback in &mut self.callbacks {
+            callback.on_train_end(&history, &state);
+        }
+
+        self.history.extend(&history);
+        self.elapsed += started.elapsed();
+
+        #[cfg(feature = "json")]
+        if let Some(path) = &self.manifest {
+            self.run_manifest(train_data)
+                .save(path)
+                .map_err(Error::Manifest)?;
+        }
+
+        Ok(history)
+    }
+
+    /// Takes a single optimizer step on samples arriving one at a time or in
+    /// small batches, e.g. from a stream, keeping the optimizer state between
+    /// calls.
+    ///
+    /// The samples go through the training phase of the transform pipeline.
+    /// Sample weights, the scheduler, metrics and callbacks only apply to
+    /// `fit`, and the epoch counter and history are left untouched.
+    pub fn partial_fit(&mut self, samples: &[Sample]) -> Result<TrainStep> {
+        if samples.is_empty() {
+            return Err(Error::EmptyBatch);
+        }
+
+        let samples: Vec<_> = match &self.transform {
+            Some(pipeline) => {
+                let seed = self.config.seed.unwrap_or(self.seed);
+                let rng = self
+                    .online_rng
+                    .get_or_insert_with(|| ChaCha8Rng::seed_from_u64(seed.wrapping_add(2)));
+
+                samples
+                    .iter()
+                    .map(|sample| pipeline.apply(sample.clone(), Phase::Train, rng))
+                    .collect()
+            }
+            None => samples.to_vec(),
+        };
+
+        let indices = (0..samples.len()).collect();
+        let (inputs, targets) = samples.into_iter().unzip();
+        let batch = Batch {
+            indices,
+            inputs,
+            targets,
+        };
+
+        self.model.train();
+        self.train_step(&batch, None)
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    fn report(&self, log: &EpochLog) {
+        if self.config.verbose {
+            let metrics: String = log
+                .metrics
+                .iter()
+                .map(|(name, value)| format!(", {name} {value}"))
+                .collect();
+
+            println!("Epoch {}, loss {}{metrics}", log.epoch, log.loss);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn report(&self, log: &EpochLog) {
+        tracing::info!(
+            epoch = log.epoch,
+            loss = log.loss,
+            grad_norm = log.grad_norm,
+            metrics = ?log.metrics,
+            "epoch finished"
+        );
+    }
+
+    /// Describes the run so far, with `data` as the training data
+    #[cfg(feature = "json")]
+    pub fn run_manifest<D: Dataset + ?Sized>(&self, data: &D) -> manifest::RunManifest {
+        let config = manifest::RunConfig {
+            layer_sizes: self.model.layer_sizes(),
+            activation: self.model.activation().name().to_string(),
+            loss: format!("{:?}", self.loss),
+            learning_rate: self.optimizer.learning_rate(),
+            batch_size: self.config.batch_size,
+            shuffle: self.config.shuffle,
+            reduction: format!("{:?}", self.config.reduction).to_lowercase(),
+            metrics: self
+                .config
+                .metrics
+                .iter()
+                .map(|metric| metric.name().to_string())
+                .collect(),
+        };
+
+        manifest::RunManifest {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            seed: self.config.seed.unwrap_or(self.seed),
+            config,
+            dataset: manifest::DatasetFingerprint::of(data),
+            epochs: self.epoch,
+            final_loss: self.history.loss.last().copied(),
+            final_metrics: self.history.metrics.last().cloned().unwrap_or_default(),
+            wall_time_secs: self.elapsed.as_secs_f64(),
+        }
+    }
+
+    /// Loss of the current model on a dataset, reduced as configured
+    pub fn compute_loss<D: Dataset + ?Sized>(&self, data: &D) -> Result<f64> {
+        match &self.transform {
+            Some(pipeline) => self.loss_of(&Transformed::new(data, pipeline)),
+            None => self.loss_of(data),
+        }
+    }
+
+    fn loss_of<D: Dataset + ?Sized>(&self, data: &D) -> Result<f64> {
+        let (xs, targets): (Vec<_>, Vec<_>) = (0..data.len()).map(|i| data.get(i)).unzip();
+        let ypred = xs
+            .iter()
+            .map(|x| self.model.predict(&inputs(x)))
+            .collect::<nn::Result<Vec<_>>>()?;
+
+        let loss = self.loss.compute(&targets, &ypred, None)?.value();
+
+        Ok(match self.config.reduction {
+            Reduction::Sum => loss,
+            Reduction::Mean => loss / data.len().max(1) as f64,
+        })
+    }
+
+    /// Computes the configured metrics of the current model on a dataset
+    pub fn evaluate<D: Dataset + ?Sized>(&self, data: &D) -> Result<BTreeMap<String, f64>> {
+        let (preds, targets) = match &self.transform {
+            Some(pipeline) => predict(&self.model, &Transformed::new(data, pipeline))?,
+            None => predict(&self.model, data)?,
+        };
+
+        Ok(self
+            .config
+            .metrics
+            .iter()
+            .map(|metric| (metric.name().to_string(), metric.compute(&preds, &targets)))
+            .collect())
+    }
+
+    fn train_step(&mut self, batch: &Batch, weights: Option<&[f64]>) -> Result<TrainStep> {
+        #[cfg(feature = "parallel")]
+        if let Some(workers) = &self.workers {
+            return workers.train_batch(
+                &self.model,
+                self.optimizer.as_mut(),
+                batch,
+                weights,
+                self.config.reduction,
+            );
+        }
+
+        train_batch(
+            &self.model,
+            self.optimizer.as_mut(),
+            &self.loss,
+            batch,
+            weights,
+            self.config.reduction,
+        )
+    }
+
+    fn train_epoch<D: Dataset + ?Sized>(
+        &mut self,
+        epoch: usize,
+        end_epoch: usize,
+        loader: &mut DataLoader<D>,
+    ) -> Result<EpochLog> {
+        let batches = loader.len();
+        let mut total = 0.0;
+        let mut samples = 0;
+        let mut grad_norm = 0.0;
+        let mut layer_grad_norms = vec![0.0; self.model.layer_sizes().len() - 1];
+
+        for (b, batch) in loader.epoch().enumerate() {
+            let weights: Option<Vec<_>> = self
+                .config
+                .sample_weights
+                .as_ref()
+                .map(|w| batch.indices.iter().map(|&i| w[i]).collect());
+
+            let step = self.train_step(&batch, weights.as_deref())?;
+            let loss = step.loss.value();
+
+            total += match self.config.reduction {
+                Reduction::Sum => loss,
+                Reduction::Mean => loss * batch.len() as f64,
+            };
+            samples += batch.len();
+
+            grad_norm += step.grad_norm / batches as f64;
+            for (mean, norm) in layer_grad_norms.iter_mut().zip(&step.layer_grad_norms) {
+                *mean += norm / batches as f64;
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                epoch,
+                batch = b,
+                loss,
+                grad_norm = step.grad_norm,
+                "batch finished"
+            );
+
+            let log = BatchLog {
+                batch: b,
+                samples: batch.len(),
+                loss,
+                grad_norm: step.grad_norm,
+                layer_grad_norms: step.layer_grad_norms,
+                gradient_flow: step.gradient_flow,
+            };
+
+            let state = TrainState {
+                epoch,
+                end_epoch,
+                batches,
+                reduction: self.config.reduction,
+                model: &self.model,
+                optimizer: self.optimizer.as_ref(),
+            };
+
+            for callback in &mut self.callbacks {
+                callback.on_batch_end(&log, &state);
+            }
+        }
+
+        if let Some(scheduler) = &mut self.scheduler {
+            scheduler.step(self.optimizer.as_mut());
+        }
+
+        Ok(EpochLog {
+            epoch,
+            loss: match self.config.reduction {
+                Reduction::Sum => total,
+                Reduction::Mean => total / samples.max(1) as f64,
+            },
+            metrics: BTreeMap::new(),
+            grad_norm,
+            layer_grad_norms,
+            parameter_stats: self
+                .model
+                .parameter_histograms(self.config.histogram_buckets.unwrap_or(0)),
+        })
+    }
+}
+
+/// Loss and metrics of a model on the held out fold of a cross-validation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FoldResult {
+    pub loss: f64,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// Results of every fold of a cross-validation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrossValidation {
+    pub folds: Vec<FoldResult>,
+}
+
+impl CrossValidation {
+    /// Mean loss and metrics across folds
+    pub fn mean(&self) -> FoldResult {
+        self.aggregate(|values| values.iter().sum::<f64>() / values.len().max(1) as f64)
+    }
+
+    /// Population standard deviation of loss and metrics across folds
+    pub fn std(&self) -> FoldResult {
+        self.aggregate(|values| {
+            let mean = values.iter().sum::<f64>() / values.len().max(1) as f64;
+
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len().max(1) as f64)
+                .sqrt()
+        })
+    }
+
+    fn aggregate(&self, f: impl Fn(&[f64]) -> f64) -> FoldResult {
+        let losses: Vec<_> = self.folds.iter().map(|fold| fold.loss).collect();
+        let names = self.folds.iter().flat_map(|fold| fold.metrics.keys());
+
+        FoldResult {
+            loss: f(&losses),
+            metrics: names
+                .map(|name| {
+                    let values: Vec<_> = self
+                        .folds
+                        .iter()
+                        .filter_map(|fold| fold.metrics.get(name).copied())
+                        .collect();
+
+                    (name.clone(), f(&values))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// K-fold cross-validation.
+///
+/// The dataset is split into `k` contiguous folds (shuffle it beforehand when
+/// its order isn't random). For every fold, `model_factory` builds a fresh
+/// trainer which is trained with `config` on the remaining folds for `epochs`
+/// epochs, then evaluated on the held out fold. Sample weights in `config`
+/// are given for the whole dataset, each fold trains with the weights of its
+/// training samples.
+pub fn cross_validate<F, D>(
+    mut model_factory: F,
+    dataset: &D,
+    k: usize,
+    config: &TrainConfig,
+    epochs: usize,
+) -> Result<CrossValidation>
+where
+    F: FnMut() -> Trainer,
+    D: Dataset + ?Sized,
+{
+    let n = dataset.len();
+
+    if k < 2 || k > n {
+        return Err(Error::InvalidFolds(k, n));
+    }
+
+    if let Some(weights) = &config.sample_weights {
+        if weights.len() != n {
+            return Err(loss::Error::WeightMismatch(n, weights.len()).into());
+        }
+    }
+
+    let folds = (0..k)
+        .map(|fold| {
+            let held_out = fold * n / k..(fold + 1) * n / k;
+
+            let (validation, train): (Vec<_>, Vec<_>) = (0..n).partition(|i| held_out.contains(i));
+            let config = TrainConfig {
+                sample_weights: config
+                    .sample_weights
+                    .as_ref()
+                    .map(|weights| train.iter().map(|&i| weights[i]).collect()),
+                ..config.clone()
+            };
+            let train = Subset::new(dataset, train);
+            let validation = Subset::new(dataset, validation);
+
+            let mut trainer = model_factory().config(config);
+            trainer.fit(&train, epochs)?;
+
+            Ok(FoldResult {
+                loss: trainer.compute_loss(&validation)?,
+                metrics: trainer.evaluate(&validation)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CrossValidation { folds })
+}
+
+/// Outcome of a single optimization step
+#[derive(Debug, Clone)]
+pub struct TrainStep {
+    /// Batch loss, reduced as requested
+    pub loss: Value,
+    /// Global L2 norm of the gradients before the step, i.e. before any clipping
+    pub grad_norm: f64,
+    /// L2 norm of the gradients of every layer before the step
+    pub layer_grad_norms: Vec<f64>,
+    /// Mean absolute gradient of every layer before the step
+    pub gradient_flow: GradientFlow,
+}
+
+/// Runs a single optimization step of `model` on one batch of samples.
+///
+/// Returns the batch loss, reduced according to `reduction`, along with the
+/// gradient norms the step was taken with.
+///
+/// With the `parallel` feature, the samples are split across threads, each
+/// running a replica of the model, and the loss is returned as a leaf value.
+pub fn train_batch(
+    model: &Mlp,
+    optimizer: &mut dyn Optimizer,
+    loss: &Loss,
+    batch: &Batch,
+    weights: Option<&[f64]>,
+    reduction: Reduction,
+) -> Result<TrainStep> {
+    #[cfg(feature = "parallel")]
+    let loss = parallel::backward(model, loss, batch, weights, reduction)?;
+    #[cfg(not(feature = "parallel"))]
+    let loss = backward(model, loss, batch, weights, reduction)?;
+
+    Ok(step(model, optimizer, loss))
+}
+
+// Measures the accumulated gradients and takes the optimizer step
+fn step(model: &Mlp, optimizer: &mut dyn Optimizer, loss: Value) -> TrainStep {
+    let layer_grad_norms: Vec<_> = model
+        .layer_parameters()
+        .iter()
+        .map(|parameters| grad_norm(parameters))
+        .collect();
+    let grad_norm = layer_grad_norms.iter().map(|n| n * n).sum::<f64>().sqrt();
+    let gradient_flow = model.gradient_flow();
+
+    optimizer.step();
+    optimizer.zero_grad();
+
+    TrainStep {
+        loss,
+        grad_norm,
+        layer_grad_norms,
+        gradient_flow,
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn backward(
+    model: &Mlp,
+    loss: &Loss,
+    batch: &Batch,
+    weights: Option<&[f64]>,
+    reduction: Reduction,
+) -> Result<Value> {
+    let ypred = batch
+        .inputs
+        .iter()
+        .map(|x| model.predict(&inputs(x)))
+        .collect::<nn::Result<Vec<_>>>()?;
+
+    let mut loss = loss.compute(&batch.targets, &ypred, weights)?;
+
+    if reduction == Reduction::Mean {
+        loss = loss * Value::new(1.0 / batch.len() as f64, "1/n");
+    }
+
+    loss.backpropagate();
+
+    Ok(loss)
+}
+
+/// Model outputs paired with the corresponding targets
+pub type Predictions = (Vec<Vec<f64>>, Vec<Vec<f64>>);
+
+/// Model outputs for every sample of a dataset, along with the dataset targets
+pub fn predict<D: Dataset + ?Sized>(model: &Mlp, data: &D) -> Result<Predictions> {
+    (0..data.len())
+        .map(|i| {
+            let (x, y) = data.get(i);
+
+            Ok((model.infer(&x)?, y))
+        })
+        .collect()
+}
+
+fn inputs(x: &[f64]) -> Vec<Value> {
+    x.iter()
+        .enumerate()
+        .map(|(i, x)| Value::new(*x, &format!("x_{}", i + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{
+        callback::{BatchLog, Callback, EpochLog, Mode, ModelCheckpoint, TrainState},
+        cross_validate, train_batch, Error, History, TrainConfig, Trainer,
+    };
+    use crate::{
+        checkpoint::Checkpoint,
+        data::{
+            transform::{GaussianNoise, Pipeline, Sample},
+            DataLoader, InMemoryDataset,
+        },
+        loss::{Loss, Reduction},
+        metrics::Metric,
+        nn::Mlp,
+        optim::{scheduler::StepLR, AdamW, Sgd},
+    };
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Callback for Recorder {
+        fn on_epoch_start(&mut self, state: &TrainState) {
+            self.events
+                .borrow_mut()
+                .push(format!("start {}", state.epoch));
+        }
+
+        fn on_batch_end(&mut self, log: &BatchLog, _state: &TrainState) {
+            self.events
+                .borrow_mut()
+                .push(format!("batch {}", log.batch));
+        }
+
+        fn on_epoch_end(&mut self, log: &EpochLog, _state: &TrainState) {
+            self.events.borrow_mut().push(format!("end {}", log.epoch));
+        }
+
+        fn on_train_end(&mut self, history: &History, _state: &TrainState) {
+            self.events
+                .borrow_mut()
+                .push(format!("done {}", history.loss.len()));
+        }
+    }
+
+    fn dataset() -> InMemoryDataset {
+        InMemoryDataset::from(data())
+    }
+
+    fn data() -> Vec<(Vec<f64>, Vec<f64>)> {
+        vec![
+            (vec![2.0, 3.0, -1.0], vec![1.0]),
+            (vec![3.0, -1.0, 0.5], vec![-1.0]),
+            (vec![0.5, 1.0, 1.0], vec![-1.0]),
+            (vec![1.0, 1.0, -1.0], vec![1.0]),
+        ]
+    }
+
+    #[test]
+    fn fit_reduces_loss() {
+        let mlp = Mlp::new_seeded(3, &[4, 4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError);
+        assert!(trainer.model().is_training());
+
+        let history = trainer
+            .fit(&dataset(), 100)
+            .expect("training should succeed");
+
+        assert_eq!(history.loss.len(), 100);
+        assert!(history.loss[99] < history.loss[0] / 10.0);
+        assert!(!trainer.model().is_training());
+    }
+
+    #[test]
+    fn fit_reports_dimension_mismatch() {
+        let mlp = Mlp::new_seeded(2, &[1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError);
+
+        assert!(trainer.fit(&dataset(), 1).is_err());
+    }
+
+    #[test]
+    fn fit_mini_batches() {
+        let mlp = Mlp::new_seeded(3, &[4, 4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.1);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError).config(TrainConfig {
+            batch_size: Some(3),
+            shuffle: true,
+            seed: Some(1),
+            reduction: Reduction::Mean,
+            ..Default::default()
+        });
+        let history = trainer
+            .fit(&dataset(), 100)
+            .expect("training should succeed");
+
+        assert!(history.loss[99] < history.loss[0] / 10.0);
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        let run = |seed| {
+            let mlp = Mlp::new_seeded(3, &[4, 1], 5);
+            let sgd = Sgd::new(mlp.parameters(), 0.1);
+
+            Trainer::new(mlp, sgd, Loss::SquaredError)
+                .config(TrainConfig {
+                    batch_size: Some(1),
+                    shuffle: true,
+                    seed: Some(seed),
+                    ..Default::default()
+                })
+                .fit(&dataset(), 5)
+                .expect("training should succeed")
+                .loss
+        };
+
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+    }
+
+    #[test]
+    fn fit_transforms_samples() {
+        let scaled = InMemoryDataset::from(
+            data()
+                .into_iter()
+                .map(|(x, y)| (x.iter().map(|x| x * 100.0).collect(), y))
+                .collect::<Vec<_>>(),
+        );
+
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError)
+            .config(TrainConfig {
+                seed: Some(1),
+                ..Default::default()
+            })
+            .transform(
+                Pipeline::new()
+                    .then(|(x, y): Sample| (x.iter().map(|x| x / 100.0).collect(), y))
+                    .augment(GaussianNoise { std: 0.01 }),
+            );
+        let history = trainer.fit(&scaled, 100).expect("training should succeed");
+
+        let loss = trainer.compute_loss(&scaled).expect("should compute loss");
+
+        assert!(history.loss[99] < history.loss[0] / 10.0);
+        assert!(loss < history.loss[0] / 10.0);
+    }
+
+    #[test]
+    fn partial_fit_learns_online() {
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError);
+
+        let initial = trainer
+            .compute_loss(&dataset())
+            .expect("should compute loss");
+
+        for sample in data().iter().cycle().take(400) {
+            let step = trainer
+                .partial_fit(std::slice::from_ref(sample))
+                .expect("should train");
+
+            assert!(step.loss.value().is_finite());
+        }
+
+        let loss = trainer
+            .compute_loss(&dataset())
+            .expect("should compute loss");
+
+        assert!(loss < initial / 10.0);
+        assert_eq!(trainer.epoch(), 0);
+        assert!(matches!(trainer.partial_fit(&[]), Err(Error::EmptyBatch)));
+    }
+
+    #[test]
+    fn train_batch_averages_loss() {
+        let mlp = Mlp::new_seeded(3, &[4, 4, 1], 1);
+        let mut sgd = Sgd::new(mlp.parameters(), 0.0);
+        let batch = DataLoader::new(&dataset(), 4)
+            .epoch()
+            .next()
+            .expect("should have a batch");
+
+        let sum = train_batch(
+            &mlp,
+            &mut sgd,
+            &Loss::SquaredError,
+            &batch,
+            None,
+            Reduction::Sum,
+        )
+        .expect("step should succeed");
+        let mean = train_batch(
+            &mlp,
+            &mut sgd,
+            &Loss::SquaredError,
+            &batch,
+            None,
+            Reduction::Mean,
+        )
+        .expect("step should succeed");
+
+        assert!((sum.loss.value() / 4.0 - mean.loss.value()).abs() < 1e-12);
+        assert!((sum.grad_norm / 4.0 - mean.grad_norm).abs() < 1e-12);
+
+        let layers = sum.layer_grad_norms.iter().map(|n| n * n).sum::<f64>();
+        assert_eq!(sum.layer_grad_norms.len(), 3);
+        assert_eq!(sum.gradient_flow.layers.len(), 3);
+        assert!((layers.sqrt() - sum.grad_norm).abs() < 1e-12);
+    }
+
+    #[test]
+    fn callbacks_receive_events() {
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let recorder = Recorder::default();
+        let events = recorder.events.clone();
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError)
+            .config(TrainConfig {
+                batch_size: Some(2),
+                ..Default::default()
+            })
+            .callback(recorder);
+        trainer.fit(&dataset(), 2).expect("training should succeed");
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "start 0", "batch 0", "batch 1", "end 0", "start 1", "batch 0", "batch 1", "end 1",
+                "done 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn callbacks_receive_parameter_stats() {
+        struct Drift(Rc<RefCell<Vec<f64>>>);
+
+        impl Callback for Drift {
+            fn on_epoch_end(&mut self, log: &EpochLog, _state: &TrainState) {
+                assert_eq!(log.parameter_stats[0].histogram.len(), 4);
+                self.0.borrow_mut().push(log.parameter_stats[0].std);
+            }
+        }
+
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let initial = mlp.parameter_stats()[0].std;
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let stds = Rc::new(RefCell::new(Vec::new()));
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError)
+            .config(TrainConfig {
+                histogram_buckets: Some(4),
+                ..Default::default()
+            })
+            .callback(Drift(stds.clone()));
+        trainer.fit(&dataset(), 3).expect("training should succeed");
+
+        assert_eq!(stds.borrow().len(), 3);
+        assert_eq!(stds.borrow()[2], trainer.model().parameter_stats()[0].std);
+        assert_ne!(stds.borrow()[0], initial);
+    }
+
+    #[test]
+    fn fit_reports_metrics() {
+        let mlp = Mlp::new_seeded(3, &[4, 4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError).config(TrainConfig {
+            metrics: vec![Metric::Accuracy { threshold: 0.0 }],
+            ..Default::default()
+        });
+        trainer
+            .fit(&dataset(), 100)
+            .expect("training should succeed");
+
+        let metrics = trainer.evaluate(&dataset()).expect("should evaluate");
+
+        assert_eq!(metrics["accuracy"], 1.0);
+    }
+
+    #[test]
+    fn history_accumulates_across_fits() {
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.1);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError)
+            .config(TrainConfig {
+                metrics: vec![Metric::Accuracy { threshold: 0.0 }],
+                ..Default::default()
+            })
+            .scheduler(StepLR::new(2, 0.5));
+        trainer.fit(&dataset(), 2).expect("training should succeed");
+        let second = trainer.fit(&dataset(), 2).expect("training should succeed");
+
+        assert_eq!(second.epochs, vec![2, 3]);
+
+        let history = trainer.history();
+
+        assert_eq!(history.epochs, vec![0, 1, 2, 3]);
+        assert_eq!(history.learning_rate, vec![0.1, 0.1, 0.05, 0.05]);
+        assert_eq!(history.loss[2..], second.loss);
+        assert!(history.metric("accuracy").iter().all(Option::is_some));
+        assert!(history.grad_norm.iter().all(|norm| *norm > 0.0));
+        assert!(history
+            .layer_grad_norms
+            .iter()
+            .all(|norms| norms.len() == 2));
+    }
+
+    #[test]
+    fn cross_validation() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut built = 0;
+
+        let factory = || {
+            built += 1;
+
+            let mlp = Mlp::new(3, &[4, 1], &mut rng);
+            let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+            Trainer::new(mlp, sgd, Loss::SquaredError)
+        };
+        let config = TrainConfig {
+            metrics: vec![Metric::Accuracy { threshold: 0.0 }],
+            ..Default::default()
+        };
+
+        let results = cross_validate(factory, &dataset(), 4, &config, 20).expect("should validate");
+
+        assert_eq!(built, 4);
+        assert_eq!(results.folds.len(), 4);
+
+        let mean = results.mean();
+        let expected = results.folds.iter().map(|f| f.loss).sum::<f64>() / 4.0;
+
+        assert!((mean.loss - expected).abs() < 1e-12);
+        assert!(mean.metrics.contains_key("accuracy"));
+    }
+
+    #[test]
+    fn cross_validation_folds() {
+        let factory = || -> Trainer { unreachable!() };
+
+        let result = cross_validate(factory, &dataset(), 5, &TrainConfig::default(), 1);
+
+        assert!(matches!(result, Err(Error::InvalidFolds(5, 4))));
+    }
+
+    #[test]
+    fn weighted_cross_validation() {
+        let factory = || {
+            let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+            let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+            Trainer::new(mlp, sgd, Loss::SquaredError)
+        };
+        let config = |weights: Vec<f64>| TrainConfig {
+            sample_weights: Some(weights),
+            ..Default::default()
+        };
+
+        let uniform = cross_validate(factory, &dataset(), 2, &config(vec![1.0; 4]), 5)
+            .expect("should validate");
+        let unweighted = cross_validate(factory, &dataset(), 2, &TrainConfig::default(), 5)
+            .expect("should validate");
+        for (uniform, unweighted) in uniform.folds.iter().zip(&unweighted.folds) {
+            assert!((uniform.loss - unweighted.loss).abs() < 1e-12);
+        }
+
+        // every fold trains with the weights of its own samples
+        let weighted = cross_validate(factory, &dataset(), 2, &config(vec![1.0, 1.0, 0.0, 0.0]), 5)
+            .expect("should validate");
+        assert!((weighted.folds[0].loss - unweighted.folds[0].loss).abs() > 1e-9);
+        assert!((weighted.folds[1].loss - unweighted.folds[1].loss).abs() < 1e-12);
+
+        let result = cross_validate(factory, &dataset(), 2, &config(vec![1.0; 3]), 1);
+        assert!(matches!(
+            result,
+            Err(Error::Loss(crate::loss::Error::WeightMismatch(4, 3)))
+        ));
+    }
+
+    #[test]
+    fn checkpoints_best_models() {
+        let dir =
+            std::env::temp_dir().join(format!("micrograd-checkpoints-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError).callback(
+            ModelCheckpoint::new(&dir)
+                .monitor("loss", Mode::Min)
+                .keep_last(2),
+        );
+        trainer.fit(&dataset(), 5).expect("training should succeed");
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .expect("checkpoint dir should exist")
+            .map(|entry| entry.expect("should list").file_name())
+            .collect();
+        files.sort();
+
+        // the loss improves every epoch, so only the last two remain
+        assert_eq!(files, vec!["checkpoint-0004.txt", "checkpoint-0005.txt"]);
+
+        let checkpoint = Checkpoint::load(dir.join("checkpoint-0005.txt")).expect("should load");
+        let weights: Vec<_> = trainer
+            .model()
+            .parameters()
+            .iter()
+            .map(|p| p.value())
+            .collect();
+
+        assert_eq!(checkpoint.epoch, 5);
+        assert_eq!(checkpoint.parameters, weights);
+
+        std::fs::remove_dir_all(&dir).expect("should clean up");
+    }
+
+    #[test]
+    fn keeps_the_best_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("micrograd-keep-best-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.05);
+
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError)
+            .callback(ModelCheckpoint::new(&dir).keep_last(0));
+        trainer.fit(&dataset(), 3).expect("training should succeed");
+
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .expect("checkpoint dir should exist")
+            .map(|entry| entry.expect("should list").file_name())
+            .collect();
+        assert_eq!(files, vec!["checkpoint-0003.txt"]);
+
+        std::fs::remove_dir_all(&dir).expect("should clean up");
+    }
+
+    #[test]
+    fn resume_continues_deterministically() {
+        let path =
+            std::env::temp_dir().join(format!("micrograd-resume-{}.txt", std::process::id()));
+
+        let trainer = || {
+            let mlp = Mlp::new_seeded(3, &[4, 1], 1);
+            let adam = AdamW::new(mlp.parameters(), 0.05, 0.01);
+
+            Trainer::new(mlp, adam, Loss::SquaredError)
+                .config(TrainConfig {
+                    batch_size: Some(2),
+                    shuffle: true,
+                    seed: Some(3),
+                    ..Default::default()
+                })
+                .scheduler(StepLR::new(2, 0.5))
+        };
+
+        let mut uninterrupted = trainer();
+        let expected = uninterrupted.fit(&dataset(), 6).expect("should train");
+
+        let mut interrupted = trainer();
+        interrupted.fit(&dataset(), 3).expect("should train");
+        interrupted.save(&path).expect("should save");
+
+        let mut resumed = trainer();
+        resumed.resume(&path).expect("should resume");
+        let continued = resumed.fit(&dataset(), 3).expect("should train");
+
+        std::fs::remove_file(&path).expect("should clean up");
+
+        assert_eq!(resumed.epoch(), 6);
+        assert_eq!(continued.loss, expected.loss[3..]);
+        assert_eq!(
+            resumed.checkpoint().parameters,
+            uninterrupted.checkpoint().parameters
+        );
+    }
+}
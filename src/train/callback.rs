@@ -0,0 +1,186 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::History;
+use crate::{
+    checkpoint::Checkpoint,
+    loss::Reduction,
+    nn::{GradientFlow, LayerStats, Mlp, Precision},
+    optim::Optimizer,
+};
+
+/// Summary of a finished epoch, as passed to callbacks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EpochLog {
+    pub epoch: usize,
+    pub loss: f64,
+    pub metrics: BTreeMap<String, f64>,
+    /// Mean global gradient norm of the epoch's optimizer steps
+    pub grad_norm: f64,
+    /// Mean gradient norm of every layer over the epoch's optimizer steps
+    pub layer_grad_norms: Vec<f64>,
+    /// Weight statistics of every layer at the end of the epoch, with
+    /// histograms if `TrainConfig::histogram_buckets` is set
+    pub parameter_stats: Vec<LayerStats>,
+}
+
+/// Summary of a finished optimizer step, as passed to callbacks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchLog {
+    /// Index of the batch within the epoch
+    pub batch: usize,
+    /// Number of samples in the batch
+    pub samples: usize,
+    pub loss: f64,
+    /// Global L2 norm of all gradients before the step, i.e. before any clipping
+    pub grad_norm: f64,
+    /// L2 norm of the gradients of every layer before the step
+    pub layer_grad_norms: Vec<f64>,
+    /// Mean absolute gradient of every layer before the step
+    pub gradient_flow: GradientFlow,
+}
+
+/// A view of the trainer passed to callbacks
+pub struct TrainState<'a> {
+    /// The current epoch, counted from 0
+    pub epoch: usize,
+    /// The epoch at which the current `fit` will stop (exclusive)
+    pub end_epoch: usize,
+    /// Number of batches in every epoch
+    pub batches: usize,
+    /// How the losses of batches and epochs are reduced over their samples
+    pub reduction: Reduction,
+    pub model: &'a Mlp,
+    pub optimizer: &'a dyn Optimizer,
+}
+
+/// Hooks into the training loop of a `Trainer`.
+///
+/// All methods have empty default implementations, so implementors only need
+/// to override the events they are interested in.
+pub trait Callback {
+    fn on_epoch_start(&mut self, _state: &TrainState) {}
+
+    /// Called after every optimizer step
+    fn on_batch_end(&mut self, _log: &BatchLog, _state: &TrainState) {}
+
+    fn on_epoch_end(&mut self, _log: &EpochLog, _state: &TrainState) {}
+
+    fn on_train_end(&mut self, _history: &History, _state: &TrainState) {}
+}
+
+/// Whether lower or higher values of a monitored quantity are better
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Min,
+    Max,
+}
+
+/// Saves a `Checkpoint` of the model and optimizer into a directory whenever
+/// the monitored quantity improves.
+///
+/// Monitors the epoch loss by default, or any metric reported by the trainer.
+/// Checkpoints are named `checkpoint-<epoch>.txt` after the number of completed
+/// epochs. Failures to save are reported on stderr rather than interrupting
+/// training.
+#[derive(Debug)]
+pub struct ModelCheckpoint {
+    dir: PathBuf,
+    monitor: String,
+    mode: Mode,
+    keep_last: Option<usize>,
+    precision: Precision,
+    best: Option<f64>,
+    saved: VecDeque<PathBuf>,
+}
+
+impl ModelCheckpoint {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            monitor: "loss".to_string(),
+            mode: Mode::Min,
+            keep_last: None,
+            precision: Precision::F64,
+            best: None,
+            saved: VecDeque::new(),
+        }
+    }
+
+    /// Name of the metric to monitor, "loss" for the epoch loss
+    pub fn monitor(mut self, name: &str, mode: Mode) -> Self {
+        self.monitor = name.to_string();
+        self.mode = mode;
+        self
+    }
+
+    /// Only keep the `n` most recent (and therefore best) checkpoints on disk,
+    /// at least the best one
+    pub fn keep_last(mut self, n: usize) -> Self {
+        self.keep_last = Some(n.max(1));
+        self
+    }
+
+    /// Save the weights in a lower precision to shrink the checkpoints
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Path of the best checkpoint saved so far
+    pub fn best_path(&self) -> Option<&Path> {
+        self.saved.back().map(PathBuf::as_path)
+    }
+
+    fn save(&mut self, log: &EpochLog, state: &TrainState) -> crate::checkpoint::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let completed = log.epoch + 1;
+        let path = self.dir.join(format!("checkpoint-{completed:04}.txt"));
+
+        Checkpoint {
+            precision: self.precision,
+            ..Checkpoint::new(completed, state.model, state.optimizer.state())
+        }
+        .save(&path)?;
+        self.saved.push_back(path);
+
+        while self.saved.len() > self.keep_last.unwrap_or(usize::MAX) {
+            if let Some(old) = self.saved.pop_front() {
+                fs::remove_file(old)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Callback for ModelCheckpoint {
+    fn on_epoch_end(&mut self, log: &EpochLog, state: &TrainState) {
+        let value = match self.monitor.as_str() {
+            "loss" => Some(log.loss),
+            name => log.metrics.get(name).copied(),
+        };
+
+        let Some(value) = value else {
+            return;
+        };
+
+        let improved = match (self.best, self.mode) {
+            (None, _) => true,
+            (Some(best), Mode::Min) => value < best,
+            (Some(best), Mode::Max) => value > best,
+        };
+
+        if improved {
+            self.best = Some(value);
+
+            if let Err(err) = self.save(log, state) {
+                eprintln!("Failed to save checkpoint: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Knowledge distillation: training a small student model on the outputs of a
+//! larger, already trained teacher as well as on the labels.
+//!
+//! Both models' outputs are read as logits. The student learns from the
+//! cross-entropy against the one-hot targets and from the KL divergence between
+//! the temperature-softened softmax of the teacher and its own, which carries
+//! how similar the teacher finds the wrong classes.
+
+use super::{inputs, Result};
+use crate::{
+    data::{Batch, DataLoader, Dataset},
+    loss::{self, cross_entropy, log_softmax},
+    nn::{softmax, Mlp},
+    optim::Optimizer,
+    value::Value,
+};
+
+/// Mean losses of a distillation step or epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DistillStep {
+    /// `alpha * hard_loss + (1 - alpha) * soft_loss`
+    pub loss: f64,
+    /// Cross-entropy of the student against the targets
+    pub hard_loss: f64,
+    /// KL divergence of the softened student from the softened teacher,
+    /// scaled by the squared temperature
+    pub soft_loss: f64,
+}
+
+/// Trains a student model against a frozen teacher. The teacher's outputs are
+/// detached, so the teacher never gets gradients and needs no optimizer.
+pub struct Distiller {
+    teacher: Mlp,
+    student: Mlp,
+    optimizer: Box<dyn Optimizer>,
+    temperature: f64,
+    alpha: f64,
+    batch_size: Option<usize>,
+}
+
+impl Distiller {
+    /// The optimizer is expected to hold the student's parameters, and both
+    /// models need the same number of inputs and outputs. The teacher is put
+    /// in evaluation mode.
+    pub fn new<O: Optimizer + 'static>(mut teacher: Mlp, student: Mlp, optimizer: O) -> Self {
+        teacher.eval();
+
+        Self {
+            teacher,
+            student,
+            optimizer: Box::new(optimizer),
+            temperature: 2.0,
+            alpha: 0.5,
+            batch_size: None,
+        }
+    }
+
+    /// Temperature softening both softmaxes of the KL term, 2 by default
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Weight of the hard-label cross-entropy, the KL term gets `1 - alpha`,
+    /// 0.5 by default
+    pub fn alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Number of samples per step of `fit`, the whole dataset by default
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn teacher(&self) -> &Mlp {
+        &self.teacher
+    }
+
+    pub fn student(&self) -> &Mlp {
+        &self.student
+    }
+
+    pub fn into_student(self) -> Mlp {
+        self.student
+    }
+
+    /// Takes an optimizer step on the distillation loss of `batch`
+    pub fn step(&mut self, batch: &Batch) -> Result<DistillStep> {
+        let mut student = Vec::with_capacity(batch.len());
+        let mut teacher = Vec::with_capacity(batch.len());
+
+        for x in &batch.inputs {
+            let x = inputs(x);
+            let logits: Vec<_> = self
+                .teacher
+                .predict(&x)?
+                .iter()
+                .map(Value::detach)
+                .collect();
+
+            student.push(self.student.predict(&x)?);
+            teacher.push(logits);
+        }
+
+        let scale = Value::new(1.0 / batch.len().max(1) as f64, "1/n");
+        let hard = cross_entropy(&batch.targets, &student, None, None)? * scale.clone();
+        let soft = self.soft_loss(&student, &teacher)? * scale;
+        let loss = hard.clone() * Value::new(self.alpha, "alpha")
+            + soft.clone() * Value::new(1.0 - self.alpha, "1 - alpha");
+
+        loss.backpropagate();
+        self.optimizer.step();
+        self.optimizer.zero_grad();
+
+        Ok(DistillStep {
+            loss: loss.value(),
+            hard_loss: hard.value(),
+            soft_loss: soft.value(),
+        })
+    }
+
+    /// Distills for `epochs` epochs over `data`, returning the mean losses of
+    /// every epoch's steps
+    pub fn fit<D: Dataset + ?Sized>(
+        &mut self,
+        data: &D,
+        epochs: usize,
+    ) -> Result<Vec<DistillStep>> {
+        let mut loader = DataLoader::new(data, self.batch_size.unwrap_or(data.len()));
+
+        (0..epochs)
+            .map(|_| {
+                let batches = loader.len().max(1) as f64;
+                let mut mean = DistillStep::default();
+
+                for batch in loader.epoch() {
+                    let step = self.step(&batch)?;
+
+                    mean.loss += step.loss / batches;
+                    mean.hard_loss += step.hard_loss / batches;
+                    mean.soft_loss += step.soft_loss / batches;
+                }
+
+                Ok(mean)
+            })
+            .collect()
+    }
+
+    // Sum over the samples of T^2 KL(teacher || student) of the softened
+    // distributions, the T^2 keeping its gradients on the scale of the hard loss
+    fn soft_loss(&self, student: &[Vec<Value>], teacher: &[Vec<Value>]) -> Result<Value> {
+        let inverse = Value::new(1.0 / self.temperature, "1/T");
+        let mut sum = Value::new(0.0, "0");
+
+        for (i, (student, teacher)) in student.iter().zip(teacher).enumerate() {
+            if student.len() != teacher.len() {
+                return Err(loss::Error::DimensionMismatch(i, teacher.len(), student.len()).into());
+            }
+
+            let targets = softmax(teacher, self.temperature);
+            let scaled: Vec<_> = student
+                .iter()
+                .map(|l| l.clone() * inverse.clone())
+                .collect();
+
+            for (p, log_q) in targets.iter().zip(log_softmax(&scaled)) {
+                let p = p.value();
+
+                if p > 0.0 {
+                    sum = sum + (Value::new(p.ln(), "ln p") - log_q) * Value::new(p, "p");
+                }
+            }
+        }
+
+        Ok(sum * Value::new(self.temperature * self.temperature, "T^2"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Distiller;
+    use crate::{
+        data::{Batch, InMemoryDataset},
+        metrics::class_of,
+        nn::Mlp,
+        optim::Sgd,
+    };
+
+    fn dataset(teacher: &Mlp) -> InMemoryDataset {
+        InMemoryDataset::from(
+            (0..16)
+                .map(|i| {
+                    let x = vec![(i % 4) as f64 - 1.5, (i / 4) as f64 - 1.5];
+                    let class = class_of(&teacher.infer(&x).expect("should infer"), 0.0);
+                    let mut target = vec![0.0; 3];
+                    target[class] = 1.0;
+
+                    (x, target)
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn distill() {
+        let teacher = Mlp::new_seeded(2, &[16, 3], 1);
+        let data = dataset(&teacher);
+        let student = Mlp::new_seeded(2, &[4, 3], 2);
+        let sgd = Sgd::new(student.parameters(), 0.2);
+        let before: Vec<_> = teacher.parameters().iter().map(|p| p.value()).collect();
+
+        let mut distiller = Distiller::new(teacher, student, sgd).batch_size(8);
+        let losses = distiller.fit(&data, 100).expect("should distill");
+
+        assert_eq!(losses.len(), 100);
+        assert!(losses[99].loss < losses[0].loss);
+        assert!(losses[99].soft_loss < losses[0].soft_loss / 2.0);
+        for step in &losses {
+            assert!((step.loss - (step.hard_loss + step.soft_loss) / 2.0).abs() < 1e-9);
+        }
+
+        let teacher = distiller.teacher();
+        assert!(teacher.parameters().iter().all(|p| p.gradient() == 0.0));
+        assert_eq!(
+            teacher
+                .parameters()
+                .iter()
+                .map(|p| p.value())
+                .collect::<Vec<_>>(),
+            before
+        );
+
+        // a student matching the teacher exactly has no soft loss
+        let copy = Mlp::new_seeded(2, &[16, 3], 1);
+        let sgd = Sgd::new(copy.parameters(), 0.0);
+        let mut identical = Distiller::new(Mlp::new_seeded(2, &[16, 3], 1), copy, sgd);
+        let batch = Batch {
+            indices: vec![0],
+            inputs: vec![vec![0.5, -0.5]],
+            targets: vec![vec![1.0, 0.0, 0.0]],
+        };
+        let step = identical.step(&batch).expect("should step");
+        assert!(step.soft_loss.abs() < 1e-12);
+
+        let mismatched = Mlp::new_seeded(2, &[2], 3);
+        let sgd = Sgd::new(mismatched.parameters(), 0.1);
+        assert!(Distiller::new(Mlp::new_seeded(2, &[3], 1), mismatched, sgd)
+            .step(&Batch {
+                targets: vec![vec![1.0, 0.0]],
+                ..batch
+            })
+            .is_err());
+    }
+}
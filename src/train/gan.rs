@@ -0,0 +1,216 @@
+//! Training generative adversarial networks.
+//!
+//! A generator maps random latent vectors to samples, and a discriminator
+//! tells generated samples from real ones. Both are plain `Mlp`s, the
+//! discriminator's single `tanh` output is read as the probability
+//! `(output + 1) / 2` of a sample being real.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::{inputs, Result};
+use crate::{
+    data::generators::gaussian,
+    nn::{self, Mlp},
+    optim::Optimizer,
+    value::Value,
+};
+
+/// Losses of a [`Gan::step`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GanStep {
+    pub discriminator_loss: f64,
+    pub generator_loss: f64,
+}
+
+/// Alternates discriminator and generator updates, each model with its own
+/// optimizer
+pub struct Gan {
+    generator: Mlp,
+    discriminator: Mlp,
+    generator_optimizer: Box<dyn Optimizer>,
+    discriminator_optimizer: Box<dyn Optimizer>,
+    rng: ChaCha8Rng,
+}
+
+impl Gan {
+    /// The latent size is the number of generator inputs, and the generator
+    /// outputs need to match the discriminator inputs
+    pub fn new<G: Optimizer + 'static, D: Optimizer + 'static>(
+        generator: Mlp,
+        generator_optimizer: G,
+        discriminator: Mlp,
+        discriminator_optimizer: D,
+    ) -> Self {
+        Self {
+            generator,
+            discriminator,
+            generator_optimizer: Box::new(generator_optimizer),
+            discriminator_optimizer: Box::new(discriminator_optimizer),
+            rng: ChaCha8Rng::seed_from_u64(0),
+        }
+    }
+
+    /// Seeds the latent vectors, fixed by default
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn generator(&self) -> &Mlp {
+        &self.generator
+    }
+
+    pub fn discriminator(&self) -> &Mlp {
+        &self.discriminator
+    }
+
+    /// Samples from standard normal latent vectors, without building graphs
+    pub fn generate(&mut self, count: usize) -> Result<Vec<Vec<f64>>> {
+        (0..count)
+            .map(|_| {
+                let z = self.latent();
+
+                Ok(self.generator.infer(&z)?)
+            })
+            .collect()
+    }
+
+    /// Updates the discriminator on `real` samples and as many generated ones,
+    /// then the generator on a new batch of generated samples.
+    ///
+    /// The discriminator sees detached generated samples, so its update
+    /// leaves the generator alone. The generator uses the non-saturating loss,
+    /// the discriminator's cross-entropy against the label "real", and the
+    /// gradients this leaves on the discriminator are discarded.
+    pub fn step(&mut self, real: &[Vec<f64>]) -> Result<GanStep> {
+        let fakes = self.fakes(real.len())?;
+
+        let real = real
+            .iter()
+            .map(|x| self.probability(&inputs(x)))
+            .collect::<nn::Result<Vec<_>>>()?;
+        let fake = fakes
+            .iter()
+            .map(|x| {
+                let detached: Vec<_> = x.iter().map(Value::detach).collect();
+
+                self.probability(&detached)
+            })
+            .collect::<nn::Result<Vec<_>>>()?;
+
+        let discriminator_loss = gan_loss(&real, true) + gan_loss(&fake, false);
+        discriminator_loss.backpropagate();
+        self.discriminator_optimizer.step();
+        self.discriminator_optimizer.zero_grad();
+
+        let fake = self
+            .fakes(real.len())?
+            .iter()
+            .map(|x| self.probability(x))
+            .collect::<nn::Result<Vec<_>>>()?;
+
+        let generator_loss = gan_loss(&fake, true);
+        generator_loss.backpropagate();
+        self.generator_optimizer.step();
+        self.generator_optimizer.zero_grad();
+        self.discriminator_optimizer.zero_grad();
+
+        Ok(GanStep {
+            discriminator_loss: discriminator_loss.value(),
+            generator_loss: generator_loss.value(),
+        })
+    }
+
+    fn latent(&mut self) -> Vec<f64> {
+        let size = self.generator.layer_sizes()[0];
+
+        (0..size).map(|_| gaussian(&mut self.rng)).collect()
+    }
+
+    fn fakes(&mut self, count: usize) -> nn::Result<Vec<Vec<Value>>> {
+        (0..count)
+            .map(|_| {
+                let z = self.latent();
+                self.generator.predict(&inputs(&z))
+            })
+            .collect()
+    }
+
+    fn probability(&self, x: &[Value]) -> nn::Result<Value> {
+        let output = self.discriminator.predict(x)?;
+
+        Ok((output[0].clone() + Value::new(1.0, "1")) * Value::new(0.5, "1/2"))
+    }
+}
+
+/// Mean binary cross-entropy of discriminator probabilities against the same
+/// label for every sample, `real` or generated
+pub fn gan_loss(probabilities: &[Value], real: bool) -> Value {
+    let scale = Value::new(-1.0 / probabilities.len().max(1) as f64, "-1/n");
+
+    probabilities.iter().fold(Value::new(0.0, "0"), |sum, p| {
+        let likelihood = if real {
+            p.clone()
+        } else {
+            Value::new(1.0, "1") - p.clone()
+        };
+
+        sum + (likelihood + Value::new(EPSILON, "eps")).ln()
+    }) * scale
+}
+
+// Guards the logarithms against probabilities of exactly 0
+const EPSILON: f64 = 1e-12;
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{gan_loss, Gan};
+    use crate::{data::generators::gaussian, nn::Mlp, optim::Sgd, value::Value};
+
+    #[test]
+    fn loss() {
+        let p = [Value::new(0.8, "p"), Value::new(0.6, "q")];
+
+        let real = gan_loss(&p, true).value();
+        let fake = gan_loss(&p, false).value();
+
+        assert!((real - -(0.8f64.ln() + 0.6f64.ln()) / 2.0).abs() < 1e-9);
+        assert!((fake - -(0.2f64.ln() + 0.4f64.ln()) / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn toy_gan() {
+        let generator = Mlp::new_seeded(1, &[8, 1], 1);
+        let discriminator = Mlp::new_seeded(1, &[8, 1], 2);
+        let generator_sgd = Sgd::new(generator.parameters(), 0.05);
+        let discriminator_sgd = Sgd::new(discriminator.parameters(), 0.05);
+        let mut gan = Gan::new(generator, generator_sgd, discriminator, discriminator_sgd);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mean =
+            |samples: &[Vec<f64>]| samples.iter().map(|s| s[0]).sum::<f64>() / samples.len() as f64;
+        let before = mean(&gan.generate(200).expect("should generate"));
+
+        for _ in 0..300 {
+            let real: Vec<_> = (0..16)
+                .map(|_| vec![0.5 + 0.05 * gaussian(&mut rng)])
+                .collect();
+
+            let step = gan.step(&real).expect("should train");
+            assert!(step.discriminator_loss.is_finite() && step.generator_loss.is_finite());
+        }
+
+        let after = mean(&gan.generate(200).expect("should generate"));
+        assert!((before - 0.5).abs() > 0.5);
+        assert!((after - 0.5).abs() < 0.1, "generated mean {after}");
+        assert!(gan
+            .generator()
+            .parameters()
+            .iter()
+            .all(|p| p.gradient() == 0.0));
+    }
+}
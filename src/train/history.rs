@@ -0,0 +1,174 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use super::callback::EpochLog;
+
+/// Per-epoch record of a training run: loss, learning rate, gradient norms
+/// and metrics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    pub epochs: Vec<usize>,
+    pub loss: Vec<f64>,
+    /// Learning rate the epoch was trained with, before any scheduler step
+    pub learning_rate: Vec<f64>,
+    /// Mean global gradient norm of every epoch
+    pub grad_norm: Vec<f64>,
+    /// Mean gradient norm of every layer, by epoch
+    pub layer_grad_norms: Vec<Vec<f64>>,
+    pub metrics: Vec<BTreeMap<String, f64>>,
+}
+
+impl History {
+    /// Number of recorded epochs
+    pub fn len(&self) -> usize {
+        self.epochs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    /// Records a finished epoch
+    pub fn push(&mut self, log: &EpochLog, learning_rate: f64) {
+        self.epochs.push(log.epoch);
+        self.loss.push(log.loss);
+        self.learning_rate.push(learning_rate);
+        self.grad_norm.push(log.grad_norm);
+        self.layer_grad_norms.push(log.layer_grad_norms.clone());
+        self.metrics.push(log.metrics.clone());
+    }
+
+    /// Appends the epochs of another history, e.g. of a later `fit` call
+    pub fn extend(&mut self, other: &History) {
+        self.epochs.extend(&other.epochs);
+        self.loss.extend(&other.loss);
+        self.learning_rate.extend(&other.learning_rate);
+        self.grad_norm.extend(&other.grad_norm);
+        self.layer_grad_norms
+            .extend(other.layer_grad_norms.iter().cloned());
+        self.metrics.extend(other.metrics.iter().cloned());
+    }
+
+    /// Values of a metric by epoch, `None` for epochs where it wasn't computed
+    pub fn metric(&self, name: &str) -> Vec<Option<f64>> {
+        self.metrics
+            .iter()
+            .map(|metrics| metrics.get(name).copied())
+            .collect()
+    }
+
+    /// Names of all metrics recorded in any epoch, sorted
+    pub fn metric_names(&self) -> Vec<&str> {
+        self.metrics
+            .iter()
+            .flat_map(|metrics| metrics.keys().map(String::as_str))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Writes the history as CSV with an `epoch,loss,learning_rate,grad_norm`
+    /// header followed by a `grad_norm_layer_<i>` column per layer and one column
+    /// per metric. Values missing in an epoch are left empty.
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        self.write_csv(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes the history as CSV, see [`History::to_csv`]
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let names = self.metric_names();
+        let layers = self
+            .layer_grad_norms
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0);
+
+        write!(writer, "epoch,loss,learning_rate,grad_norm")?;
+        for layer in 0..layers {
+            write!(writer, ",grad_norm_layer_{layer}")?;
+        }
+        for name in &names {
+            write!(writer, ",{name}")?;
+        }
+        writeln!(writer)?;
+
+        for i in 0..self.len() {
+            write!(
+                writer,
+                "{},{},{},{}",
+                self.epochs[i], self.loss[i], self.learning_rate[i], self.grad_norm[i]
+            )?;
+
+            for layer in 0..layers {
+                match self.layer_grad_norms[i].get(layer) {
+                    Some(value) => write!(writer, ",{value}")?,
+                    None => write!(writer, ",")?,
+                }
+            }
+
+            for name in &names {
+                match self.metrics[i].get(*name) {
+                    Some(value) => write!(writer, ",{value}")?,
+                    None => write!(writer, ",")?,
+                }
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::History;
+    use crate::train::callback::EpochLog;
+
+    #[test]
+    fn csv() {
+        let mut history = History::default();
+
+        history.push(
+            &EpochLog {
+                epoch: 0,
+                loss: 2.0,
+                grad_norm: 3.0,
+                layer_grad_norms: vec![2.0, 1.0],
+                ..Default::default()
+            },
+            0.1,
+        );
+        history.push(
+            &EpochLog {
+                epoch: 1,
+                loss: 1.5,
+                metrics: BTreeMap::from([("accuracy".to_string(), 0.75)]),
+                grad_norm: 0.5,
+                layer_grad_norms: vec![0.25, 0.5],
+                ..Default::default()
+            },
+            0.05,
+        );
+
+        let mut csv = Vec::new();
+        history.write_csv(&mut csv).expect("should write");
+
+        assert_eq!(
+            String::from_utf8(csv).expect("should be utf-8"),
+            "epoch,loss,learning_rate,grad_norm,grad_norm_layer_0,grad_norm_layer_1,accuracy\n\
+             0,2,0.1,3,2,1,\n\
+             1,1.5,0.05,0.5,0.25,0.5,0.75\n"
+        );
+        assert_eq!(history.metric("accuracy"), vec![None, Some(0.75)]);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::Dataset;
+
+/// Description of a training run, to tell after the fact how a model was
+/// trained and to compare runs. Written as JSON by a `Trainer` with
+/// [`Trainer::manifest`](super::Trainer::manifest) set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// Version of micrograd the model was trained with
+    pub crate_version: String,
+    /// Seed of the shuffling and augmentations, which reproduces the run
+    /// together with the seed of the model's initialization
+    pub seed: u64,
+    pub config: RunConfig,
+    /// The training data of the last `fit`
+    pub dataset: DatasetFingerprint,
+    /// Number of epochs trained over all calls of `fit`
+    pub epochs: usize,
+    /// Loss of the last epoch, `None` before the first one
+    pub final_loss: Option<f64>,
+    /// Metrics of the last epoch
+    pub final_metrics: BTreeMap<String, f64>,
+    /// Seconds spent in `fit` over all its calls
+    pub wall_time_secs: f64,
+}
+
+/// The model and training settings of a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub layer_sizes: Vec<usize>,
+    pub activation: String,
+    pub loss: String,
+    /// Learning rate of the optimizer at the end of the run
+    pub learning_rate: f64,
+    pub batch_size: Option<usize>,
+    pub shuffle: bool,
+    pub reduction: String,
+    pub metrics: Vec<String>,
+}
+
+/// Identifies a dataset by its size and a hash of its samples, which changes
+/// with any input or target as well as with the order of the samples
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetFingerprint {
+    pub samples: usize,
+    /// 64 bit FNV-1a hash of the bits of every input and target, in hex
+    pub hash: String,
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl DatasetFingerprint {
+    /// Reads every sample of `data` once
+    pub fn of<D: Dataset + ?Sized>(data: &D) -> Self {
+        let mut hash = FNV_OFFSET;
+        let mut feed = |values: &[f64]| {
+            let bytes = (values.len() as u64)
+                .to_le_bytes()
+                .into_iter()
+                .chain(values.iter().flat_map(|v| v.to_bits().to_le_bytes()));
+
+            for byte in bytes {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        for i in 0..data.len() {
+            let (x, y) = data.get(i);
+
+            feed(&x);
+            feed(&y);
+        }
+
+        Self {
+            samples: data.len(),
+            hash: format!("{hash:016x}"),
+        }
+    }
+}
+
+impl RunManifest {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("manifests should serialize")
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::from_json(&fs::read_to_string(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatasetFingerprint, RunManifest};
+    use crate::{
+        data::InMemoryDataset,
+        loss::Loss,
+        metrics::Metric,
+        nn::Mlp,
+        optim::Sgd,
+        train::{TrainConfig, Trainer},
+    };
+
+    fn dataset() -> InMemoryDataset {
+        InMemoryDataset::from(vec![
+            (vec![0.5, -0.5], vec![1.0]),
+            (vec![-0.5, 0.5], vec![-1.0]),
+            (vec![0.25, 0.0], vec![0.5]),
+        ])
+    }
+
+    #[test]
+    fn fingerprint() {
+        let a = DatasetFingerprint::of(&dataset());
+        assert_eq!(a, DatasetFingerprint::of(&dataset()));
+        assert_eq!((a.samples, a.hash.len()), (3, 16));
+
+        let changed = InMemoryDataset::from(vec![
+            (vec![0.5, -0.5], vec![1.0]),
+            (vec![-0.5, 0.5], vec![-1.0]),
+            (vec![0.25, 0.0], vec![0.25]),
+        ]);
+        assert_ne!(DatasetFingerprint::of(&changed).hash, a.hash);
+    }
+
+    #[test]
+    fn written_after_fit() {
+        let path =
+            std::env::temp_dir().join(format!("micrograd-manifest-{}.json", std::process::id()));
+
+        let mlp = Mlp::new_seeded(2, &[3, 1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.1);
+        let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError)
+            .config(TrainConfig {
+                batch_size: Some(2),
+                seed: Some(7),
+                metrics: vec![Metric::Rmse],
+                ..Default::default()
+            })
+            .manifest(&path);
+        trainer.fit(&dataset(), 3).expect("should train");
+        trainer.fit(&dataset(), 2).expect("should train");
+
+        let manifest = RunManifest::load(&path).expect("should load");
+        std::fs::remove_file(&path).expect("should clean up");
+
+        assert_eq!(manifest, trainer.run_manifest(&dataset()));
+        assert_eq!(manifest.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(manifest.seed, 7);
+        assert_eq!(manifest.epochs, 5);
+        assert_eq!(manifest.final_loss, trainer.history().loss.last().copied());
+        assert!(manifest.final_metrics.contains_key("rmse"));
+        assert!(manifest.wall_time_secs > 0.0);
+        assert_eq!(manifest.dataset, DatasetFingerprint::of(&dataset()));
+        assert_eq!(manifest.config.layer_sizes, [2, 3, 1]);
+        assert_eq!(manifest.config.activation, "tanh");
+        assert_eq!(manifest.config.loss, "SquaredError");
+        assert_eq!(manifest.config.batch_size, Some(2));
+        assert_eq!(manifest.config.reduction, "sum");
+        assert_eq!(manifest.config.metrics, ["rmse"]);
+    }
+}
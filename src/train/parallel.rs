@@ -0,0 +1,445 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use rayon::prelude::*;
+
+use super::{inputs, step, Result, TrainStep, Trainer};
+use crate::{
+    data::Batch,
+    loss::{self, Loss, Reduction},
+    nn::{self, Mlp},
+    optim::Optimizer,
+    value::Value,
+};
+
+impl Trainer {
+    /// Trains data-parallel on `workers` threads, each owning a replica of the
+    /// model.
+    ///
+    /// Every batch is split into one shard per worker. The workers compute the
+    /// gradients of their shards, which are then averaged, weighted by shard
+    /// size, into a single optimizer step on the model. The result matches
+    /// serial training up to floating point rounding.
+    pub fn data_parallel(mut self, workers: usize) -> Self {
+        self.workers = Some(Workers::spawn(&self.model, &self.loss, workers.max(1)));
+        self
+    }
+}
+
+struct Job {
+    parameters: Arc<Vec<f64>>,
+    inputs: Vec<Vec<f64>>,
+    targets: Vec<Vec<f64>>,
+    weights: Option<Vec<f64>>,
+}
+
+struct Worker {
+    jobs: Sender<Job>,
+    results: Receiver<Result<(f64, Vec<f64>)>>,
+    handle: JoinHandle<()>,
+}
+
+/// Threads each holding a replica of the model, kept alive between steps
+pub(super) struct Workers {
+    workers: Vec<Worker>,
+}
+
+impl Workers {
+    fn spawn(model: &Mlp, loss: &Loss, count: usize) -> Self {
+        let sizes = model.layer_sizes();
+
+        let workers = (0..count)
+            .map(|_| {
+                let (jobs, job_receiver) = mpsc::channel::<Job>();
+                let (result_sender, results) = mpsc::channel();
+                let sizes = sizes.clone();
+                let activation = model.activation().clone();
+                let quantized = model.quantized_layers();
+                let loss = loss.clone();
+
+                let handle = thread::spawn(move || {
+                    let replica = Mlp::new_seeded(sizes[0], &sizes[1..], 0)
+                        .with_activation(activation)
+                        .quantization_aware_layers(&quantized);
+
+                    for job in job_receiver {
+                        let result = shard_gradients(&replica, &loss, &job);
+
+                        if result_sender.send(result).is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                Worker {
+                    jobs,
+                    results,
+                    handle,
+                }
+            })
+            .collect();
+
+        Self { workers }
+    }
+
+    /// Runs a single optimization step of `model` on one batch, like
+    /// [`super::train_batch`], with the gradients computed by the workers
+    pub(super) fn train_batch(
+        &self,
+        model: &Mlp,
+        optimizer: &mut dyn Optimizer,
+        batch: &Batch,
+        weights: Option<&[f64]>,
+        reduction: Reduction,
+    ) -> Result<TrainStep> {
+        if let Some(weights) = weights {
+            if weights.len() != batch.len() {
+                return Err(loss::Error::WeightMismatch(batch.len(), weights.len()).into());
+            }
+        }
+
+        let model_parameters = model.parameters();
+        let parameters = Arc::new(
+            model_parameters
+                .iter()
+                .map(Value::value)
+                .collect::<Vec<_>>(),
+        );
+        let shard = batch.len().div_ceil(self.workers.len()).max(1);
+
+        let mut busy = 0;
+        for (s, (inputs, targets)) in batch
+            .inputs
+            .chunks(shard)
+            .zip(batch.targets.chunks(shard))
+            .enumerate()
+        {
+            let job = Job {
+                parameters: Arc::clone(&parameters),
+                inputs: inputs.to_vec(),
+                targets: targets.to_vec(),
+                weights: weights.map(|w| w[s * shard..s * shard + inputs.len()].to_vec()),
+            };
+
+            self.workers[s]
+                .jobs
+                .send(job)
+                .expect("worker threads should be running");
+            busy += 1;
+        }
+
+        let scale = match reduction {
+            Reduction::Sum => 1.0,
+            Reduction::Mean => 1.0 / batch.len().max(1) as f64,
+        };
+
+        // every result is received before returning any error, so that no
+        // stale result is left for the next step
+        let results: Vec<_> = self.workers[..busy]
+            .iter()
+            .map(|worker| {
+                worker
+                    .results
+                    .recv()
+                    .expect("worker threads should be running")
+            })
+            .collect();
+
+        let mut total = 0.0;
+        for result in results {
+            let (loss, gradients) = result?;
+            total += loss;
+
+            for (parameter, gradient) in model_parameters.iter().zip(gradients) {
+                parameter.set_gradient(parameter.gradient() + gradient * scale);
+            }
+        }
+
+        Ok(step(model, optimizer, Value::new(total * scale, "loss")))
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        for Worker { jobs, handle, .. } in self.workers.drain(..) {
+            // closing the job channel stops the worker
+            drop(jobs);
+            handle.join().ok();
+        }
+    }
+}
+
+// Summed loss of a shard and its gradients with respect to every parameter
+fn shard_gradients(replica: &Mlp, loss: &Loss, job: &Job) -> Result<(f64, Vec<f64>)> {
+    replica.load_parameters(&job.parameters)?;
+
+    let parameters = replica.parameters();
+    for parameter in &parameters {
+        parameter.zero_gradient();
+    }
+
+    let ypred = job
+        .inputs
+        .iter()
+        .map(|x| replica.predict(&inputs(x)))
+        .collect::<nn::Result<Vec<_>>>()?;
+
+    let loss = loss.compute(&job.targets, &ypred, job.weights.as_deref())?;
+    loss.backpropagate();
+
+    Ok((
+        loss.value(),
+        parameters.iter().map(Value::gradient).collect(),
+    ))
+}
+
+/// Runs the forward and backward passes of a batch across the rayon thread
+/// pool, accumulating the gradients into the model's parameters.
+///
+/// The graph isn't `Send`, so every thread works on its own replica of the
+/// model, built from the parameter values, with a contiguous chunk of the
+/// samples. The replica gradients are summed in chunk order. The returned loss
+/// is a leaf holding the batch loss, not connected to the model.
+pub(super) fn backward(
+    model: &Mlp,
+    loss: &Loss,
+    batch: &Batch,
+    weights: Option<&[f64]>,
+    reduction: Reduction,
+) -> Result<Value> {
+    if let Some(weights) = weights {
+        if weights.len() != batch.len() {
+            return Err(loss::Error::WeightMismatch(batch.len(), weights.len()).into());
+        }
+    }
+
+    let sizes = model.layer_sizes();
+    let activation = model.activation();
+    let quantized = model.quantized_layers();
+    let parameters = model.parameters();
+    let values: Vec<_> = parameters.iter().map(Value::value).collect();
+
+    let scale = match reduction {
+        Reduction::Sum => 1.0,
+        Reduction::Mean => 1.0 / batch.len().max(1) as f64,
+    };
+    let chunk = batch.len().div_ceil(rayon::current_num_threads()).max(1);
+
+    let replicas = batch
+        .inputs
+        .par_chunks(chunk)
+        .zip(batch.targets.par_chunks(chunk))
+        .enumerate()
+        .map(|(c, (xs, ys))| {
+            let replica = Mlp::new_seeded(sizes[0], &sizes[1..], 0)
+                .with_activation(activation.clone())
+                .quantization_aware_layers(&quantized);
+            replica.load_parameters(&values)?;
+
+            let ypred = xs
+                .iter()
+                .map(|x| replica.predict(&inputs(x)))
+                .collect::<nn::Result<Vec<_>>>()?;
+
+            let weights = weights.map(|w| &w[c * chunk..c * chunk + xs.len()]);
+            let loss = loss.compute(ys, &ypred, weights)? * Value::new(scale, "1/n");
+            loss.backpropagate();
+
+            let gradients: Vec<_> = replica.parameters().iter().map(Value::gradient).collect();
+
+            Ok((loss.value(), gradients))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut total = 0.0;
+    for (loss, gradients) in replicas {
+        total += loss;
+
+        for (parameter, gradient) in parameters.iter().zip(gradients) {
+            parameter.set_gradient(parameter.gradient() + gradient);
+        }
+    }
+
+    Ok(Value::new(total, "loss"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backward;
+    use crate::{
+        data::{Batch, InMemoryDataset},
+        loss::{Loss, Reduction},
+        nn::Mlp,
+        optim::Sgd,
+        train::{inputs, TrainConfig, Trainer},
+    };
+
+    #[test]
+    fn matches_serial_gradients() {
+        let batch = Batch {
+            indices: (0..5).collect(),
+            inputs: (0..5)
+                .map(|i| vec![i as f64 / 5.0, 1.0 - i as f64])
+                .collect(),
+            targets: (0..5).map(|i| vec![(i % 2) as f64]).collect(),
+        };
+        let weights = [1.0, 2.0, 0.5, 1.0, 3.0];
+
+        let serial = Mlp::new_seeded(2, &[3, 1], 1);
+        let ypred: Vec<_> = batch
+            .inputs
+            .iter()
+            .map(|x| serial.predict(&inputs(x)).expect("should predict"))
+            .collect();
+        let expected = Loss::SquaredError
+            .compute(&batch.targets, &ypred, Some(&weights))
+            .expect("should compute loss");
+        expected.backpropagate();
+
+        let parallel = Mlp::new_seeded(2, &[3, 1], 1);
+        let loss = backward(
+            &parallel,
+            &Loss::SquaredError,
+            &batch,
+            Some(&weights),
+            Reduction::Sum,
+        )
+        .expect("should compute loss");
+
+        assert!((loss.value() - expected.value()).abs() < 1e-12);
+
+        for (p, s) in parallel.parameters().iter().zip(serial.parameters()) {
+            assert!((p.gradient() - s.gradient()).abs() < 1e-12);
+        }
+
+        assert!(backward(
+            &parallel,
+            &Loss::SquaredError,
+            &batch,
+            Some(&[1.0]),
+            Reduction::Sum
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn quantization_aware() {
+        let data = InMemoryDataset::from(
+            (0..10)
+                .map(|i| (vec![i as f64 / 10.0, 1.0], vec![(i % 2) as f64]))
+                .collect::<Vec<_>>(),
+        );
+        let batch = Batch {
+            indices: vec![0, 1],
+            inputs: vec![vec![0.3, 1.0], vec![-0.7, 0.2]],
+            targets: vec![vec![1.0], vec![-1.0]],
+        };
+
+        // fake-quantized weights change the gradients, which replicas have to match
+        let serial = Mlp::new_seeded(2, &[4, 1], 1).quantization_aware();
+        let ypred: Vec<_> = batch
+            .inputs
+            .iter()
+            .map(|x| serial.predict(&inputs(x)).expect("should predict"))
+            .collect();
+        Loss::SquaredError
+            .compute(&batch.targets, &ypred, None)
+            .expect("should compute loss")
+            .backpropagate();
+
+        let parallel = Mlp::new_seeded(2, &[4, 1], 1).quantization_aware();
+        backward(&parallel, &Loss::SquaredError, &batch, None, Reduction::Sum)
+            .expect("should compute loss");
+
+        for (p, s) in parallel.parameters().iter().zip(serial.parameters()) {
+            assert!((p.gradient() - s.gradient()).abs() < 1e-12);
+        }
+
+        let trainer = |workers: Option<usize>| {
+            let mlp = Mlp::new_seeded(2, &[4, 1], 1).quantization_aware_layers(&[0]);
+            let sgd = Sgd::new(mlp.parameters(), 0.1);
+            let trainer = Trainer::new(mlp, sgd, Loss::SquaredError).config(TrainConfig {
+                batch_size: Some(4),
+                ..Default::default()
+            });
+
+            match workers {
+                Some(workers) => trainer.data_parallel(workers),
+                None => trainer,
+            }
+        };
+
+        let expected = trainer(None).fit(&data, 3).expect("should train");
+        let history = trainer(Some(2)).fit(&data, 3).expect("should train");
+
+        for (loss, expected) in history.loss.iter().zip(&expected.loss) {
+            assert!((loss - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn mean_reduction() {
+        let batch = Batch {
+            indices: vec![0, 1],
+            inputs: vec![vec![1.0], vec![-1.0]],
+            targets: vec![vec![0.5], vec![0.5]],
+        };
+        let model = Mlp::new_seeded(1, &[1], 3);
+
+        let sum = backward(&model, &Loss::SquaredError, &batch, None, Reduction::Sum)
+            .expect("should compute loss");
+        let mean = backward(&model, &Loss::SquaredError, &batch, None, Reduction::Mean)
+            .expect("should compute loss");
+
+        assert!((mean.value() - sum.value() / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn data_parallel_matches_serial() {
+        let data = InMemoryDataset::from(
+            (0..10)
+                .map(|i| (vec![i as f64 / 10.0, 1.0], vec![(i % 2) as f64]))
+                .collect::<Vec<_>>(),
+        );
+        let config = TrainConfig {
+            batch_size: Some(4),
+            reduction: Reduction::Mean,
+            ..Default::default()
+        };
+
+        let trainer = |workers: Option<usize>| {
+            let mlp = Mlp::new_seeded(2, &[4, 1], 1);
+            let sgd = Sgd::new(mlp.parameters(), 0.1);
+            let trainer = Trainer::new(mlp, sgd, Loss::SquaredError).config(config.clone());
+
+            match workers {
+                Some(workers) => trainer.data_parallel(workers),
+                None => trainer,
+            }
+        };
+
+        let mut serial = trainer(None);
+        let mut parallel = trainer(Some(3));
+
+        let expected = serial.fit(&data, 5).expect("should train");
+        let history = parallel.fit(&data, 5).expect("should train");
+
+        for (loss, expected) in history.loss.iter().zip(&expected.loss) {
+            assert!((loss - expected).abs() < 1e-9);
+        }
+
+        for (p, s) in parallel
+            .model()
+            .parameters()
+            .iter()
+            .zip(serial.model().parameters())
+        {
+            assert!((p.value() - s.value()).abs() < 1e-9);
+        }
+    }
+}
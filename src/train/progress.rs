@@ -0,0 +1,159 @@
+use indicatif::{ProgressBar, ProgressStyle};
+
+use super::callback::{BatchLog, Callback, EpochLog, TrainState};
+use crate::{loss::Reduction, train::History};
+
+/// Shows a terminal progress bar over all batches of a `fit`, with the current
+/// epoch, running loss and estimated time remaining
+#[derive(Debug, Default)]
+pub struct TrainingProgress {
+    bar: Option<ProgressBar>,
+    // summed losses and samples of the batches of the current epoch so far
+    epoch_loss: f64,
+    epoch_samples: usize,
+}
+
+impl TrainingProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bar(&mut self, state: &TrainState) -> &ProgressBar {
+        self.bar.get_or_insert_with(|| {
+            let epochs = state.end_epoch.saturating_sub(state.epoch);
+            let bar = ProgressBar::new((epochs * state.batches) as u64);
+
+            bar.set_style(
+                ProgressStyle::with_template(
+                    "{bar:40} {pos}/{len} batches [{elapsed_precise} < {eta_precise}] {msg}",
+                )
+                .expect("template should be valid"),
+            );
+
+            bar
+        })
+    }
+}
+
+impl Callback for TrainingProgress {
+    fn on_epoch_start(&mut self, state: &TrainState) {
+        self.epoch_loss = 0.0;
+        self.epoch_samples = 0;
+        self.bar(state);
+    }
+
+    fn on_batch_end(&mut self, log: &BatchLog, state: &TrainState) {
+        // the running loss of the epoch, reduced like its final one
+        let loss = match state.reduction {
+            Reduction::Sum => {
+                self.epoch_loss += log.loss;
+                self.epoch_loss
+            }
+            Reduction::Mean => {
+                self.epoch_loss += log.loss * log.samples as f64;
+                self.epoch_loss / (self.epoch_samples + log.samples).max(1) as f64
+            }
+        };
+        self.epoch_samples += log.samples;
+
+        let message = message(state.epoch, state.end_epoch, loss);
+
+        let bar = self.bar(state);
+        bar.set_message(message);
+        bar.inc(1);
+    }
+
+    fn on_epoch_end(&mut self, log: &EpochLog, state: &TrainState) {
+        let message = message(log.epoch, state.end_epoch, log.loss);
+
+        self.bar(state).set_message(message);
+    }
+
+    fn on_train_end(&mut self, _history: &History, _state: &TrainState) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish();
+        }
+    }
+}
+
+fn message(epoch: usize, end_epoch: usize, loss: f64) -> String {
+    format!("epoch {}/{end_epoch}, loss {loss:.6}", epoch + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use indicatif::{FormattedDuration, ProgressDrawTarget};
+
+    use super::TrainingProgress;
+    use crate::{
+        loss::Reduction,
+        nn::Mlp,
+        optim::Sgd,
+        train::{
+            callback::{BatchLog, Callback, EpochLog, TrainState},
+            History,
+        },
+    };
+
+    #[test]
+    fn reports_progress() {
+        let mlp = Mlp::new_seeded(2, &[1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.1);
+        let state = |epoch| TrainState {
+            epoch,
+            end_epoch: 3,
+            batches: 2,
+            reduction: Reduction::Mean,
+            model: &mlp,
+            optimizer: &sgd,
+        };
+        let batch = |batch, samples, loss| BatchLog {
+            batch,
+            samples,
+            loss,
+            ..Default::default()
+        };
+
+        let mut progress = TrainingProgress::new();
+        progress.on_epoch_start(&state(1));
+        let bar = progress.bar.clone().expect("should show a bar");
+        bar.set_draw_target(ProgressDrawTarget::hidden());
+
+        // epochs before the one `fit` started at aren't counted
+        assert_eq!(bar.length(), Some(4));
+
+        // the running mean over the samples seen, like the epoch loss
+        progress.on_batch_end(&batch(0, 3, 0.25), &state(1));
+        assert_eq!(bar.message(), "epoch 2/3, loss 0.250000");
+        progress.on_batch_end(&batch(1, 1, 0.5), &state(1));
+        assert_eq!(bar.position(), 2);
+        assert_eq!(bar.message(), "epoch 2/3, loss 0.312500");
+
+        let log = EpochLog {
+            epoch: 1,
+            loss: 0.3125,
+            ..Default::default()
+        };
+        progress.on_epoch_end(&log, &state(1));
+        assert_eq!(bar.message(), "epoch 2/3, loss 0.312500");
+
+        // a new epoch starts its running loss over, summed losses add up
+        let sum = TrainState {
+            reduction: Reduction::Sum,
+            ..state(2)
+        };
+        progress.on_epoch_start(&sum);
+        progress.on_batch_end(&batch(0, 3, 0.125), &sum);
+        assert_eq!(bar.message(), "epoch 3/3, loss 0.125000");
+        progress.on_batch_end(&batch(1, 1, 0.5), &sum);
+        assert_eq!(bar.message(), "epoch 3/3, loss 0.625000");
+
+        // rendered like `{eta_precise}`
+        let eta = FormattedDuration(bar.eta()).to_string();
+        assert_eq!(eta.len(), "00:00:00".len());
+
+        progress.on_train_end(&History::default(), &sum);
+        assert!(bar.is_finished());
+        assert!(progress.bar.is_none());
+    }
+}
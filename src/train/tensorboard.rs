@@ -0,0 +1,240 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::callback::{Callback, EpochLog, TrainState};
+use crate::protobuf::{encode_bytes, encode_double, encode_float, encode_varint_field};
+
+/// Writes the epoch loss, metrics, learning rate and gradient norms as scalar
+/// summaries into a TensorBoard event file, so runs can be viewed with
+/// `tensorboard --logdir <dir>`.
+///
+/// Write failures are reported on stderr once, after which logging stops.
+pub struct TensorBoardLogger {
+    writer: Option<EventWriter>,
+}
+
+impl TensorBoardLogger {
+    /// Creates a new event file in `dir`, creating the directory if needed
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        Ok(Self {
+            writer: Some(EventWriter::create(dir)?),
+        })
+    }
+
+    /// Path of the event file being written
+    pub fn path(&self) -> Option<&Path> {
+        self.writer.as_ref().map(|writer| writer.path.as_path())
+    }
+}
+
+impl Callback for TensorBoardLogger {
+    fn on_epoch_end(&mut self, log: &EpochLog, state: &TrainState) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+
+        let layer_tags: Vec<_> = (0..log.layer_grad_norms.len())
+            .map(|layer| format!("grad_norm/layer_{layer}"))
+            .collect();
+
+        let scalars = [
+            ("loss", log.loss),
+            ("learning_rate", state.optimizer.learning_rate()),
+            ("grad_norm", log.grad_norm),
+        ]
+        .into_iter()
+        .chain(
+            layer_tags
+                .iter()
+                .map(String::as_str)
+                .zip(log.layer_grad_norms.iter().copied()),
+        )
+        .chain(
+            log.metrics
+                .iter()
+                .map(|(name, value)| (name.as_str(), *value)),
+        );
+
+        if let Err(err) = writer.write_scalars(log.epoch as i64, scalars) {
+            eprintln!("Failed to write TensorBoard events: {err}");
+            self.writer = None;
+        }
+    }
+}
+
+struct EventWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl EventWriter {
+    fn create<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = dir.as_ref().join(format!(
+            "events.out.tfevents.{}.micrograd.{}",
+            now.as_secs(),
+            std::process::id()
+        ));
+
+        let mut writer = Self {
+            file: BufWriter::new(File::create(&path)?),
+            path,
+        };
+
+        // every event file starts with its version
+        let mut event = Vec::new();
+        encode_double(&mut event, 1, wall_time());
+        encode_bytes(&mut event, 3, b"brain.Event:2");
+        writer.write_record(&event)?;
+        writer.file.flush()?;
+
+        Ok(writer)
+    }
+
+    fn write_scalars<'a>(
+        &mut self,
+        step: i64,
+        scalars: impl Iterator<Item = (&'a str, f64)>,
+    ) -> io::Result<()> {
+        let mut summary = Vec::new();
+
+        for (tag, value) in scalars {
+            let mut entry = Vec::new();
+            encode_bytes(&mut entry, 1, tag.as_bytes());
+            encode_float(&mut entry, 2, value as f32);
+
+            encode_bytes(&mut summary, 1, &entry);
+        }
+
+        let mut event = Vec::new();
+        encode_double(&mut event, 1, wall_time());
+        encode_varint_field(&mut event, 2, step as u64);
+        encode_bytes(&mut event, 5, &summary);
+
+        self.write_record(&event)?;
+        self.file.flush()
+    }
+
+    // TFRecord framing: length, masked CRC of the length, data, masked CRC of the data
+    fn write_record(&mut self, data: &[u8]) -> io::Result<()> {
+        let length = (data.len() as u64).to_le_bytes();
+
+        self.file.write_all(&length)?;
+        self.file.write_all(&masked_crc32c(&length).to_le_bytes())?;
+        self.file.write_all(data)?;
+        self.file.write_all(&masked_crc32c(data).to_le_bytes())
+    }
+}
+
+fn wall_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in data {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+
+    crc.rotate_right(15).wrapping_add(0xA282_EAD8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{crc32c, masked_crc32c, TensorBoardLogger};
+    use crate::{
+        loss::Reduction,
+        nn::Mlp,
+        optim::Sgd,
+        train::callback::{Callback, EpochLog, TrainState},
+    };
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn writes_records() {
+        let dir =
+            std::env::temp_dir().join(format!("micrograd-tensorboard-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mlp = Mlp::new_seeded(2, &[1], 1);
+        let sgd = Sgd::new(mlp.parameters(), 0.1);
+
+        let mut logger = TensorBoardLogger::new(&dir).expect("should create event file");
+        let path = logger.path().expect("should have a path").to_path_buf();
+
+        let state = TrainState {
+            epoch: 0,
+            end_epoch: 1,
+            batches: 1,
+            reduction: Reduction::Sum,
+            model: &mlp,
+            optimizer: &sgd,
+        };
+        let log = EpochLog {
+            epoch: 0,
+            loss: 0.5,
+            metrics: BTreeMap::from([("accuracy".to_string(), 1.0)]),
+            grad_norm: 0.1,
+            layer_grad_norms: vec![0.1],
+            ..Default::default()
+        };
+        logger.on_epoch_end(&log, &state);
+
+        let bytes = std::fs::read(&path).expect("should read event file");
+        std::fs::remove_dir_all(&dir).expect("should clean up");
+
+        let mut records = Vec::new();
+        let mut rest = bytes.as_slice();
+
+        while !rest.is_empty() {
+            let length = u64::from_le_bytes(rest[..8].try_into().unwrap()) as usize;
+            let length_crc = u32::from_le_bytes(rest[8..12].try_into().unwrap());
+            let data = &rest[12..12 + length];
+            let data_crc = u32::from_le_bytes(rest[12 + length..16 + length].try_into().unwrap());
+
+            assert_eq!(length_crc, masked_crc32c(&rest[..8]));
+            assert_eq!(data_crc, masked_crc32c(data));
+
+            records.push(data.to_vec());
+            rest = &rest[16 + length..];
+        }
+
+        assert_eq!(records.len(), 2);
+        assert!(records[0].windows(13).any(|w| w == b"brain.Event:2"));
+        assert!(records[1].windows(8).any(|w| w == b"accuracy"));
+        assert!(records[1].windows(13).any(|w| w == b"learning_rate"));
+        assert!(records[1].windows(17).any(|w| w == b"grad_norm/layer_0"));
+    }
+}
@@ -0,0 +1,621 @@
+mod lr_finder;
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error as ThisError;
+
+use crate::train::{self, callback::Mode};
+pub use lr_finder::{lr_finder, LrCurve, LrFinder};
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Train(#[from] train::Error),
+    #[error("No {1} parameter named '{0}'")]
+    MissingParameter(String, &'static str),
+    #[error("Parameter '{0}' has no values to search")]
+    EmptyParameter(String),
+    #[error("Parameter '{0}' is continuous and can't be enumerated in a grid")]
+    NotEnumerable(String),
+    #[error("Invalid range {0} to {1}")]
+    InvalidRange(f64, f64),
+    #[error("No data to sweep the learning rate on")]
+    EmptyData,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A single hyperparameter value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Float(f64),
+    Int(usize),
+    /// Layer sizes, e.g. the hidden layers of an `Mlp`
+    Sizes(Vec<usize>),
+    /// A named option, e.g. an activation or loss
+    Choice(String),
+}
+
+impl Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Param::Float(value) => write!(f, "{value}"),
+            Param::Int(value) => write!(f, "{value}"),
+            Param::Sizes(sizes) => write!(f, "{sizes:?}"),
+            Param::Choice(choice) => write!(f, "{choice}"),
+        }
+    }
+}
+
+/// Hyperparameters of one trial, by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    values: BTreeMap<String, Param>,
+}
+
+impl Params {
+    pub fn get(&self, name: &str) -> Option<&Param> {
+        self.values.get(name)
+    }
+
+    pub fn float(&self, name: &str) -> Result<f64> {
+        match self.get(name) {
+            Some(Param::Float(value)) => Ok(*value),
+            _ => Err(Error::MissingParameter(name.to_string(), "float")),
+        }
+    }
+
+    pub fn int(&self, name: &str) -> Result<usize> {
+        match self.get(name) {
+            Some(Param::Int(value)) => Ok(*value),
+            _ => Err(Error::MissingParameter(name.to_string(), "integer")),
+        }
+    }
+
+    pub fn sizes(&self, name: &str) -> Result<&[usize]> {
+        match self.get(name) {
+            Some(Param::Sizes(sizes)) => Ok(sizes),
+            _ => Err(Error::MissingParameter(name.to_string(), "sizes")),
+        }
+    }
+
+    pub fn choice(&self, name: &str) -> Result<&str> {
+        match self.get(name) {
+            Some(Param::Choice(choice)) => Ok(choice),
+            _ => Err(Error::MissingParameter(name.to_string(), "choice")),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Param)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+/// Where the values of a hyperparameter come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Domain {
+    /// A fixed set of values, sampled uniformly by random search
+    Values(Vec<Param>),
+    /// Floats drawn uniformly from `[low, high)`, `low` at most `high`
+    Uniform { low: f64, high: f64 },
+    /// Floats whose logarithm is uniform in `[ln low, ln high)`, e.g. learning
+    /// rates, `low` positive and at most `high`
+    LogUniform { low: f64, high: f64 },
+    /// Integers drawn uniformly from `low..=high`, without values when `low`
+    /// is above `high`
+    IntRange { low: usize, high: usize },
+}
+
+impl Domain {
+    fn sample<R: Rng>(&self, name: &str, rng: &mut R) -> Result<Param> {
+        Ok(match self {
+            Domain::Values(values) if values.is_empty() => {
+                return Err(Error::EmptyParameter(name.to_string()))
+            }
+            Domain::IntRange { low, high } if low > high => {
+                return Err(Error::EmptyParameter(name.to_string()))
+            }
+            Domain::Uniform { low, high }
+                if !(low.is_finite() && high.is_finite() && low <= high) =>
+            {
+                return Err(Error::InvalidRange(*low, *high))
+            }
+            Domain::LogUniform { low, high }
+                if !(*low > 0.0 && high.is_finite() && low <= high) =>
+            {
+                return Err(Error::InvalidRange(*low, *high))
+            }
+            Domain::Values(values) => values[rng.gen_range(0..values.len())].clone(),
+            Domain::Uniform { low, high } => Param::Float(low + rng.gen::<f64>() * (high - low)),
+            Domain::LogUniform { low, high } => {
+                let (low, high) = (low.ln(), high.ln());
+
+                Param::Float((low + rng.gen::<f64>() * (high - low)).exp())
+            }
+            Domain::IntRange { low, high } => Param::Int(rng.gen_range(*low..=*high)),
+        })
+    }
+}
+
+/// Named hyperparameters and the values or distributions to search them in
+#[derive(Debug, Clone, Default)]
+pub struct SearchSpace {
+    params: Vec<(String, Domain)>,
+}
+
+impl SearchSpace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn floats(self, name: &str, values: &[f64]) -> Self {
+        self.values(name, values.iter().map(|v| Param::Float(*v)).collect())
+    }
+
+    pub fn ints(self, name: &str, values: &[usize]) -> Self {
+        self.values(name, values.iter().map(|v| Param::Int(*v)).collect())
+    }
+
+    pub fn sizes(self, name: &str, values: &[&[usize]]) -> Self {
+        self.values(
+            name,
+            values.iter().map(|v| Param::Sizes(v.to_vec())).collect(),
+        )
+    }
+
+    pub fn choices(self, name: &str, values: &[&str]) -> Self {
+        self.values(
+            name,
+            values
+                .iter()
+                .map(|v| Param::Choice(v.to_string()))
+                .collect(),
+        )
+    }
+
+    pub fn values(self, name: &str, values: Vec<Param>) -> Self {
+        self.domain(name, Domain::Values(values))
+    }
+
+    pub fn uniform(self, name: &str, low: f64, high: f64) -> Self {
+        self.domain(name, Domain::Uniform { low, high })
+    }
+
+    pub fn log_uniform(self, name: &str, low: f64, high: f64) -> Self {
+        self.domain(name, Domain::LogUniform { low, high })
+    }
+
+    pub fn int_range(self, name: &str, low: usize, high: usize) -> Self {
+        self.domain(name, Domain::IntRange { low, high })
+    }
+
+    /// Declares a parameter, replacing any previous declaration of the same name
+    pub fn domain(mut self, name: &str, domain: Domain) -> Self {
+        self.params.retain(|(existing, _)| existing != name);
+        self.params.push((name.to_string(), domain));
+        self
+    }
+
+    /// Every combination of parameter values, the last declared parameter
+    /// changing fastest. Integer ranges are enumerated, other distributions
+    /// can't be.
+    pub fn grid(&self) -> Result<Vec<Params>> {
+        let mut grid = vec![Params::default()];
+
+        for (name, domain) in &self.params {
+            let values = match domain {
+                Domain::Values(values) if values.is_empty() => {
+                    return Err(Error::EmptyParameter(name.clone()))
+                }
+                Domain::IntRange { low, high } if low > high => {
+                    return Err(Error::EmptyParameter(name.clone()))
+                }
+                Domain::Values(values) => values.clone(),
+                Domain::IntRange { low, high } => (*low..=*high).map(Param::Int).collect(),
+                Domain::Uniform { .. } | Domain::LogUniform { .. } => {
+                    return Err(Error::NotEnumerable(name.clone()))
+                }
+            };
+
+            grid = grid
+                .into_iter()
+                .flat_map(|params| {
+                    values.iter().map(move |value| {
+                        let mut params = params.clone();
+                        params.values.insert(name.clone(), value.clone());
+                        params
+                    })
+                })
+                .collect();
+        }
+
+        Ok(grid)
+    }
+
+    /// Draws a value of every parameter from its domain
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Result<Params> {
+        let values = self
+            .params
+            .iter()
+            .map(|(name, domain)| Ok((name.clone(), domain.sample(name, rng)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(Params { values })
+    }
+}
+
+/// Hyperparameters of a trial and the score the objective gave them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trial {
+    pub params: Params,
+    pub score: f64,
+}
+
+/// Trials of a search ranked from the best to the worst score
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResults {
+    pub trials: Vec<Trial>,
+}
+
+impl SearchResults {
+    fn ranked(mut trials: Vec<Trial>, mode: Mode) -> Self {
+        // NaN scores, e.g. of diverged runs, rank last
+        trials.sort_by(|a, b| match (a.score.is_nan(), b.score.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => match mode {
+                Mode::Min => a.score.total_cmp(&b.score),
+                Mode::Max => b.score.total_cmp(&a.score),
+            },
+        });
+
+        Self { trials }
+    }
+
+    pub fn best(&self) -> Option<&Trial> {
+        self.trials.first()
+    }
+}
+
+impl Display for SearchResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self
+            .trials
+            .first()
+            .map(|trial| trial.params.iter().map(|(name, _)| name).collect())
+            .unwrap_or_default();
+
+        let rows: Vec<Vec<String>> = self
+            .trials
+            .iter()
+            .enumerate()
+            .map(|(rank, trial)| {
+                [(rank + 1).to_string(), format!("{:.6}", trial.score)]
+                    .into_iter()
+                    .chain(names.iter().map(|name| {
+                        trial
+                            .params
+                            .get(name)
+                            .map(Param::to_string)
+                            .unwrap_or_default()
+                    }))
+                    .collect()
+            })
+            .collect();
+
+        let header: Vec<_> = ["rank", "score"].into_iter().chain(names).collect();
+        let widths: Vec<_> = header
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                rows.iter()
+                    .map(|row| row[i].len())
+                    .chain([name.len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let header: Vec<_> = header.iter().map(|name| name.to_string()).collect();
+
+        for row in [&header].into_iter().chain(&rows) {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:>width$}"))
+                .collect();
+
+            writeln!(f, "{}", cells.join("  "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Exhaustive search over every combination of the values in `space`.
+///
+/// The objective typically builds and trains a `Trainer` with the given
+/// parameters and returns its validation loss or metric. Trials are ranked
+/// lowest score first with `Mode::Min` and highest first with `Mode::Max`.
+pub fn grid_search<F>(space: &SearchSpace, mode: Mode, objective: F) -> Result<SearchResults>
+where
+    F: FnMut(&Params) -> Result<f64>,
+{
+    run_trials(space.grid()?, mode, objective)
+}
+
+/// Evaluates `n_trials` parameter sets drawn from the distributions in `space`.
+///
+/// Samples are fully determined by `seed`. For continuous parameters such as
+/// the learning rate this usually finds good values with far fewer trials than
+/// a grid. Trials are ranked like in `grid_search`.
+pub fn random_search<F>(
+    space: &SearchSpace,
+    n_trials: usize,
+    seed: u64,
+    mode: Mode,
+    objective: F,
+) -> Result<SearchResults>
+where
+    F: FnMut(&Params) -> Result<f64>,
+{
+    run_trials(random_samples(space, n_trials, seed)?, mode, objective)
+}
+
+/// Like `grid_search`, running the trials concurrently on the rayon thread
+/// pool.
+///
+/// The objective is shared between threads, so it can't mutate captured state.
+/// Results are the same as those of a serial search, and when trials fail, the
+/// error of the first failing trial of the grid is returned.
+#[cfg(feature = "parallel")]
+pub fn par_grid_search<F>(space: &SearchSpace, mode: Mode, objective: F) -> Result<SearchResults>
+where
+    F: Fn(&Params) -> Result<f64> + Sync,
+{
+    par_run_trials(space.grid()?, mode, objective)
+}
+
+/// Like `random_search`, running the trials concurrently on the rayon thread
+/// pool, see `par_grid_search`
+#[cfg(feature = "parallel")]
+pub fn par_random_search<F>(
+    space: &SearchSpace,
+    n_trials: usize,
+    seed: u64,
+    mode: Mode,
+    objective: F,
+) -> Result<SearchResults>
+where
+    F: Fn(&Params) -> Result<f64> + Sync,
+{
+    par_run_trials(random_samples(space, n_trials, seed)?, mode, objective)
+}
+
+fn random_samples(space: &SearchSpace, n_trials: usize, seed: u64) -> Result<Vec<Params>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..n_trials).map(|_| space.sample(&mut rng)).collect()
+}
+
+fn run_trials<F>(samples: Vec<Params>, mode: Mode, mut objective: F) -> Result<SearchResults>
+where
+    F: FnMut(&Params) -> Result<f64>,
+{
+    let trials = samples
+        .into_iter()
+        .map(|params| {
+            let score = objective(&params)?;
+
+            Ok(Trial { params, score })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(SearchResults::ranked(trials, mode))
+}
+
+#[cfg(feature = "parallel")]
+fn par_run_trials<F>(samples: Vec<Params>, mode: Mode, objective: F) -> Result<SearchResults>
+where
+    F: Fn(&Params) -> Result<f64> + Sync,
+{
+    use rayon::prelude::*;
+
+    // collected in sample order first, so that the reported error doesn't
+    // depend on scheduling
+    let trials: Vec<_> = samples
+        .into_par_iter()
+        .map(|params| {
+            let score = objective(&params)?;
+
+            Ok(Trial { params, score })
+        })
+        .collect();
+
+    Ok(SearchResults::ranked(
+        trials.into_iter().collect::<Result<_>>()?,
+        mode,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{grid_search, random_search, Error, Param, SearchSpace};
+    use crate::{
+        data::InMemoryDataset,
+        loss::Loss,
+        nn::Mlp,
+        optim::Sgd,
+        train::{callback::Mode, Trainer},
+    };
+
+    #[test]
+    fn grid() {
+        let grid = SearchSpace::new()
+            .floats("learning_rate", &[0.1, 0.01])
+            .sizes("hidden", &[&[4], &[4, 4], &[8]])
+            .grid()
+            .expect("should enumerate");
+
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[1].float("learning_rate").unwrap(), 0.1);
+        assert_eq!(grid[1].sizes("hidden").unwrap(), &[4, 4]);
+        assert_eq!(grid[3].float("learning_rate").unwrap(), 0.01);
+        assert!(matches!(
+            grid[0].choice("activation"),
+            Err(Error::MissingParameter(..))
+        ));
+
+        let empty = SearchSpace::new().values("empty", vec![]).grid();
+        assert!(matches!(empty, Err(Error::EmptyParameter(_))));
+
+        // an inverted range fails the same way in both searches
+        let inverted = SearchSpace::new().int_range("width", 8, 2);
+        assert!(matches!(inverted.grid(), Err(Error::EmptyParameter(_))));
+        let sampled = random_search(&inverted, 1, 1, Mode::Max, |_| Ok(0.0));
+        assert!(matches!(sampled, Err(Error::EmptyParameter(_))));
+
+        for (space, low, high) in [
+            (SearchSpace::new().uniform("dropout", 0.5, 0.1), 0.5, 0.1),
+            (
+                SearchSpace::new().log_uniform("learning_rate", 0.0, 0.1),
+                0.0,
+                0.1,
+            ),
+            (
+                SearchSpace::new().log_uniform("learning_rate", 0.1, 1e-3),
+                0.1,
+                1e-3,
+            ),
+        ] {
+            let sampled = random_search(&space, 1, 1, Mode::Max, |_| Ok(0.0));
+            assert!(matches!(sampled, Err(Error::InvalidRange(l, h)) if l == low && h == high));
+        }
+    }
+
+    #[test]
+    fn search_ranks_trials() {
+        let dataset = InMemoryDataset::from(vec![
+            (vec![2.0, 3.0, -1.0], vec![1.0]),
+            (vec![3.0, -1.0, 0.5], vec![-1.0]),
+            (vec![0.5, 1.0, 1.0], vec![-1.0]),
+            (vec![1.0, 1.0, -1.0], vec![1.0]),
+        ]);
+        let space = SearchSpace::new()
+            .floats("learning_rate", &[0.0, 0.05])
+            .sizes("hidden", &[&[4], &[4, 4]]);
+
+        let results = grid_search(&space, Mode::Min, |params| {
+            let mut sizes = params.sizes("hidden")?.to_vec();
+            sizes.push(1);
+
+            let mlp = Mlp::new_seeded(3, &sizes, 1);
+            let sgd = Sgd::new(mlp.parameters(), params.float("learning_rate")?);
+
+            let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError);
+            trainer.fit(&dataset, 50)?;
+
+            Ok(trainer.compute_loss(&dataset)?)
+        })
+        .expect("should search");
+
+        assert_eq!(results.trials.len(), 4);
+        assert!(results
+            .trials
+            .windows(2)
+            .all(|pair| pair[0].score <= pair[1].score));
+
+        let best = results.best().expect("should have a best trial");
+        assert_eq!(best.params.get("learning_rate"), Some(&Param::Float(0.05)));
+
+        let table = results.to_string();
+        assert!(table.starts_with("rank"));
+        assert_eq!(table.lines().count(), 5);
+    }
+
+    #[test]
+    fn random_search_samples_distributions() {
+        let space = SearchSpace::new()
+            .log_uniform("learning_rate", 1e-4, 1e-1)
+            .int_range("width", 2, 8)
+            .choices("activation", &["tanh", "relu"]);
+
+        assert!(matches!(space.grid(), Err(Error::NotEnumerable(_))));
+
+        let mut seen = Vec::new();
+        let results = random_search(&space, 20, 7, Mode::Max, |params| {
+            let learning_rate = params.float("learning_rate")?;
+            let width = params.int("width")?;
+
+            assert!((1e-4..1e-1).contains(&learning_rate));
+            assert!((2..=8).contains(&width));
+            assert!(["tanh", "relu"].contains(&params.choice("activation")?));
+
+            seen.push(params.clone());
+
+            Ok(learning_rate)
+        })
+        .expect("should search");
+
+        assert_eq!(results.trials.len(), 20);
+        assert!(results
+            .trials
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        let again = random_search(&space, 20, 7, Mode::Max, |params| {
+            params.float("learning_rate")
+        })
+        .expect("should search");
+
+        assert_eq!(results, again);
+        assert_ne!(seen[0], seen[1]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_search_matches_serial() {
+        use super::{par_grid_search, par_random_search};
+
+        let space = SearchSpace::new()
+            .floats("learning_rate", &[0.01, 0.05, 0.1])
+            .ints("width", &[2, 4]);
+        let objective = |params: &super::Params| {
+            let dataset = InMemoryDataset::from(vec![
+                (vec![1.0, -1.0], vec![1.0]),
+                (vec![-1.0, 1.0], vec![-1.0]),
+            ]);
+            let mlp = Mlp::new_seeded(2, &[params.int("width")?, 1], 1);
+            let sgd = Sgd::new(mlp.parameters(), params.float("learning_rate")?);
+
+            let mut trainer = Trainer::new(mlp, sgd, Loss::SquaredError);
+            trainer.fit(&dataset, 10)?;
+
+            Ok(trainer.compute_loss(&dataset)?)
+        };
+
+        assert_eq!(
+            par_grid_search(&space, Mode::Min, objective).expect("should search"),
+            grid_search(&space, Mode::Min, objective).expect("should search")
+        );
+        assert_eq!(
+            par_random_search(&space, 5, 3, Mode::Min, objective).expect("should search"),
+            random_search(&space, 5, 3, Mode::Min, objective).expect("should search")
+        );
+
+        let failing = par_grid_search(&space, Mode::Min, |params| {
+            params.choice("width").map(|_| 0.0)
+        });
+        assert!(matches!(failing, Err(Error::MissingParameter(..))));
+    }
+}
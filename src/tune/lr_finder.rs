@@ -0,0 +1,293 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::Range,
+    path::Path,
+};
+
+use super::{Error, Result};
+use crate::{
+    data::{DataLoader, Dataset},
+    loss::{Loss, Reduction},
+    nn::Mlp,
+    optim::{Optimizer, Sgd},
+    train::train_batch,
+};
+
+/// Sweeps the learning rate exponentially from `start` to `end` while training
+/// on one batch per step, to find a range of learning rates worth trying
+#[derive(Debug, Clone)]
+pub struct LrFinder {
+    start: f64,
+    end: f64,
+    steps: usize,
+    batch_size: usize,
+    seed: u64,
+    smoothing: f64,
+    divergence: f64,
+}
+
+impl Default for LrFinder {
+    fn default() -> Self {
+        Self {
+            start: 1e-6,
+            end: 10.0,
+            steps: 200,
+            batch_size: 32,
+            seed: 0,
+            smoothing: 0.9,
+            divergence: 4.0,
+        }
+    }
+}
+
+impl LrFinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Learning rates of the first and the last step, 1e-6 and 10 by default
+    pub fn range(mut self, start: f64, end: f64) -> Self {
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    /// Number of steps of the sweep, 200 by default
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Seed of the order the batches are drawn in
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Weight of the past in the exponential moving average of the loss the
+    /// suggestion is based on, 0.9 by default
+    pub fn smoothing(mut self, smoothing: f64) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Stops the sweep once the smoothed loss exceeds its minimum this many
+    /// times, 4 by default
+    pub fn divergence(mut self, divergence: f64) -> Self {
+        self.divergence = divergence;
+        self
+    }
+
+    /// Runs the sweep, stepping `optimizer`, which should be fresh and hold the
+    /// parameters of `model`. The parameters are restored afterwards, the state
+    /// of the optimizer isn't.
+    pub fn run<D: Dataset + ?Sized>(
+        &self,
+        model: &Mlp,
+        optimizer: &mut dyn Optimizer,
+        data: &D,
+        loss: &Loss,
+    ) -> Result<LrCurve> {
+        if !(self.start > 0.0 && self.start < self.end) {
+            return Err(Error::InvalidRange(self.start, self.end));
+        }
+        if data.is_empty() {
+            return Err(Error::EmptyData);
+        }
+
+        let parameters = model.parameters();
+        let initial: Vec<_> = parameters.iter().map(|p| p.value()).collect();
+        let swept = self.sweep(model, optimizer, data, loss);
+
+        for (parameter, value) in parameters.iter().zip(initial) {
+            parameter.set_value(value);
+        }
+        optimizer.zero_grad();
+
+        swept
+    }
+
+    fn sweep<D: Dataset + ?Sized>(
+        &self,
+        model: &Mlp,
+        optimizer: &mut dyn Optimizer,
+        data: &D,
+        loss: &Loss,
+    ) -> Result<LrCurve> {
+        let factor = (self.end / self.start).powf(1.0 / self.steps.saturating_sub(1).max(1) as f64);
+        let mut loader = DataLoader::new(data, self.batch_size).shuffle(self.seed);
+        let mut batches = loader.epoch();
+        let mut curve = LrCurve::default();
+        let mut average = 0.0;
+        let mut best = f64::INFINITY;
+
+        for step in 0..self.steps {
+            let batch = match batches.next() {
+                Some(batch) => batch,
+                None => {
+                    batches = loader.epoch();
+                    batches.next().ok_or(Error::EmptyData)?
+                }
+            };
+
+            let learning_rate = self.start * factor.powi(step as i32);
+            optimizer.set_learning_rate(learning_rate);
+
+            let loss = train_batch(model, optimizer, loss, &batch, None, Reduction::Mean)?
+                .loss
+                .value();
+            if !loss.is_finite() {
+                break;
+            }
+
+            // bias corrected, so that the first steps aren't pulled towards 0
+            average = self.smoothing * average + (1.0 - self.smoothing) * loss;
+            let smoothed = average / (1.0 - self.smoothing.powi(step as i32 + 1));
+
+            curve.learning_rates.push(learning_rate);
+            curve.losses.push(loss);
+            curve.smoothed_losses.push(smoothed);
+
+            best = best.min(smoothed);
+            if smoothed > self.divergence * best {
+                break;
+            }
+        }
+
+        Ok(curve)
+    }
+}
+
+/// Learning rates of a sweep with the batch loss and the smoothed loss of
+/// every step
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LrCurve {
+    pub learning_rates: Vec<f64>,
+    pub losses: Vec<f64>,
+    pub smoothed_losses: Vec<f64>,
+}
+
+impl LrCurve {
+    pub fn len(&self) -> usize {
+        self.learning_rates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.learning_rates.is_empty()
+    }
+
+    /// Learning rates from the steepest descent of the smoothed loss to its
+    /// minimum. The start is usually a safe choice, the end the most
+    /// aggressive one. `None` if the loss never went down.
+    pub fn suggestion(&self) -> Option<Range<f64>> {
+        let minimum = (0..self.len())
+            .min_by(|&a, &b| self.smoothed_losses[a].total_cmp(&self.smoothed_losses[b]))?;
+
+        let steepest = (0..minimum).min_by(|&a, &b| {
+            let slope = |i: usize| self.smoothed_losses[i + 1] - self.smoothed_losses[i];
+
+            slope(a).total_cmp(&slope(b))
+        })?;
+
+        Some(self.learning_rates[steepest]..self.learning_rates[minimum])
+    }
+
+    /// Writes the curve as CSV with a `step,learning_rate,loss,smoothed_loss`
+    /// header, best plotted with a logarithmic learning rate axis
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        self.write_csv(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes the curve as CSV, see [`LrCurve::to_csv`]
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "step,learning_rate,loss,smoothed_loss")?;
+
+        for i in 0..self.len() {
+            writeln!(
+                writer,
+                "{i},{},{},{}",
+                self.learning_rates[i], self.losses[i], self.smoothed_losses[i]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Sweeps the learning rate of plain SGD on `model` with the defaults of
+/// [`LrFinder`], leaving the model as it was.
+///
+/// ```no_run
+/// # use micrograd::{data::InMemoryDataset, loss::Loss, nn::Mlp, tune};
+/// # let data = InMemoryDataset::from(vec![(vec![1.0], vec![1.0])]);
+/// let model = Mlp::new_seeded(1, &[8, 1], 0);
+/// let curve = tune::lr_finder(&model, &data, &Loss::SquaredError)?;
+///
+/// println!("try a learning rate in {:?}", curve.suggestion());
+/// curve.to_csv("lr.csv")?;
+/// # Ok::<(), micrograd::Error>(())
+/// ```
+pub fn lr_finder<D: Dataset + ?Sized>(model: &Mlp, data: &D, loss: &Loss) -> Result<LrCurve> {
+    let mut optimizer = Sgd::new(model.parameters(), 0.0);
+
+    LrFinder::new().run(model, &mut optimizer, data, loss)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lr_finder, LrFinder};
+    use crate::{data::InMemoryDataset, loss::Loss, nn::Mlp, optim::Sgd, tune::Error};
+
+    #[test]
+    fn sweep() {
+        let dataset = InMemoryDataset::from(
+            (0..32)
+                .map(|i| {
+                    let x = i as f64 / 16.0 - 1.0;
+                    (vec![x], vec![0.8 * x])
+                })
+                .collect::<Vec<_>>(),
+        );
+        let model = Mlp::new_seeded(1, &[4, 1], 3);
+        let before: Vec<_> = model.parameters().iter().map(|p| p.value()).collect();
+
+        let curve = lr_finder(&model, &dataset, &Loss::SquaredError).expect("should sweep");
+
+        assert!(!curve.is_empty() && curve.len() <= 200);
+        assert_eq!(curve.learning_rates[0], 1e-6);
+        let ratio = curve.learning_rates[1] / curve.learning_rates[0];
+        assert!(curve
+            .learning_rates
+            .windows(2)
+            .all(|pair| (pair[1] / pair[0] - ratio).abs() < 1e-9));
+
+        let range = curve.suggestion().expect("should suggest");
+        assert!(1e-6 <= range.start && range.start < range.end && range.end <= 10.0);
+
+        let after: Vec<_> = model.parameters().iter().map(|p| p.value()).collect();
+        assert_eq!(after, before);
+
+        let mut csv = Vec::new();
+        curve.write_csv(&mut csv).expect("should write");
+        let csv = String::from_utf8(csv).expect("should be utf-8");
+        assert!(csv.starts_with("step,learning_rate,loss,smoothed_loss\n0,0.000001,"));
+        assert_eq!(csv.lines().count(), curve.len() + 1);
+
+        let mut sgd = Sgd::new(model.parameters(), 0.0);
+        assert!(matches!(
+            LrFinder::new()
+                .range(1.0, 0.1)
+                .run(&model, &mut sgd, &dataset, &Loss::SquaredError),
+            Err(Error::InvalidRange(..))
+        ));
+    }
+}
@@ -1,9 +1,63 @@
-use std::{
+mod checked;
+mod graph;
+mod recompute;
+#[cfg(feature = "svg")]
+mod svg;
+mod tape;
+
+use alloc::{
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
     cell::RefCell,
+    mem,
     ops::{Add, Mul, Neg, Sub},
-    rc::Rc,
+    sync::atomic::{self, AtomicUsize},
+};
+
+// hashing needs `std`, ordered collections only an allocator
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap as Map, BTreeSet as Set};
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::{
+    cell::Cell,
+    collections::{HashMap as Map, HashSet as Set},
 };
 
+use thiserror::Error as ThisError;
+
+use crate::math;
+
+pub use tape::Tape;
+
+#[derive(ThisError, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("{node} is undefined for {}, {reason}", format_operands(.operands))]
+    Domain {
+        node: String,
+        operands: Vec<(String, f64)>,
+        reason: &'static str,
+    },
+    #[error("Length mismatch, expected {0} values, got {1}")]
+    LengthMismatch(usize, usize),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+fn format_operands(operands: &[(String, f64)]) -> String {
+    operands
+        .iter()
+        .map(|(label, value)| format!("{label} = {value}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(Debug)]
 enum Operation {
     Constant,
@@ -12,6 +66,12 @@ enum Operation {
     Pow(Rc<RefCell<ValueInner>>, f64),
     Multiply(Rc<RefCell<ValueInner>>, Rc<RefCell<ValueInner>>),
     Tanh(Rc<RefCell<ValueInner>>),
+    Exp(Rc<RefCell<ValueInner>>),
+    Ln(Rc<RefCell<ValueInner>>),
+    Sigmoid(Rc<RefCell<ValueInner>>),
+    FakeQuantize(Rc<RefCell<ValueInner>>, f64),
+    Dot(Vec<Rc<RefCell<ValueInner>>>, Vec<Rc<RefCell<ValueInner>>>),
+    Checkpoint(Vec<Rc<RefCell<ValueInner>>>, recompute::Function),
 }
 
 #[derive(Debug)]
@@ -20,43 +80,182 @@ struct ValueInner {
     label: String,
     gradient: f64,
     operation: Operation,
+    // few entries, if any, cheaper to scan than to hash
+    metadata: Vec<(String, String)>,
 }
 
 impl ValueInner {
-    fn backpropagate(&self) {
+    // Value of this node computed from the current values of its children
+    fn compute(&self) -> f64 {
+        match &self.operation {
+            Operation::Constant => self.value,
+            Operation::Add(lhs, rhs) => lhs.borrow().value + rhs.borrow().value,
+            Operation::Sub(lhs, rhs) => lhs.borrow().value - rhs.borrow().value,
+            Operation::Multiply(lhs, rhs) => lhs.borrow().value * rhs.borrow().value,
+            Operation::Pow(it, exponent) => math::powf(it.borrow().value, *exponent),
+            Operation::Tanh(it) => math::tanh(it.borrow().value),
+            Operation::Exp(it) => math::exp(it.borrow().value),
+            Operation::Ln(it) => math::ln(it.borrow().value),
+            Operation::Sigmoid(it) => 1.0 / (1.0 + math::exp(-it.borrow().value)),
+            Operation::FakeQuantize(it, scale) => math::fake_quantize(it.borrow().value, *scale),
+            Operation::Dot(lhs, rhs) => lhs
+                .iter()
+                .zip(rhs)
+                .map(|(l, r)| l.borrow().value * r.borrow().value)
+                .sum(),
+            Operation::Checkpoint(inputs, function) => function.forward(&values(inputs)),
+        }
+    }
+
+    fn children(&self) -> Vec<Rc<RefCell<ValueInner>>> {
+        match &self.operation {
+            Operation::Constant => vec![],
+            Operation::Add(lhs, rhs) | Operation::Sub(lhs, rhs) | Operation::Multiply(lhs, rhs) => {
+                vec![lhs.clone(), rhs.clone()]
+            }
+            Operation::Pow(it, _)
+            | Operation::Tanh(it)
+            | Operation::Exp(it)
+            | Operation::Ln(it)
+            | Operation::Sigmoid(it)
+            | Operation::FakeQuantize(it, _) => vec![it.clone()],
+            Operation::Dot(lhs, rhs) => lhs.iter().chain(rhs).cloned().collect(),
+            Operation::Checkpoint(inputs, _) => inputs.clone(),
+        }
+    }
+
+    // Pushes this node's gradient to its direct children. Callers are responsible
+    // for visiting nodes in topological order, so that shared sub-expressions
+    // have their gradient fully accumulated before it's passed on.
+    fn propagate(&self) {
         match &self.operation {
             Operation::Constant => (),
             Operation::Add(lhs, rhs) => {
                 lhs.borrow_mut().gradient += self.gradient;
                 rhs.borrow_mut().gradient += self.gradient;
-
-                lhs.borrow().backpropagate();
-                rhs.borrow().backpropagate();
             }
             Operation::Sub(lhs, rhs) => {
                 lhs.borrow_mut().gradient += self.gradient;
                 rhs.borrow_mut().gradient -= self.gradient;
-
-                lhs.borrow().backpropagate();
-                rhs.borrow().backpropagate();
             }
             Operation::Multiply(lhs, rhs) => {
-                lhs.borrow_mut().gradient += rhs.borrow().value * self.gradient;
-                rhs.borrow_mut().gradient += lhs.borrow().value * self.gradient;
+                let (lhs_value, rhs_value) = (lhs.borrow().value, rhs.borrow().value);
 
-                lhs.borrow().backpropagate();
-                rhs.borrow().backpropagate();
+                lhs.borrow_mut().gradient += rhs_value * self.gradient;
+                rhs.borrow_mut().gradient += lhs_value * self.gradient;
             }
             Operation::Pow(it, exponent) => {
                 let val = it.borrow().value;
 
-                it.borrow_mut().gradient += (exponent * val.powf(*exponent - 1.0)) * self.gradient;
-                it.borrow().backpropagate()
+                it.borrow_mut().gradient +=
+                    (exponent * math::powf(val, *exponent - 1.0)) * self.gradient;
             }
             Operation::Tanh(it) => {
-                it.borrow_mut().gradient += (1.0 - self.value.powf(2.0)) * self.gradient;
-                it.borrow().backpropagate();
+                it.borrow_mut().gradient += (1.0 - math::powf(self.value, 2.0)) * self.gradient;
+            }
+            Operation::Exp(it) => {
+                it.borrow_mut().gradient += self.value * self.gradient;
             }
+            Operation::Ln(it) => {
+                let val = it.borrow().value;
+
+                it.borrow_mut().gradient += self.gradient / val;
+            }
+            Operation::Sigmoid(it) => {
+                it.borrow_mut().gradient += self.value * (1.0 - self.value) * self.gradient;
+            }
+            // straight through, as if the rounding weren't there
+            Operation::FakeQuantize(it, _) => {
+                it.borrow_mut().gradient += self.gradient;
+            }
+            Operation::Dot(lhs, rhs) => {
+                for (l, r) in lhs.iter().zip(rhs) {
+                    let (l_value, r_value) = (l.borrow().value, r.borrow().value);
+
+                    l.borrow_mut().gradient += r_value * self.gradient;
+                    r.borrow_mut().gradient += l_value * self.gradient;
+                }
+            }
+            Operation::Checkpoint(inputs, function) => {
+                let gradients = function.backward(&values(inputs), self.gradient);
+
+                for (input, gradient) in inputs.iter().zip(gradients) {
+                    input.borrow_mut().gradient += gradient;
+                }
+            }
+        }
+    }
+}
+
+fn values(nodes: &[Rc<RefCell<ValueInner>>]) -> Vec<f64> {
+    nodes.iter().map(|node| node.borrow().value).collect()
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static LAZY: Cell<bool> = const { Cell::new(false) };
+}
+
+#[cfg(feature = "std")]
+fn set_lazy(lazy: bool) -> bool {
+    LAZY.with(|flag| flag.replace(lazy))
+}
+
+#[cfg(feature = "std")]
+fn is_lazy() -> bool {
+    LAZY.with(Cell::get)
+}
+
+// without `std` there are no thread locals, one flag serves the whole program
+#[cfg(not(feature = "std"))]
+static LAZY: AtomicBool = AtomicBool::new(false);
+
+#[cfg(not(feature = "std"))]
+fn set_lazy(lazy: bool) -> bool {
+    LAZY.swap(lazy, Ordering::Relaxed)
+}
+
+#[cfg(not(feature = "std"))]
+fn is_lazy() -> bool {
+    LAZY.load(Ordering::Relaxed)
+}
+
+/// Runs `build` with the arithmetic of new values deferred, so that graphs are
+/// only recorded, until `Value::evaluate` computes them in a single pass.
+///
+/// Applies to values created on the current thread while `build` runs, or
+/// anywhere in the program without the `std` feature.
+pub fn lazy<T>(build: impl FnOnce() -> T) -> T {
+    // restores the previous mode even if `build` panics
+    struct Reset(bool);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            set_lazy(self.0);
+        }
+    }
+
+    let _reset = Reset(set_lazy(true));
+
+    build()
+}
+
+/// Approximate heap usage of graph nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    /// Number of distinct nodes
+    pub nodes: usize,
+    /// Bytes of the nodes, including their reference counts and labels
+    pub bytes: usize,
+}
+
+impl Add for MemoryEstimate {
+    type Output = MemoryEstimate;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        MemoryEstimate {
+            nodes: self.nodes + rhs.nodes,
+            bytes: self.bytes + rhs.bytes,
         }
     }
 }
@@ -74,10 +273,40 @@ impl Value {
                 label: label.to_string(),
                 gradient: 0.0,
                 operation: Operation::Constant,
+                metadata: Vec::new(),
             })),
         }
     }
 
+    /// A constant labeled with a generated short name, `v0`, `v1` and so on,
+    /// e.g. for coefficients of a loss built in code
+    pub fn unlabeled(value: f64) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let id = NEXT.fetch_add(1, atomic::Ordering::Relaxed);
+
+        Self::new(value, &format!("v{id}"))
+    }
+
+    fn from_operation(label: String, operation: Operation) -> Self {
+        let mut inner = ValueInner {
+            value: f64::NAN,
+            label,
+            gradient: 0.0,
+            operation,
+            metadata: Vec::new(),
+        };
+
+        if !is_lazy() {
+            inner.value = inner.compute();
+        }
+
+        Self {
+            inner: Rc::new(RefCell::new(inner)),
+        }
+    }
+
+    /// Current value, `NaN` for values built lazily and not evaluated yet
     pub fn value(&self) -> f64 {
         self.inner.borrow().value
     }
@@ -90,42 +319,231 @@ impl Value {
         self.inner.borrow().label.clone()
     }
 
-    pub fn tanh(self) -> Value {
-        Value {
-            inner: Rc::new(RefCell::new(ValueInner {
-                value: self.inner.borrow().value.tanh(),
-                label: format!("tanh({})", self.inner.borrow().label),
-                gradient: 0.0,
-                operation: Operation::Tanh(self.inner.clone()),
-            })),
+    /// Attaches `value` to this node under `key`, replacing an earlier value,
+    /// e.g. the layer of a parameter or the unit of an input. Included in
+    /// graph exports.
+    pub fn set_metadata(&self, key: &str, value: &str) {
+        let metadata = &mut self.inner.borrow_mut().metadata;
+
+        match metadata.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => metadata.push((key.to_string(), value.to_string())),
         }
     }
 
+    /// Like [`Value::set_metadata`], for building values in one expression
+    pub fn with_metadata(self, key: &str, value: &str) -> Self {
+        self.set_metadata(key, value);
+
+        self
+    }
+
+    pub fn metadata(&self, key: &str) -> Option<String> {
+        self.inner
+            .borrow()
+            .metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    }
+
+    /// Every metadata entry of this node, in the order they were first set
+    pub fn metadata_entries(&self) -> Vec<(String, String)> {
+        self.inner.borrow().metadata.clone()
+    }
+
+    pub fn tanh(self) -> Value {
+        let label = format!("tanh({})", self.inner.borrow().label);
+
+        Value::from_operation(label, Operation::Tanh(self.inner.clone()))
+    }
+
+    pub fn exp(self) -> Value {
+        let label = format!("exp({})", self.inner.borrow().label);
+
+        Value::from_operation(label, Operation::Exp(self.inner.clone()))
+    }
+
+    pub fn ln(self) -> Value {
+        let label = format!("ln({})", self.inner.borrow().label);
+
+        Value::from_operation(label, Operation::Ln(self.inner.clone()))
+    }
+
+    pub fn sigmoid(self) -> Value {
+        let label = format!("sigmoid({})", self.inner.borrow().label);
+
+        Value::from_operation(label, Operation::Sigmoid(self.inner.clone()))
+    }
+
+    /// Rounds to the nearest multiple of `scale` within the 8 bit integer
+    /// range `-127..=127`, like int8 quantization followed by dequantization,
+    /// but passes gradients straight through. Training through it makes a
+    /// model robust to being quantized, see `Mlp::quantization_aware`.
+    pub fn fake_quantize(self, scale: f64) -> Value {
+        let label = format!("fq({})", self.inner.borrow().label);
+
+        Value::from_operation(label, Operation::FakeQuantize(self.inner.clone(), scale))
+    }
+
     pub fn pow(self, exponent: f64) -> Value {
-        Value {
-            inner: Rc::new(RefCell::new(ValueInner {
-                value: self.inner.borrow().value.powf(exponent),
-                label: format!("{}^{}", self.inner.borrow().label, exponent),
-                gradient: 0.0,
-                operation: Operation::Pow(self.inner.clone(), exponent),
-            })),
+        let label = format!("{}^{}", self.inner.borrow().label, exponent);
+
+        Value::from_operation(label, Operation::Pow(self.inner.clone(), exponent))
+    }
+
+    /// Sum of the pairwise products of `lhs` and `rhs` as a single node,
+    /// instead of a chain of multiplications and additions.
+    ///
+    /// Panics if the operands differ in length, see [`Value::try_dot`].
+    pub fn dot(lhs: &[Value], rhs: &[Value]) -> Value {
+        assert_eq!(
+            lhs.len(),
+            rhs.len(),
+            "dot product operands differ in length"
+        );
+
+        let label = lhs
+            .iter()
+            .zip(rhs)
+            .map(|(l, r)| format!("{} * {}", l.inner.borrow().label, r.inner.borrow().label))
+            .collect::<Vec<_>>()
+            .join(" + ");
+
+        Value::from_operation(
+            format!("({label})"),
+            Operation::Dot(
+                lhs.iter().map(|l| l.inner.clone()).collect(),
+                rhs.iter().map(|r| r.inner.clone()).collect(),
+            ),
+        )
+    }
+
+    /// A new leaf with the current value and label of this one, cutting it off
+    /// from its graph, so that nothing built on it backpropagates further
+    pub fn detach(&self) -> Value {
+        let inner = self.inner.borrow();
+
+        Value::new(inner.value, &inner.label)
+    }
+
+    /// Recomputes every node of the graph from its leaves in a single forward
+    /// pass, e.g. after building it with `lazy` or changing leaf values with
+    /// `set_value`, and returns the result
+    pub fn evaluate(&self) -> f64 {
+        for node in self.topological_order() {
+            let value = node.borrow().compute();
+            node.borrow_mut().value = value;
         }
+
+        self.value()
     }
 
     pub fn backpropagate(&self) {
         // Kick off with a gradient of 1
         self.inner.borrow_mut().gradient = 1.0;
 
-        // propagate through the graph
-        self.inner.borrow().backpropagate()
+        // propagate through the graph, parents before children
+        for node in self.topological_order().iter().rev() {
+            node.borrow().propagate();
+        }
+    }
+
+    /// Approximate heap usage of the graph of this value, counting every shared
+    /// node once. Allocator overhead isn't included.
+    pub fn memory_estimate(&self) -> MemoryEstimate {
+        self.topological_order()
+            .iter()
+            .map(|node| node_estimate(&node.borrow()))
+            .fold(MemoryEstimate::default(), Add::add)
+    }
+
+    /// Lowers the graph of this value into a flat tape, which can evaluate and
+    /// differentiate it repeatedly for different values of its leaves
+    pub fn compile(&self) -> Tape {
+        Tape::compile(self)
+    }
+
+    // Nodes of the graph ordered so that every node comes after all of its
+    // children. Iterative, because deep graphs (long sums) overflow the stack.
+    fn topological_order(&self) -> Vec<Rc<RefCell<ValueInner>>> {
+        let mut order = Vec::new();
+        let mut visited = Set::new();
+        let mut stack = vec![(self.inner.clone(), false)];
+
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                order.push(node);
+                continue;
+            }
+
+            if !visited.insert(Rc::as_ptr(&node)) {
+                continue;
+            }
+
+            let children = node.borrow().children();
+
+            stack.push((node, true));
+            stack.extend(children.into_iter().map(|child| (child, false)));
+        }
+
+        order
+    }
+
+    /// Every node of the graph of this value, operands before the results
+    /// computed from them and this value last, e.g. to inspect metadata
+    pub fn nodes(&self) -> Vec<Value> {
+        self.topological_order()
+            .into_iter()
+            .map(|inner| Value { inner })
+            .collect()
+    }
+
+    /// Renames this node, e.g. to keep the labels of values computed from
+    /// large expressions short, since every label spells out its operands
+    pub fn set_label(&self, label: &str) {
+        self.inner.borrow_mut().label = label.to_string();
+    }
+
+    pub fn set_value(&self, value: f64) {
+        self.inner.borrow_mut().value = value;
+    }
+
+    pub fn set_gradient(&self, gradient: f64) {
+        self.inner.borrow_mut().gradient = gradient;
     }
 
-    pub fn nudge(&self, rate: f64) {
-        let grad = self.inner.borrow().gradient;
-        let mut inner = self.inner.borrow_mut();
+    pub fn zero_gradient(&self) {
+        self.inner.borrow_mut().gradient = 0.0;
+    }
+}
 
-        inner.value -= rate * grad;
-        inner.gradient = 0.0; // reset gradient to avoid accumulating in subsequent backpropagations
+// An Rc allocation holds the strong and weak counts next to the value
+fn node_estimate(node: &ValueInner) -> MemoryEstimate {
+    let operands = match &node.operation {
+        Operation::Dot(lhs, rhs) => (lhs.capacity() + rhs.capacity()) * mem::size_of::<usize>(),
+        Operation::Checkpoint(inputs, _) => inputs.capacity() * mem::size_of::<usize>(),
+        _ => 0,
+    };
+
+    MemoryEstimate {
+        nodes: 1,
+        bytes: 2 * mem::size_of::<usize>()
+            + mem::size_of::<RefCell<ValueInner>>()
+            + node.label.capacity()
+            + node.metadata.capacity() * mem::size_of::<(String, String)>()
+            + node
+                .metadata
+                .iter()
+                .map(|(key, value)| key.capacity() + value.capacity())
+                .sum::<usize>()
+            + operands,
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::unlabeled(value)
     }
 }
 
@@ -133,18 +551,16 @@ impl Mul for Value {
     type Output = Value;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        Value {
-            inner: Rc::new(RefCell::new(ValueInner {
-                value: self.inner.borrow().value * rhs.inner.borrow().value,
-                label: format!(
-                    "({} * {})",
-                    self.inner.borrow().label,
-                    rhs.inner.borrow().label
-                ),
-                gradient: 0.0,
-                operation: Operation::Multiply(self.inner.clone(), rhs.inner.clone()),
-            })),
-        }
+        let label = format!(
+            "({} * {})",
+            self.inner.borrow().label,
+            rhs.inner.borrow().label
+        );
+
+        Value::from_operation(
+            label,
+            Operation::Multiply(self.inner.clone(), rhs.inner.clone()),
+        )
     }
 }
 
@@ -152,18 +568,13 @@ impl Add for Value {
     type Output = Value;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Value {
-            inner: Rc::new(RefCell::new(ValueInner {
-                value: self.inner.borrow().value + rhs.inner.borrow().value,
-                label: format!(
-                    "({} + {})",
-                    self.inner.borrow().label,
-                    rhs.inner.borrow().label
-                ),
-                gradient: 0.0,
-                operation: Operation::Add(self.inner.clone(), rhs.inner.clone()),
-            })),
-        }
+        let label = format!(
+            "({} + {})",
+            self.inner.borrow().label,
+            rhs.inner.borrow().label
+        );
+
+        Value::from_operation(label, Operation::Add(self.inner.clone(), rhs.inner.clone()))
     }
 }
 
@@ -171,18 +582,13 @@ impl Sub for Value {
     type Output = Value;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Value {
-            inner: Rc::new(RefCell::new(ValueInner {
-                value: self.inner.borrow().value - rhs.inner.borrow().value,
-                label: format!(
-                    "({} - {})",
-                    self.inner.borrow().label,
-                    rhs.inner.borrow().label
-                ),
-                gradient: 0.0,
-                operation: Operation::Sub(self.inner.clone(), rhs.inner.clone()),
-            })),
-        }
+        let label = format!(
+            "({} - {})",
+            self.inner.borrow().label,
+            rhs.inner.borrow().label
+        );
+
+        Value::from_operation(label, Operation::Sub(self.inner.clone(), rhs.inner.clone()))
     }
 }
 
@@ -206,6 +612,71 @@ mod tests {
         assert_eq!("c", c.label());
     }
 
+    #[test]
+    fn unlabeled() {
+        let a = Value::unlabeled(2.0);
+        let b = Value::from(3.0);
+
+        assert_eq!(a.value(), 2.0);
+        assert!(a.label().starts_with('v'));
+        assert_ne!(a.label(), b.label());
+        assert_eq!(
+            (a.clone() * b.clone()).label(),
+            format!("({} * {})", a.label(), b.label())
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let x = Value::new(2.0, "x").with_metadata("unit", "m");
+        let w = Value::new(3.0, "w").with_metadata("layer", "0");
+        let y = x.clone() * w;
+
+        x.set_metadata("unit", "cm");
+        x.set_metadata("sample", "7");
+
+        assert_eq!(x.metadata("unit").as_deref(), Some("cm"));
+        assert_eq!(x.metadata("layer"), None);
+        assert_eq!(
+            x.metadata_entries(),
+            [
+                ("unit".to_string(), "cm".to_string()),
+                ("sample".to_string(), "7".to_string())
+            ]
+        );
+
+        let layers: Vec<_> = y
+            .nodes()
+            .iter()
+            .filter_map(|node| node.metadata("layer"))
+            .collect();
+        assert_eq!(layers, ["0"]);
+        assert_eq!(y.nodes().last().map(Value::label), Some(y.label()));
+    }
+
+    #[test]
+    fn set_label() {
+        let a = Value::new(2.0, "a");
+        let b = a.clone() * a.clone();
+        b.set_label("b");
+
+        assert_eq!((b + a).label(), "(b + a)");
+    }
+
+    #[test]
+    fn detach() {
+        let a = Value::new(2.0, "a");
+        let b = a.clone() * a.clone();
+        let c = b.detach() * a.clone();
+        c.backpropagate();
+
+        assert_eq!(c.value(), 8.0);
+        assert_eq!(c.label(), "((a * a) * a)");
+        // only the direct use of `a` contributes
+        assert_eq!(a.gradient(), 4.0);
+        assert_eq!(b.gradient(), 0.0);
+    }
+
     #[test]
     fn add() {
         let a = Value::new(3.0, "a");
@@ -247,6 +718,60 @@ mod tests {
         assert_eq!(o.value(), 0.707106777676776);
     }
 
+    #[test]
+    fn lazy_evaluation() {
+        let a = Value::new(2.0, "a");
+        let b = Value::new(-3.0, "b");
+
+        let c = super::lazy(|| (a.clone() * b.clone() + a.clone()).tanh());
+
+        assert!(c.value().is_nan());
+        assert_eq!(c.evaluate(), (2.0f64 * -3.0 + 2.0).tanh());
+
+        let eager = (a.clone() * b.clone() + a.clone()).tanh();
+        assert_eq!(eager.value(), c.value());
+
+        a.set_value(0.5);
+        assert_eq!(c.evaluate(), (0.5f64 * -3.0 + 0.5).tanh());
+
+        c.backpropagate();
+        assert!((b.gradient() - (1.0 - c.value().powi(2)) * 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dot() {
+        let a = Value::new(2.0, "a");
+        let b = Value::new(-3.0, "b");
+        let x = Value::new(0.5, "x");
+        let y = Value::new(4.0, "y");
+
+        let d = Value::dot(&[a.clone(), b.clone()], &[x.clone(), y.clone()]);
+
+        assert_eq!(d.value(), -11.0);
+        assert_eq!(d.label(), "(a * x + b * y)");
+
+        d.backpropagate();
+
+        assert_eq!(a.gradient(), 0.5);
+        assert_eq!(b.gradient(), 4.0);
+        assert_eq!(x.gradient(), 2.0);
+        assert_eq!(y.gradient(), -3.0);
+    }
+
+    #[test]
+    fn memory_estimate() {
+        let a = Value::new(2.0, "a");
+        let b = a.clone() * a.clone();
+        let c = b.clone() + b;
+
+        let single = a.memory_estimate();
+        let estimate = c.memory_estimate();
+
+        assert_eq!(single.nodes, 1);
+        assert_eq!(estimate.nodes, 3);
+        assert!(estimate.bytes > 3 * single.bytes);
+    }
+
     #[test]
     fn backpropagation() {
         let a = Value::new(-2.0, "a");
@@ -291,4 +816,58 @@ mod tests {
         assert_eq!(a.gradient(), d.gradient());
         assert_eq!(b.gradient(), e.gradient());
     }
+
+    #[test]
+    fn backpropagation_shared_node() {
+        let a = Value::new(3.0, "a");
+        let b = a.clone() * Value::new(2.0, "2");
+        let c = b.clone() + b.clone();
+        let d = a.clone() * a.clone();
+
+        c.backpropagate();
+        d.backpropagate();
+
+        assert_eq!(b.gradient(), 2.0);
+        assert_eq!(a.gradient(), 4.0 + 6.0);
+    }
+
+    #[test]
+    fn backpropagation_exp_ln() {
+        let a = Value::new(2.0, "a");
+        let b = a.clone().exp();
+
+        let c = Value::new(4.0, "c");
+        let d = c.clone().ln();
+
+        b.backpropagate();
+        d.backpropagate();
+
+        assert_eq!(b.value(), 2.0f64.exp());
+        assert_eq!(a.gradient(), 2.0f64.exp());
+        assert_eq!(d.value(), 4.0f64.ln());
+        assert_eq!(c.gradient(), 0.25);
+    }
+
+    #[test]
+    fn backpropagation_sigmoid() {
+        let a = Value::new(0.0, "a");
+        let b = a.clone().sigmoid();
+
+        b.backpropagate();
+
+        assert_eq!(b.value(), 0.5);
+        assert_eq!(a.gradient(), 0.25);
+    }
+
+    #[test]
+    fn backpropagation_fake_quantize() {
+        let a = Value::new(0.26, "a");
+        let b = a.clone().fake_quantize(0.1) * Value::new(2.0, "c");
+
+        b.backpropagate();
+
+        assert!((b.value() - 0.6).abs() < 1e-12);
+        assert_eq!(a.gradient(), 2.0);
+        assert!((Value::new(100.0, "d").fake_quantize(0.1).value() - 12.7).abs() < 1e-12);
+    }
 }
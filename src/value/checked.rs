@@ -0,0 +1,198 @@
+use alloc::{format, string::String};
+
+use super::{is_lazy, Error, Operation, Result, Value};
+use crate::math;
+
+impl Value {
+    /// Like [`Value::ln`], but fails unless the operand is positive or NaN
+    pub fn try_ln(self) -> Result<Value> {
+        let label = format!("ln({})", self.inner.borrow().label);
+        check(
+            &label,
+            &[&self],
+            self.value() > 0.0 || self.value().is_nan(),
+            "expected a positive operand",
+        )?;
+
+        Ok(Value::from_operation(
+            label,
+            Operation::Ln(self.inner.clone()),
+        ))
+    }
+
+    /// Square root, failing for negative operands
+    pub fn try_sqrt(self) -> Result<Value> {
+        let label = format!("sqrt({})", self.inner.borrow().label);
+        check(
+            &label,
+            &[&self],
+            self.value() >= 0.0 || self.value().is_nan(),
+            "expected a non-negative operand",
+        )?;
+
+        Ok(Value::from_operation(
+            label,
+            Operation::Pow(self.inner.clone(), 0.5),
+        ))
+    }
+
+    /// Like [`Value::pow`], but fails for fractional exponents of negative
+    /// operands and negative exponents of zero. NaN operands and exponents
+    /// give NaN without failing.
+    pub fn try_pow(self, exponent: f64) -> Result<Value> {
+        let label = format!("{}^{}", self.inner.borrow().label, exponent);
+        let value = self.value();
+
+        if exponent.is_nan() {
+            // the result is NaN (or 1 for an operand of 1) either way
+        } else if math::round(exponent) != exponent {
+            check(
+                &label,
+                &[&self],
+                value >= 0.0 || value.is_nan(),
+                "expected a non-negative operand for a fractional exponent",
+            )?;
+        } else {
+            check(
+                &label,
+                &[&self],
+                value != 0.0 || exponent >= 0.0,
+                "expected a non-zero operand for a negative exponent",
+            )?;
+        }
+
+        Ok(Value::from_operation(
+            label,
+            Operation::Pow(self.inner.clone(), exponent),
+        ))
+    }
+
+    /// Like [`Value::dot`], but fails for operands of different lengths
+    pub fn try_dot(lhs: &[Value], rhs: &[Value]) -> Result<Value> {
+        if lhs.len() != rhs.len() {
+            return Err(Error::LengthMismatch(lhs.len(), rhs.len()));
+        }
+
+        Ok(Value::dot(lhs, rhs))
+    }
+
+    /// Division, failing for a zero divisor, even of a NaN dividend
+    pub fn try_div(self, rhs: Value) -> Result<Value> {
+        let label = format!(
+            "({} / {})",
+            self.inner.borrow().label,
+            rhs.inner.borrow().label
+        );
+        check(
+            &label,
+            &[&self, &rhs],
+            rhs.value() != 0.0,
+            "expected a non-zero divisor",
+        )?;
+
+        let reciprocal = rhs.pow(-1.0);
+
+        Ok(Value::from_operation(
+            label,
+            Operation::Multiply(self.inner.clone(), reciprocal.inner.clone()),
+        ))
+    }
+}
+
+// Lazily built operands have no values to check yet
+fn check(node: &str, operands: &[&Value], valid: bool, reason: &'static str) -> Result<()> {
+    if valid || is_lazy() {
+        return Ok(());
+    }
+
+    Err(Error::Domain {
+        node: String::from(node),
+        operands: operands
+            .iter()
+            .map(|operand| (operand.label(), operand.value()))
+            .collect(),
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::{lazy, Error, Value};
+
+    #[test]
+    fn checked_operations() {
+        let x = Value::new(4.0, "x");
+        let y = Value::new(0.0, "y");
+
+        let root = x.clone().try_sqrt().expect("should take the root");
+        assert_eq!(root.value(), 2.0);
+        assert_eq!(root.label(), "sqrt(x)");
+
+        let quotient = Value::new(1.0, "one")
+            .try_div(x.clone())
+            .expect("should divide");
+        quotient.backpropagate();
+        assert_eq!(quotient.value(), 0.25);
+        assert_eq!(quotient.label(), "(one / x)");
+        assert_eq!(x.gradient(), -1.0 / 16.0);
+
+        let error = x.clone().try_div(y.clone()).expect_err("should fail");
+        assert_eq!(
+            error,
+            Error::Domain {
+                node: "(x / y)".to_string(),
+                operands: vec![("x".to_string(), 4.0), ("y".to_string(), 0.0)],
+                reason: "expected a non-zero divisor",
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "(x / y) is undefined for x = 4, y = 0, expected a non-zero divisor"
+        );
+
+        assert_eq!(
+            Value::try_dot(&[Value::new(1.0, "z")], &[]).expect_err("should fail"),
+            Error::LengthMismatch(1, 0)
+        );
+        assert!(y.clone().try_ln().is_err());
+        assert!((-x.clone()).try_sqrt().is_err());
+        assert!((-x.clone()).try_pow(0.5).is_err());
+        assert!(y.clone().try_pow(-1.0).is_err());
+        assert_eq!(
+            (-x.clone()).try_pow(2.0).expect("should square").value(),
+            16.0
+        );
+
+        // NaN operands give NaN, unless the other operand is invalid anyway
+        let nan = Value::new(f64::NAN, "nan");
+        assert!(nan.clone().try_ln().expect("should log").value().is_nan());
+        assert!(nan
+            .clone()
+            .try_sqrt()
+            .expect("should root")
+            .value()
+            .is_nan());
+        assert!(nan
+            .clone()
+            .try_pow(-0.5)
+            .expect("should raise")
+            .value()
+            .is_nan());
+        assert!((-x.clone())
+            .try_pow(f64::NAN)
+            .expect("should raise")
+            .value()
+            .is_nan());
+        assert!(nan.clone().try_div(y.clone()).is_err());
+        assert!(x
+            .clone()
+            .try_div(nan)
+            .expect("should divide")
+            .value()
+            .is_nan());
+
+        // nothing to check before evaluation
+        let deferred = lazy(|| (x.clone() - x.clone()).try_ln());
+        assert!(deferred.expect("should defer").evaluate().is_infinite());
+    }
+}
@@ -0,0 +1,427 @@
+use alloc::{format, rc::Rc, string::String, vec, vec::Vec};
+use core::cell::RefCell;
+
+use super::{Map, Operation, Value, ValueInner};
+
+// Nodes in topological order, ids being their positions, and the edges from
+// every operand to the node computed from it
+pub(super) struct Graph {
+    pub(super) nodes: Vec<Rc<RefCell<ValueInner>>>,
+    pub(super) edges: Vec<(usize, usize)>,
+}
+
+impl Graph {
+    pub(super) fn new(output: &Value) -> Self {
+        let nodes = output.topological_order();
+        let ids: Map<_, _> = nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (Rc::as_ptr(node), id))
+            .collect();
+
+        let edges = nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(target, node)| {
+                node.borrow()
+                    .children()
+                    .iter()
+                    .map(|child| (ids[&Rc::as_ptr(child)], target))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+}
+
+impl Operation {
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Operation::Constant => "constant",
+            Operation::Add(..) => "add",
+            Operation::Sub(..) => "sub",
+            Operation::Pow(..) => "pow",
+            Operation::Multiply(..) => "mul",
+            Operation::Tanh(_) => "tanh",
+            Operation::Exp(_) => "exp",
+            Operation::Ln(_) => "ln",
+            Operation::Sigmoid(_) => "sigmoid",
+            Operation::FakeQuantize(..) => "fake_quantize",
+            Operation::Dot(..) => "dot",
+            Operation::Checkpoint(..) => "checkpoint",
+        }
+    }
+}
+
+impl Value {
+    /// The graph of this value as JSON, for rendering and analysis in other
+    /// tools:
+    ///
+    /// ```json
+    /// {
+    ///   "nodes": [{ "id": 0, "label": "a", "value": 2.0, "grad": 1.0, "op": "constant" }, ...],
+    ///   "edges": [{ "source": 0, "target": 2 }, ...]
+    /// }
+    /// ```
+    ///
+    /// Nodes are in topological order, edges point from operands to results.
+    /// Non-finite values and gradients are `null`. Nodes with metadata have
+    /// it as an object of strings under `"metadata"`.
+    pub fn to_json_graph(&self) -> String {
+        let graph = Graph::new(self);
+
+        let nodes: Vec<_> = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| {
+                let node = node.borrow();
+
+                let metadata = if node.metadata.is_empty() {
+                    String::new()
+                } else {
+                    let entries: Vec<_> = node
+                        .metadata
+                        .iter()
+                        .map(|(key, value)| {
+                            format!("\"{}\": \"{}\"", json_escape(key), json_escape(value))
+                        })
+                        .collect();
+
+                    format!(", \"metadata\": {{ {} }}", entries.join(", "))
+                };
+
+                format!(
+                    "    {{ \"id\": {id}, \"label\": \"{}\", \"value\": {}, \"grad\": {}, \"op\": \"{}\"{metadata} }}",
+                    json_escape(&node.label),
+                    json_number(node.value),
+                    json_number(node.gradient),
+                    node.operation.name()
+                )
+            })
+            .collect();
+
+        let edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|(source, target)| format!("    {{ \"source\": {source}, \"target\": {target} }}"))
+            .collect();
+
+        format!(
+            "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+            nodes.join(",\n"),
+            edges.join(",\n")
+        )
+    }
+
+    /// The graph of this value as GraphML, e.g. for Gephi, with the same nodes
+    /// and edges as [`Value::to_json_graph`]. Every metadata key becomes a
+    /// string attribute.
+    pub fn to_graphml(&self) -> String {
+        let graph = Graph::new(self);
+
+        let mut keys: Vec<String> = Vec::new();
+        for node in &graph.nodes {
+            for (key, _) in &node.borrow().metadata {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+
+        let mut graphml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="value" for="node" attr.name="value" attr.type="double"/>
+  <key id="grad" for="node" attr.name="grad" attr.type="double"/>
+  <key id="op" for="node" attr.name="op" attr.type="string"/>
+"#,
+        );
+
+        for (index, key) in keys.iter().enumerate() {
+            graphml.push_str(&format!(
+                "  <key id=\"meta{index}\" for=\"node\" attr.name=\"{}\" attr.type=\"string\"/>\n",
+                xml_escape(key)
+            ));
+        }
+        graphml.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+
+        for (id, node) in graph.nodes.iter().enumerate() {
+            let node = node.borrow();
+
+            graphml.push_str(&format!(
+                "    <node id=\"n{id}\">\n      <data key=\"label\">{}</data>\n      <data key=\"value\">{}</data>\n      <data key=\"grad\">{}</data>\n      <data key=\"op\">{}</data>\n",
+                xml_escape(&node.label),
+                xml_double(node.value),
+                xml_double(node.gradient),
+                node.operation.name()
+            ));
+            for (key, value) in &node.metadata {
+                let index = keys.iter().position(|k| k == key).unwrap_or_default();
+
+                graphml.push_str(&format!(
+                    "      <data key=\"meta{index}\">{}</data>\n",
+                    xml_escape(value)
+                ));
+            }
+            graphml.push_str("    </node>\n");
+        }
+
+        for (source, target) in &graph.edges {
+            graphml.push_str(&format!(
+                "    <edge source=\"n{source}\" target=\"n{target}\"/>\n"
+            ));
+        }
+
+        graphml.push_str("  </graph>\n</graphml>\n");
+
+        graphml
+    }
+
+    /// The graph of this value as a Mermaid flowchart, which renders in
+    /// GitHub and most Markdown tools.
+    ///
+    /// Like in micrograd's `draw_dot`, every value is a box with its label,
+    /// value and gradient, fed by a circle of the operation computing it.
+    pub fn to_mermaid(&self) -> String {
+        let graph = Graph::new(self);
+        let mut mermaid = String::from("flowchart LR\n");
+
+        for (id, node) in graph.nodes.iter().enumerate() {
+            let node = node.borrow();
+
+            mermaid.push_str(&format!(
+                "    n{id}[\"{} | value {:.4} | grad {:.4}\"]\n",
+                mermaid_escape(&node.label),
+                node.value,
+                node.gradient
+            ));
+
+            if !matches!(node.operation, Operation::Constant) {
+                mermaid.push_str(&format!(
+                    "    n{id}_op((\"{}\")) --> n{id}\n",
+                    node.operation.name()
+                ));
+            }
+        }
+
+        for (source, target) in &graph.edges {
+            mermaid.push_str(&format!("    n{source} --> n{target}_op\n"));
+        }
+
+        mermaid
+    }
+
+    /// The graph of this value as an indented tree, with an operation or label,
+    /// the value and the gradient of every node:
+    ///
+    /// ```text
+    /// add = -4.0000 (grad 1.0000)
+    /// ├── mul = -6.0000 (grad 1.0000)
+    /// │   ├── a = 2.0000 (grad -2.0000) [#1]
+    /// │   └── b = -3.0000 (grad 2.0000)
+    /// └── a [#1, see above]
+    /// ```
+    ///
+    /// Nodes used by more than one operation are numbered and only expanded
+    /// where they first appear.
+    pub fn pretty_print(&self) -> String {
+        let graph = Graph::new(self);
+
+        // edges are ordered like the operands of every node
+        let mut operands = vec![Vec::new(); graph.nodes.len()];
+        let mut uses = vec![0; graph.nodes.len()];
+        for &(source, target) in &graph.edges {
+            operands[target].push(source);
+            uses[source] += 1;
+        }
+
+        let mut markers = vec![None; graph.nodes.len()];
+        let mut shared = 0;
+        let mut tree = String::new();
+
+        // iterative, because deep graphs overflow the stack
+        let mut stack = vec![(graph.nodes.len() - 1, String::new(), String::new())];
+        while let Some((id, prefix, indent)) = stack.pop() {
+            let node = graph.nodes[id].borrow();
+            let name = match node.operation {
+                Operation::Constant => node.label.clone(),
+                Operation::Pow(_, exponent) => format!("pow {exponent}"),
+                _ => String::from(node.operation.name()),
+            };
+
+            if let Some(marker) = markers[id] {
+                tree.push_str(&format!("{prefix}{name} [#{marker}, see above]\n"));
+                continue;
+            }
+
+            tree.push_str(&format!(
+                "{prefix}{name} = {:.4} (grad {:.4})",
+                node.value, node.gradient
+            ));
+            if uses[id] > 1 {
+                shared += 1;
+                markers[id] = Some(shared);
+                tree.push_str(&format!(" [#{shared}]"));
+            }
+            tree.push('\n');
+
+            let last = operands[id].len().saturating_sub(1);
+            for (index, &operand) in operands[id].iter().enumerate().rev() {
+                let (branch, continuation) = if index == last {
+                    ("└── ", "    ")
+                } else {
+                    ("├── ", "│   ")
+                };
+
+                stack.push((
+                    operand,
+                    format!("{indent}{branch}"),
+                    format!("{indent}{continuation}"),
+                ));
+            }
+        }
+
+        tree
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+// Debug formatting round-trips and is valid JSON for finite numbers
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:?}")
+    } else {
+        String::from("null")
+    }
+}
+
+pub(super) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Quotes end Mermaid labels and angle brackets are read as HTML
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+fn xml_double(value: f64) -> String {
+    match value {
+        v if v.is_nan() => String::from("NaN"),
+        v if v == f64::INFINITY => String::from("INF"),
+        v if v == f64::NEG_INFINITY => String::from("-INF"),
+        v => format!("{v:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::{lazy, Value};
+
+    #[test]
+    fn json_graph() {
+        let a = Value::new(2.0, "a").with_metadata("unit", "m");
+        let b = Value::new(-3.0, "\"b\"");
+        let c = a.clone() * b + a;
+        c.backpropagate();
+
+        assert_eq!(
+            c.to_json_graph(),
+            r#"{
+  "nodes": [
+    { "id": 0, "label": "a", "value": 2.0, "grad": -2.0, "op": "constant", "metadata": { "unit": "m" } },
+    { "id": 1, "label": "\"b\"", "value": -3.0, "grad": 2.0, "op": "constant" },
+    { "id": 2, "label": "(a * \"b\")", "value": -6.0, "grad": 1.0, "op": "mul" },
+    { "id": 3, "label": "((a * \"b\") + a)", "value": -4.0, "grad": 1.0, "op": "add" }
+  ],
+  "edges": [
+    { "source": 0, "target": 2 },
+    { "source": 1, "target": 2 },
+    { "source": 2, "target": 3 },
+    { "source": 0, "target": 3 }
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn graphml() {
+        let x = Value::new(0.5, "x<1").with_metadata("sample", "<7>");
+        let y = lazy(|| x.clone().exp().ln());
+
+        let graphml = y.to_graphml();
+
+        assert_eq!(graphml.matches("<node ").count(), 3);
+        assert_eq!(graphml.matches("<edge ").count(), 2);
+        assert!(graphml.contains("<data key=\"label\">x&lt;1</data>"));
+        assert!(graphml.contains("<data key=\"value\">NaN</data>"));
+        assert!(graphml.contains("<edge source=\"n1\" target=\"n2\"/>"));
+        assert!(graphml.contains(
+            "<key id=\"meta0\" for=\"node\" attr.name=\"sample\" attr.type=\"string\"/>"
+        ));
+        assert!(graphml.contains("<data key=\"meta0\">&lt;7&gt;</data>"));
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+
+    #[test]
+    fn pretty_print() {
+        let a = Value::new(2.0, "a");
+        let b = Value::new(-3.0, "b");
+        let c = (a.clone() * b + a).pow(2.0);
+        c.backpropagate();
+
+        assert_eq!(
+            c.pretty_print(),
+            "pow 2 = 16.0000 (grad 1.0000)
+└── add = -4.0000 (grad -8.0000)
+    ├── mul = -6.0000 (grad -8.0000)
+    │   ├── a = 2.0000 (grad 16.0000) [#1]
+    │   └── b = -3.0000 (grad -16.0000)
+    └── a [#1, see above]
+"
+        );
+    }
+
+    #[test]
+    fn mermaid() {
+        let a = Value::new(2.0, "a");
+        let b = Value::new(-3.0, "\"b\"");
+        let c = a * b;
+        c.backpropagate();
+
+        assert_eq!(
+            c.to_mermaid(),
+            r##"flowchart LR
+    n0["#quot;b#quot; | value -3.0000 | grad 2.0000"]
+    n1["a | value 2.0000 | grad -3.0000"]
+    n2["(a * #quot;b#quot;) | value -6.0000 | grad 1.0000"]
+    n2_op(("mul")) --> n2
+    n1 --> n2_op
+    n0 --> n2_op
+"##
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::{format, rc::Rc, vec::Vec};
+use core::fmt;
+
+use super::{set_lazy, Operation, Value};
+
+type Subgraph = dyn Fn(&[Value]) -> Value;
+
+// Builds a checkpointed subgraph from copies of its inputs
+#[derive(Clone)]
+pub(super) struct Function(Rc<Subgraph>);
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Function")
+    }
+}
+
+impl Function {
+    // Builds the subgraph for `inputs` eagerly, even within `lazy`, since
+    // only its output is kept
+    fn build(&self, inputs: &[f64]) -> (Vec<Value>, Value) {
+        struct Reset(bool);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                set_lazy(self.0);
+            }
+        }
+
+        let _reset = Reset(set_lazy(false));
+
+        let leaves: Vec<_> = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| Value::new(*x, &format!("x_{i}")))
+            .collect();
+        let output = (self.0)(&leaves);
+
+        (leaves, output)
+    }
+
+    pub(super) fn forward(&self, inputs: &[f64]) -> f64 {
+        self.build(inputs).1.value()
+    }
+
+    // Rebuilds the subgraph and backpropagates `gradient` through it, which
+    // accumulates into captured values directly, and returns the gradients
+    // of the inputs
+    pub(super) fn backward(&self, inputs: &[f64], gradient: f64) -> Vec<f64> {
+        let (leaves, output) = self.build(inputs);
+
+        (output * Value::new(gradient, "gradient")).backpropagate();
+
+        leaves.iter().map(Value::gradient).collect()
+    }
+}
+
+impl Value {
+    /// Runs `function` on `inputs` keeping only its result in the graph, and
+    /// runs it again to backpropagate, trading compute for memory, e.g. for a
+    /// step of a long unrolled recurrent network.
+    ///
+    /// `function` gets copies of the inputs. Values it captures, e.g. weights,
+    /// get their gradients during every backpropagation through the result,
+    /// so they should be leaves, anything computed needs to be an input.
+    pub fn checkpoint(inputs: &[Value], function: impl Fn(&[Value]) -> Value + 'static) -> Value {
+        let label = inputs
+            .iter()
+            .map(|input| input.inner.borrow().label.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Value::from_operation(
+            format!("checkpoint({label})"),
+            Operation::Checkpoint(
+                inputs.iter().map(|input| input.inner.clone()).collect(),
+                Function(Rc::new(function)),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::{lazy, Value};
+
+    fn block(x: Value, w: &Value) -> Value {
+        (0..20).fold(x, |x, _| (x * w.clone()).tanh())
+    }
+
+    #[test]
+    fn checkpoint() {
+        let w = Value::new(0.9, "w");
+        let x = Value::new(0.5, "x");
+        let plain = block(x.clone() * x.clone(), &w);
+        plain.backpropagate();
+        let (w_gradient, x_gradient) = (w.gradient(), x.gradient());
+
+        w.zero_gradient();
+        x.zero_gradient();
+        let captured = w.clone();
+        let checkpointed = Value::checkpoint(&[x.clone() * x.clone()], move |inputs| {
+            block(inputs[0].clone(), &captured)
+        });
+        checkpointed.backpropagate();
+
+        assert_eq!(checkpointed.value(), plain.value());
+        assert_eq!(checkpointed.label(), "checkpoint((x * x))");
+        assert!((w.gradient() - w_gradient).abs() < 1e-12);
+        assert!((x.gradient() - x_gradient).abs() < 1e-12);
+        assert!(checkpointed.memory_estimate().nodes < plain.memory_estimate().nodes / 10);
+
+        let captured = w.clone();
+        let deferred = lazy(|| {
+            Value::checkpoint(std::slice::from_ref(&x), move |inputs| {
+                block(inputs[0].clone(), &captured)
+            })
+        });
+        assert!(deferred.value().is_nan());
+        assert_eq!(deferred.evaluate(), block(x.clone(), &w).value());
+
+        let mut tape = checkpointed.compile();
+        let value = tape.forward(&[0.5]).expect("should evaluate");
+        assert_eq!(value, plain.value());
+        tape.backward();
+        assert!((tape.gradients()[0] - x_gradient).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,145 @@
+use super::{
+    graph::{xml_escape, Graph},
+    Operation, Value,
+};
+
+const MARGIN: usize = 20;
+const NODE_WIDTH: usize = 180;
+const NODE_HEIGHT: usize = 40;
+const OP_RADIUS: usize = 16;
+// horizontal room for the operation circle between two columns
+const GAP: usize = 80;
+const ROW: usize = NODE_HEIGHT + 20;
+const COLUMN: usize = NODE_WIDTH + GAP;
+const LABEL_CHARS: usize = 24;
+
+impl Value {
+    /// Renders the graph of this value as an SVG image, without needing an
+    /// external Graphviz installation.
+    ///
+    /// Values are laid out left to right in columns by their depth from the
+    /// leaves, every one a box with its label, value and gradient, fed by a
+    /// circle of the operation computing it. Long labels are shortened.
+    pub fn to_svg(&self) -> String {
+        let graph = Graph::new(self);
+
+        // topological order puts children first, so their depths are known
+        let mut depths = vec![0; graph.nodes.len()];
+        for &(source, target) in &graph.edges {
+            depths[target] = depths[target].max(depths[source] + 1);
+        }
+
+        let mut rows = vec![0; depths.iter().max().map_or(0, |max| max + 1)];
+        let positions: Vec<_> = depths
+            .iter()
+            .map(|&depth| {
+                let row = rows[depth];
+                rows[depth] += 1;
+
+                (MARGIN + depth * COLUMN, MARGIN + row * ROW)
+            })
+            .collect();
+
+        let width = 2 * MARGIN + rows.len().saturating_sub(1) * COLUMN + NODE_WIDTH;
+        let height = 2 * MARGIN + rows.iter().max().unwrap_or(&0) * ROW - (ROW - NODE_HEIGHT);
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="monospace" font-size="11">
+  <defs>
+    <marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" orient="auto">
+      <path d="M 0 0 L 10 5 L 0 10 z"/>
+    </marker>
+  </defs>
+"#
+        );
+
+        for &(source, target) in &graph.edges {
+            let (x1, y1) = positions[source];
+            let (x2, y2) = positions[target];
+
+            svg.push_str(&format!(
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" marker-end=\"url(#arrow)\"/>\n",
+                x1 + NODE_WIDTH,
+                y1 + NODE_HEIGHT / 2,
+                x2 - GAP / 2 - OP_RADIUS,
+                y2 + NODE_HEIGHT / 2
+            ));
+        }
+
+        for (node, &(x, y)) in graph.nodes.iter().zip(&positions) {
+            let node = node.borrow();
+            let middle = y + NODE_HEIGHT / 2;
+
+            if !matches!(node.operation, Operation::Constant) {
+                let cx = x - GAP / 2;
+
+                svg.push_str(&format!(
+                    "  <circle cx=\"{cx}\" cy=\"{middle}\" r=\"{OP_RADIUS}\" fill=\"white\" stroke=\"black\"/>\n  <text x=\"{cx}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n  <line x1=\"{}\" y1=\"{middle}\" x2=\"{x}\" y2=\"{middle}\" stroke=\"black\" marker-end=\"url(#arrow)\"/>\n",
+                    middle + 4,
+                    node.operation.name(),
+                    cx + OP_RADIUS
+                ));
+            }
+
+            svg.push_str(&format!(
+                "  <rect x=\"{x}\" y=\"{y}\" width=\"{NODE_WIDTH}\" height=\"{NODE_HEIGHT}\" fill=\"white\" stroke=\"black\"/>\n  <text x=\"{}\" y=\"{}\">{}</text>\n  <text x=\"{}\" y=\"{}\">value {:.4} grad {:.4}</text>\n",
+                x + 6,
+                y + 16,
+                xml_escape(&shorten(&node.label)),
+                x + 6,
+                y + 32,
+                node.value,
+                node.gradient
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+
+    /// Displays the graph inline in an [evcxr](https://github.com/evcxr/evcxr)
+    /// Jupyter notebook, which calls this for the value of a cell
+    pub fn evcxr_display(&self) {
+        println!(
+            "EVCXR_BEGIN_CONTENT image/svg+xml\n{}\nEVCXR_END_CONTENT",
+            self.to_svg()
+        );
+    }
+}
+
+fn shorten(label: &str) -> String {
+    if label.chars().count() <= LABEL_CHARS {
+        label.to_string()
+    } else {
+        let mut short: String = label.chars().take(LABEL_CHARS - 1).collect();
+        short.push('…');
+
+        short
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::Value;
+
+    #[test]
+    fn svg() {
+        let a = Value::new(2.0, "a");
+        let b = Value::new(-3.0, "a_rather_long_label_for_b");
+        let c = (a.clone() * b + a).tanh();
+        c.backpropagate();
+
+        let svg = c.to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), 5);
+        assert_eq!(svg.matches("<circle ").count(), 3);
+        // an edge per operand and one from each operation to its result
+        assert_eq!(svg.matches("<line ").count(), 5 + 3);
+        assert!(svg.contains(">a_rather_long_label_for…</text>"));
+        assert!(svg.contains(">tanh</text>"));
+        assert!(svg.contains(&format!("value {:.4} grad 1.0000", c.value())));
+    }
+}
@@ -0,0 +1,291 @@
+use alloc::{rc::Rc, vec, vec::Vec};
+use core::cell::RefCell;
+
+use super::{recompute::Function, Error, Map, Operation, Result, Value, ValueInner};
+use crate::math;
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Leaf,
+    Add(usize, usize),
+    Sub(usize, usize),
+    Multiply(usize, usize),
+    Pow(usize, f64),
+    Tanh(usize),
+    Exp(usize),
+    Ln(usize),
+    Sigmoid(usize),
+    FakeQuantize(usize, f64),
+    // index into the operand pairs of the tape's dot products
+    Dot(usize),
+    // index into the tape's checkpointed subgraphs
+    Checkpoint(usize),
+}
+
+/// An expression graph lowered into a flat list of operations over a buffer
+/// of values, built by [`Value::compile`].
+///
+/// Every node of the graph gets a slot in the buffer, children before their
+/// parents and the compiled value last, so evaluating and differentiating the
+/// expression are walks over arrays. The leaves of the graph, e.g. inputs and
+/// parameters, are the tape's inputs.
+#[derive(Debug, Clone)]
+pub struct Tape {
+    ops: Vec<Op>,
+    values: Vec<f64>,
+    gradients: Vec<f64>,
+    leaves: Vec<Value>,
+    leaf_slots: Vec<usize>,
+    dots: Vec<Vec<(usize, usize)>>,
+    checkpoints: Vec<(Function, Vec<usize>)>,
+}
+
+impl Tape {
+    pub(super) fn compile(output: &Value) -> Self {
+        let order = output.topological_order();
+        let slots: Map<_, _> = order
+            .iter()
+            .enumerate()
+            .map(|(slot, node)| (Rc::as_ptr(node), slot))
+            .collect();
+        let slot = |node: &Rc<RefCell<ValueInner>>| slots[&Rc::as_ptr(node)];
+
+        let mut ops = Vec::with_capacity(order.len());
+        let mut leaves = Vec::new();
+        let mut leaf_slots = Vec::new();
+        let mut dots = Vec::new();
+        let mut checkpoints = Vec::new();
+
+        for (index, node) in order.iter().enumerate() {
+            let op = match &node.borrow().operation {
+                Operation::Constant => {
+                    leaves.push(Value {
+                        inner: node.clone(),
+                    });
+                    leaf_slots.push(index);
+
+                    Op::Leaf
+                }
+                Operation::Add(lhs, rhs) => Op::Add(slot(lhs), slot(rhs)),
+                Operation::Sub(lhs, rhs) => Op::Sub(slot(lhs), slot(rhs)),
+                Operation::Multiply(lhs, rhs) => Op::Multiply(slot(lhs), slot(rhs)),
+                Operation::Pow(it, exponent) => Op::Pow(slot(it), *exponent),
+                Operation::Tanh(it) => Op::Tanh(slot(it)),
+                Operation::Exp(it) => Op::Exp(slot(it)),
+                Operation::Ln(it) => Op::Ln(slot(it)),
+                Operation::Sigmoid(it) => Op::Sigmoid(slot(it)),
+                Operation::FakeQuantize(it, scale) => Op::FakeQuantize(slot(it), *scale),
+                Operation::Dot(lhs, rhs) => {
+                    dots.push(
+                        lhs.iter()
+                            .zip(rhs)
+                            .map(|(l, r)| (slot(l), slot(r)))
+                            .collect(),
+                    );
+
+                    Op::Dot(dots.len() - 1)
+                }
+                Operation::Checkpoint(inputs, function) => {
+                    checkpoints.push((function.clone(), inputs.iter().map(slot).collect()));
+
+                    Op::Checkpoint(checkpoints.len() - 1)
+                }
+            };
+
+            ops.push(op);
+        }
+
+        Self {
+            values: order.iter().map(|node| node.borrow().value).collect(),
+            gradients: vec![0.0; ops.len()],
+            ops,
+            leaves,
+            leaf_slots,
+            dots,
+            checkpoints,
+        }
+    }
+
+    /// The leaves of the graph, in the order `forward` expects their values
+    pub fn leaves(&self) -> &[Value] {
+        &self.leaves
+    }
+
+    /// Evaluates the expression with new values of the leaves, returning the
+    /// result, or an error unless there's a value for every leaf
+    pub fn forward(&mut self, inputs: &[f64]) -> Result<f64> {
+        if inputs.len() != self.leaf_slots.len() {
+            return Err(Error::LengthMismatch(self.leaf_slots.len(), inputs.len()));
+        }
+
+        for (&slot, &input) in self.leaf_slots.iter().zip(inputs) {
+            self.values[slot] = input;
+        }
+
+        for slot in 0..self.ops.len() {
+            let values = &self.values;
+
+            self.values[slot] = match self.ops[slot] {
+                Op::Leaf => continue,
+                Op::Add(lhs, rhs) => values[lhs] + values[rhs],
+                Op::Sub(lhs, rhs) => values[lhs] - values[rhs],
+                Op::Multiply(lhs, rhs) => values[lhs] * values[rhs],
+                Op::Pow(it, exponent) => math::powf(values[it], exponent),
+                Op::Tanh(it) => math::tanh(values[it]),
+                Op::Exp(it) => math::exp(values[it]),
+                Op::Ln(it) => math::ln(values[it]),
+                Op::Sigmoid(it) => 1.0 / (1.0 + math::exp(-values[it])),
+                Op::FakeQuantize(it, scale) => math::fake_quantize(values[it], scale),
+                Op::Dot(dot) => self.dots[dot]
+                    .iter()
+                    .map(|&(lhs, rhs)| values[lhs] * values[rhs])
+                    .sum(),
+                Op::Checkpoint(checkpoint) => {
+                    let (function, inputs) = &self.checkpoints[checkpoint];
+
+                    function.forward(
+                        &inputs
+                            .iter()
+                            .map(|&input| values[input])
+                            .collect::<Vec<_>>(),
+                    )
+                }
+            };
+        }
+
+        Ok(self.value())
+    }
+
+    /// Result of the last evaluation
+    pub fn value(&self) -> f64 {
+        self.values.last().copied().unwrap_or_default()
+    }
+
+    /// Computes the gradient of the result with respect to every node,
+    /// using the values of the last evaluation
+    pub fn backward(&mut self) {
+        self.gradients.fill(0.0);
+
+        if let Some(last) = self.gradients.last_mut() {
+            *last = 1.0;
+        }
+
+        for slot in (0..self.ops.len()).rev() {
+            let (value, gradient) = (self.values[slot], self.gradients[slot]);
+            let values = &self.values;
+            let gradients = &mut self.gradients;
+
+            match self.ops[slot] {
+                Op::Leaf => {}
+                Op::Add(lhs, rhs) => {
+                    gradients[lhs] += gradient;
+                    gradients[rhs] += gradient;
+                }
+                Op::Sub(lhs, rhs) => {
+                    gradients[lhs] += gradient;
+                    gradients[rhs] -= gradient;
+                }
+                Op::Multiply(lhs, rhs) => {
+                    gradients[lhs] += values[rhs] * gradient;
+                    gradients[rhs] += values[lhs] * gradient;
+                }
+                Op::Pow(it, exponent) => {
+                    gradients[it] += exponent * math::powf(values[it], exponent - 1.0) * gradient;
+                }
+                Op::Tanh(it) => gradients[it] += (1.0 - math::powf(value, 2.0)) * gradient,
+                Op::Exp(it) => gradients[it] += value * gradient,
+                Op::Ln(it) => gradients[it] += gradient / values[it],
+                Op::Sigmoid(it) => gradients[it] += value * (1.0 - value) * gradient,
+                Op::FakeQuantize(it, _) => gradients[it] += gradient,
+                Op::Dot(dot) => {
+                    for &(lhs, rhs) in &self.dots[dot] {
+                        gradients[lhs] += values[rhs] * gradient;
+                        gradients[rhs] += values[lhs] * gradient;
+                    }
+                }
+                Op::Checkpoint(checkpoint) => {
+                    let (function, inputs) = &self.checkpoints[checkpoint];
+                    let input_values: Vec<_> = inputs.iter().map(|&input| values[input]).collect();
+
+                    for (&input, input_gradient) in inputs
+                        .iter()
+                        .zip(function.backward(&input_values, gradient))
+                    {
+                        gradients[input] += input_gradient;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gradients of the result with respect to the leaves, in the order of
+    /// `leaves`, as of the last `backward`
+    pub fn gradients(&self) -> Vec<f64> {
+        self.leaf_slots
+            .iter()
+            .map(|&slot| self.gradients[slot])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::Value;
+
+    #[test]
+    fn matches_graph() {
+        let x = Value::new(0.5, "x");
+        let w = Value::new(-1.5, "w");
+        let b = Value::new(0.25, "b");
+
+        let y = ((x.clone() * w.clone() + b.clone()).tanh() - x.clone().exp()).pow(2.0)
+            + (w.clone().sigmoid() * b.clone()).ln()
+            + Value::dot(&[x.clone(), w.clone()], &[w.clone(), b.clone()])
+            + w.clone().fake_quantize(0.1) * x.clone();
+
+        let mut tape = y.compile();
+        assert_eq!(tape.value(), y.value());
+        assert_eq!(tape.leaves().len(), 3);
+
+        y.backpropagate();
+        tape.backward();
+
+        let expected: Vec<_> = tape.leaves().iter().map(Value::gradient).collect();
+        for (gradient, expected) in tape.gradients().iter().zip(expected) {
+            assert!((gradient - expected).abs() < 1e-12);
+        }
+
+        assert!(tape.forward(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn reevaluates() {
+        let a = Value::new(2.0, "a");
+        let b = Value::new(3.0, "b");
+        let c = (a.clone() * b.clone() + a.clone()).tanh();
+
+        let mut tape = c.compile();
+        let inputs: Vec<_> = tape
+            .leaves()
+            .iter()
+            .map(|leaf| if leaf.label() == "a" { 0.1 } else { -0.2 })
+            .collect();
+
+        let value = tape.forward(&inputs).expect("should evaluate");
+        assert!((value - (0.1f64 * -0.2 + 0.1).tanh()).abs() < 1e-12);
+
+        tape.backward();
+        let gradients = tape.gradients();
+        let local = 1.0 - value.powi(2);
+
+        for (leaf, gradient) in tape.leaves().iter().zip(gradients) {
+            let expected = if leaf.label() == "a" {
+                local * (-0.2 + 1.0)
+            } else {
+                local * 0.1
+            };
+
+            assert!((gradient - expected).abs() < 1e-12);
+        }
+    }
+}
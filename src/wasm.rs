@@ -0,0 +1,133 @@
+//! JavaScript bindings for `wasm32-unknown-unknown`, for training models in
+//! the browser. Built as a `cdylib` with `cargo rustc --crate-type cdylib
+//! --features wasm` and bound with `wasm-bindgen`, see the README.
+//!
+//! Batches of samples are passed as flat arrays of consecutive rows.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::{transform::Sample, InMemoryDataset},
+    loss::Loss,
+    nn::Mlp,
+    optim::Sgd,
+    train::{TrainConfig, Trainer},
+};
+
+/// A model trained by stochastic gradient descent on the squared error
+#[wasm_bindgen(js_name = Trainer)]
+pub struct WasmTrainer {
+    trainer: Trainer,
+    inputs: usize,
+    outputs: usize,
+}
+
+#[wasm_bindgen(js_class = Trainer)]
+impl WasmTrainer {
+    /// `layer_sizes` are the outputs of every layer, the last one being the
+    /// number of model outputs
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        inputs: usize,
+        layer_sizes: &[u32],
+        learning_rate: f64,
+        seed: u32,
+    ) -> Result<WasmTrainer, JsError> {
+        let sizes: Vec<_> = layer_sizes.iter().map(|&size| size as usize).collect();
+        let outputs = *sizes
+            .last()
+            .ok_or_else(|| JsError::new("expected at least one layer"))?;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed.into());
+        let mlp =
+            Mlp::try_new(inputs, &sizes, &mut rng).map_err(|e| JsError::new(&e.to_string()))?;
+        let sgd = Sgd::new(mlp.parameters(), learning_rate);
+        let trainer = Trainer::new(mlp, sgd, Loss::SquaredError).config(TrainConfig {
+            seed: Some(seed.into()),
+            ..Default::default()
+        });
+
+        Ok(Self {
+            trainer,
+            inputs,
+            outputs,
+        })
+    }
+
+    /// Takes a single optimizer step on a batch, returning its loss
+    #[wasm_bindgen(js_name = trainStep)]
+    pub fn train_step(&mut self, inputs: &[f64], targets: &[f64]) -> Result<f64, JsError> {
+        let samples = self.samples(inputs, targets)?;
+        let step = self.trainer.partial_fit(&samples)?;
+
+        Ok(step.loss.value())
+    }
+
+    /// Trains full batch epochs, returning the loss of every epoch
+    pub fn fit(
+        &mut self,
+        inputs: &[f64],
+        targets: &[f64],
+        epochs: usize,
+    ) -> Result<Vec<f64>, JsError> {
+        let data = InMemoryDataset::from(self.samples(inputs, targets)?);
+
+        Ok(self.trainer.fit(&data, epochs)?.loss)
+    }
+
+    /// Outputs of the model for a single sample
+    pub fn predict(&self, input: &[f64]) -> Result<Vec<f64>, JsError> {
+        Ok(self.trainer.model().infer(input)?)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> usize {
+        self.trainer.epoch()
+    }
+}
+
+impl WasmTrainer {
+    fn samples(&self, inputs: &[f64], targets: &[f64]) -> Result<Vec<Sample>, JsError> {
+        let count = inputs.len() / self.inputs.max(1);
+
+        if inputs.len() != count * self.inputs || targets.len() != count * self.outputs {
+            return Err(JsError::new(&format!(
+                "expected {count} rows of {} inputs and {} targets",
+                self.inputs, self.outputs
+            )));
+        }
+
+        Ok(inputs
+            .chunks(self.inputs.max(1))
+            .zip(targets.chunks(self.outputs))
+            .map(|(x, y)| (x.to_vec(), y.to_vec()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmTrainer;
+
+    // JsError can only be constructed on wasm, so only successful calls are
+    // exercised natively
+    #[test]
+    fn trains_flat_batches() {
+        let mut trainer = WasmTrainer::new(2, &[4, 1], 0.05, 1).expect("should create");
+
+        let inputs = [1.0, -1.0, -1.0, 1.0, 0.5, 0.5];
+        let targets = [1.0, -1.0, 0.0];
+
+        let first = trainer.train_step(&inputs, &targets).expect("should train");
+        let losses = trainer.fit(&inputs, &targets, 50).expect("should train");
+
+        assert!(losses[49] < first);
+        assert_eq!(trainer.epoch(), 50);
+        assert_eq!(
+            trainer.predict(&[1.0, -1.0]).expect("should predict").len(),
+            1
+        );
+    }
+}